@@ -0,0 +1,185 @@
+use alloc::vec::Vec;
+
+use crate::cpu::CPU;
+
+const INTERRUPT_VECTORS: [u16; 5] = [0x40, 0x48, 0x50, 0x58, 0x60];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FrameKind {
+    Call,
+    Rst,
+    Interrupt,
+}
+
+/// One entry on the shadow stack
+#[derive(Debug, Clone, Copy)]
+pub struct StackFrame {
+    pub kind: FrameKind,
+    /// Address of the CALL/RST, or of the instruction the interrupt cut in front of
+    pub call_site: u16,
+    /// Where the call went
+    pub target: u16,
+    pub return_address: u16,
+    /// SP right after the return address was pushed
+    pub stack_pointer: u16,
+}
+
+/// Something that doesn't add up with plain CALL/RET pairs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Imbalance {
+    /// A RET at `at` went to `to`, which isn't the return address on top of the shadow stack
+    ReturnMismatch { at: u16, to: u16 },
+    /// SP moved past `frames` frames without returning from them, e.g. LD SP or popping the return address
+    Discarded { at: u16, frames: usize },
+}
+
+/// Calls and interrupts as the CPU went through them, kept next to the real stack in memory.
+/// Games are free to do anything with SP, so this can only guess and says so when it has to.
+#[derive(Clone, Default)]
+pub struct CallStack {
+    /// Innermost call last
+    pub frames: Vec<StackFrame>,
+    pub last_imbalance: Option<Imbalance>,
+    pub imbalances: u32,
+}
+
+impl CallStack {
+    pub fn new() -> CallStack {
+        CallStack::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.last_imbalance = None;
+        self.imbalances = 0;
+    }
+
+    /// Call after every instruction with what PC, the opcode and SP were before it ran
+    pub fn update(&mut self, cpu: &CPU, pc: u16, opcode: u8, stack_pointer: u16) {
+        let new_sp = cpu.registry.sp;
+        let new_pc = cpu.registry.pc;
+        let pushed = new_sp == stack_pointer.wrapping_sub(2);
+        let popped = new_sp == stack_pointer.wrapping_add(2);
+
+        let kind = match opcode {
+            0xC4 | 0xCC | 0xCD | 0xD4 | 0xDC if pushed => Some(FrameKind::Call),
+            0xC7 | 0xCF | 0xD7 | 0xDF | 0xE7 | 0xEF | 0xF7 | 0xFF if pushed => Some(FrameKind::Rst),
+            // Dispatching pushes PC and jumps to a vector without any instruction asking for it
+            _ if pushed && INTERRUPT_VECTORS.contains(&new_pc) && cpu.memory.peek_word(new_sp) == pc => Some(FrameKind::Interrupt),
+            _ => None,
+        };
+        if let Some(kind) = kind {
+            self.frames.push(StackFrame {
+                kind,
+                call_site: pc,
+                target: new_pc,
+                return_address: cpu.memory.peek_word(new_sp),
+                stack_pointer: new_sp,
+            });
+            return;
+        }
+
+        if matches!(opcode, 0xC0 | 0xC8 | 0xC9 | 0xD0 | 0xD8 | 0xD9) && popped {
+            self.unwind(pc, stack_pointer);
+            match self.frames.last() {
+                Some(frame) if frame.stack_pointer == stack_pointer && frame.return_address == new_pc => {
+                    self.frames.pop();
+                }
+                _ => self.imbalance(Imbalance::ReturnMismatch { at: pc, to: new_pc }),
+            }
+            return;
+        }
+
+        self.unwind(pc, new_sp);
+    }
+
+    // Frames whose return address lies below SP have been thrown away one way or another
+    fn unwind(&mut self, pc: u16, stack_pointer: u16) {
+        let keep = self.frames.iter().take_while(|frame| frame.stack_pointer >= stack_pointer).count();
+        let frames = self.frames.len() - keep;
+        if frames > 0 {
+            self.frames.truncate(keep);
+            self.imbalance(Imbalance::Discarded { at: pc, frames });
+        }
+    }
+
+    fn imbalance(&mut self, imbalance: Imbalance) {
+        self.last_imbalance = Some(imbalance);
+        self.imbalances += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The machine as it is after an instruction at `pc` left SP at `sp` with `top` on the stack
+    fn after(pc: u16, sp: u16, top: u16) -> CPU {
+        let mut cpu = CPU::new();
+        cpu.memory.in_bootrom = false;
+        cpu.registry.pc = pc;
+        cpu.registry.sp = sp;
+        cpu.memory.write_word(sp, top);
+        cpu
+    }
+
+    #[test]
+    fn call_and_return() {
+        let mut stack = CallStack::new();
+        // CALL $4000 at 0x0150
+        stack.update(&after(0x4000, 0xFFFC, 0x0153), 0x0150, 0xCD, 0xFFFE);
+        assert_eq!(stack.frames.len(), 1);
+        let frame = stack.frames[0];
+        assert_eq!((frame.kind, frame.call_site, frame.target, frame.return_address), (FrameKind::Call, 0x0150, 0x4000, 0x0153));
+
+        // RET at 0x4010
+        stack.update(&after(0x0153, 0xFFFE, 0), 0x4010, 0xC9, 0xFFFC);
+        assert!(stack.frames.is_empty());
+        assert_eq!(stack.imbalances, 0);
+    }
+
+    #[test]
+    fn rst_and_interrupts() {
+        let mut stack = CallStack::new();
+        stack.update(&after(0x0038, 0xFFFC, 0x0201), 0x0200, 0xFF, 0xFFFE);
+        // VBlank dispatched in front of a NOP at 0x0038
+        stack.update(&after(0x0040, 0xFFFA, 0x0038), 0x0038, 0x00, 0xFFFC);
+        let kinds: Vec<FrameKind> = stack.frames.iter().map(|frame| frame.kind).collect();
+        assert_eq!(kinds, [FrameKind::Rst, FrameKind::Interrupt]);
+
+        // RETI
+        stack.update(&after(0x0038, 0xFFFC, 0x0201), 0x0045, 0xD9, 0xFFFA);
+        assert_eq!(stack.frames.len(), 1);
+    }
+
+    #[test]
+    fn a_conditional_call_that_isnt_taken_pushes_nothing() {
+        let mut stack = CallStack::new();
+        stack.update(&after(0x0153, 0xFFFE, 0), 0x0150, 0xC4, 0xFFFE);
+        assert!(stack.frames.is_empty());
+    }
+
+    #[test]
+    fn returning_somewhere_else_is_a_mismatch() {
+        let mut stack = CallStack::new();
+        stack.update(&after(0x4000, 0xFFFC, 0x0153), 0x0150, 0xCD, 0xFFFE);
+        // The return address was overwritten before the RET
+        stack.update(&after(0x0200, 0xFFFE, 0), 0x4010, 0xC9, 0xFFFC);
+        assert_eq!(stack.last_imbalance, Some(Imbalance::ReturnMismatch { at: 0x4010, to: 0x0200 }));
+        assert_eq!(stack.frames.len(), 1);
+    }
+
+    #[test]
+    fn moving_sp_past_frames_discards_them() {
+        let mut stack = CallStack::new();
+        stack.update(&after(0x4000, 0xFFFC, 0x0153), 0x0150, 0xCD, 0xFFFE);
+        stack.update(&after(0x5000, 0xFFFA, 0x4003), 0x4000, 0xCD, 0xFFFC);
+        // LD SP, $FFFE
+        stack.update(&after(0x5003, 0xFFFE, 0), 0x5000, 0x31, 0xFFFA);
+        assert!(stack.frames.is_empty());
+        assert_eq!(stack.last_imbalance, Some(Imbalance::Discarded { at: 0x5000, frames: 2 }));
+
+        stack.clear();
+        assert_eq!((stack.last_imbalance, stack.imbalances), (None, 0));
+    }
+}
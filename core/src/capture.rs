@@ -0,0 +1,321 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::cpu::{CLOCK_HZ, FRAME_T_CYCLES};
+use crate::palette::Palette;
+use crate::ppu::{SCREEN_HEIGHT, SCREEN_WIDTH};
+
+// Four colours fit in two bits, GIF's LZW wants at least that as its minimum code size
+const LZW_MIN_CODE_SIZE: u8 = 2;
+const CLEAR_CODE: u16 = 1 << LZW_MIN_CODE_SIZE;
+const END_CODE: u16 = CLEAR_CODE + 1;
+const MAX_CODES: u16 = 0x1000;
+// Browsers play anything shorter than 2/100 s at 1/10 s
+const MIN_DELAY_CENTISECONDS: u64 = 2;
+
+/// A frame from `ppu::render` blown up `scale` times in both directions
+pub fn scale_screen(screen: &[u8], scale: usize) -> Vec<u8> {
+    let width = SCREEN_WIDTH * scale;
+    let mut scaled = vec![0; width * SCREEN_HEIGHT * scale];
+    for (index, pixel) in scaled.iter_mut().enumerate() {
+        let (x, y) = (index % width / scale, index / width / scale);
+        *pixel = screen[y * SCREEN_WIDTH + x];
+    }
+    scaled
+}
+
+/// Writes frames into an animated GIF that plays at the Game Boy's speed. GIF delays are in
+/// hundredths of a second and 59.7 fps needs less than two of those, so frames that can't
+/// be shown long enough are dropped, the raw frames are better for stepping through.
+pub struct GifEncoder {
+    data: Vec<u8>,
+    scale: usize,
+    // The last frame, held until it's known how long it stays on screen
+    pending: Option<Vec<u8>>,
+    frames: u64,
+    written_centiseconds: u64,
+}
+
+impl GifEncoder {
+    pub fn new(scale: usize, palette: Palette) -> GifEncoder {
+        let mut data = Vec::new();
+        data.extend_from_slice(b"GIF89a");
+        data.extend_from_slice(&((SCREEN_WIDTH * scale) as u16).to_le_bytes());
+        data.extend_from_slice(&((SCREEN_HEIGHT * scale) as u16).to_le_bytes());
+        // Global colour table with 4 entries, 8 bits per primary colour
+        data.extend_from_slice(&[0xF1, 0, 0]);
+        for colour in palette {
+            data.extend_from_slice(&colour);
+        }
+        // Loop forever
+        data.extend_from_slice(&[0x21, 0xFF, 0x0B]);
+        data.extend_from_slice(b"NETSCAPE2.0");
+        data.extend_from_slice(&[0x03, 0x01, 0x00, 0x00, 0x00]);
+        GifEncoder { data, scale, pending: None, frames: 0, written_centiseconds: 0 }
+    }
+
+    /// Frames handed in so far, including dropped ones
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Adds one frame from `ppu::render`, call it once per emulated frame
+    pub fn add_frame(&mut self, screen: &[u8]) {
+        let started = centiseconds(self.frames);
+        self.frames += 1;
+        match &self.pending {
+            // Unchanged frames just keep the last one on screen longer
+            Some(pending) if pending == screen => (),
+            Some(_) if started - self.written_centiseconds < MIN_DELAY_CENTISECONDS => self.pending = Some(screen.to_vec()),
+            Some(_) => {
+                self.write_pending(started);
+                self.pending = Some(screen.to_vec());
+            }
+            None => self.pending = Some(screen.to_vec()),
+        }
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        let end = centiseconds(self.frames).max(self.written_centiseconds + MIN_DELAY_CENTISECONDS);
+        self.write_pending(end);
+        self.data.push(0x3B);
+        self.data
+    }
+
+    // Writes the held frame so that it stays on screen until `until`
+    fn write_pending(&mut self, until: u64) {
+        let Some(screen) = self.pending.take() else {
+            return;
+        };
+        let delay = (until - self.written_centiseconds).min(u16::MAX as u64) as u16;
+        self.written_centiseconds = until;
+
+        let (width, height) = ((SCREEN_WIDTH * self.scale) as u16, (SCREEN_HEIGHT * self.scale) as u16);
+        // Graphic control extension, the frame stays when the next one is drawn
+        self.data.extend_from_slice(&[0x21, 0xF9, 0x04, 0x04]);
+        self.data.extend_from_slice(&delay.to_le_bytes());
+        self.data.extend_from_slice(&[0x00, 0x00]);
+        // Image descriptor covering the whole screen, no local colour table
+        self.data.push(0x2C);
+        self.data.extend_from_slice(&[0, 0, 0, 0]);
+        self.data.extend_from_slice(&width.to_le_bytes());
+        self.data.extend_from_slice(&height.to_le_bytes());
+        self.data.push(0x00);
+
+        self.data.push(LZW_MIN_CODE_SIZE);
+        let compressed = lzw(&scale_screen(&screen, self.scale));
+        for block in compressed.chunks(255) {
+            self.data.push(block.len() as u8);
+            self.data.extend_from_slice(block);
+        }
+        self.data.push(0x00);
+    }
+}
+
+// When frame number `frame` starts, in hundredths of a second since the recording started
+fn centiseconds(frame: u64) -> u64 {
+    frame * FRAME_T_CYCLES * 100 / CLOCK_HZ
+}
+
+/// Packs codes of varying width into bytes, least significant bit first
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u8) {
+        self.buffer |= (code as u32) << self.bits;
+        self.bits += size;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+// GIF flavoured LZW over 2 bit pixels. Every code has a child per colour, 0 meaning none yet,
+// the table starts over with a clear code once all 4096 codes are taken.
+fn lzw(pixels: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter { bytes: Vec::new(), buffer: 0, bits: 0 };
+    let mut children = vec![[0u16; 4]; MAX_CODES as usize];
+    let mut code_size = LZW_MIN_CODE_SIZE + 1;
+    let mut next_code = END_CODE + 1;
+    writer.write(CLEAR_CODE, code_size);
+
+    let Some((&first, rest)) = pixels.split_first() else {
+        writer.write(END_CODE, code_size);
+        return writer.finish();
+    };
+    let mut prefix = first as u16;
+    for &pixel in rest {
+        let child = children[prefix as usize][pixel as usize];
+        if child != 0 {
+            prefix = child;
+            continue;
+        }
+        writer.write(prefix, code_size);
+        if next_code < MAX_CODES {
+            if next_code == 1 << code_size {
+                code_size += 1;
+            }
+            children[prefix as usize][pixel as usize] = next_code;
+            next_code += 1;
+        } else {
+            writer.write(CLEAR_CODE, code_size);
+            children.iter_mut().for_each(|child| *child = [0; 4]);
+            code_size = LZW_MIN_CODE_SIZE + 1;
+            next_code = END_CODE + 1;
+        }
+        prefix = pixel as u16;
+    }
+    writer.write(prefix, code_size);
+    writer.write(END_CODE, code_size);
+    writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PALETTE: Palette = [[0xFF; 3], [0xAA; 3], [0x55; 3], [0x00; 3]];
+
+    // Plain GIF LZW decoding, the other way round from `lzw`
+    fn unlzw(data: &[u8]) -> Vec<u8> {
+        let mut bit = 0;
+        let mut read = |size: u8| {
+            let mut code = 0;
+            for index in 0..size {
+                code |= ((data[bit / 8] >> (bit % 8)) as u16 & 1) << index;
+                bit += 1;
+            }
+            code
+        };
+        let mut table: Vec<Vec<u8>> = Vec::new();
+        let mut size = LZW_MIN_CODE_SIZE + 1;
+        let mut previous: Option<Vec<u8>> = None;
+        let mut pixels = Vec::new();
+        loop {
+            let code = read(size);
+            if code == CLEAR_CODE {
+                table = (0..4).map(|colour| vec![colour]).chain([vec![], vec![]]).collect();
+                size = LZW_MIN_CODE_SIZE + 1;
+                previous = None;
+                continue;
+            }
+            if code == END_CODE {
+                return pixels;
+            }
+            let entry = match table.get(code as usize) {
+                Some(entry) => entry.clone(),
+                None => {
+                    let mut entry = previous.clone().unwrap();
+                    entry.push(entry[0]);
+                    entry
+                }
+            };
+            if let Some(mut previous) = previous.take() {
+                if table.len() < MAX_CODES as usize {
+                    previous.push(entry[0]);
+                    table.push(previous);
+                    if table.len() == 1 << size && size < 12 {
+                        size += 1;
+                    }
+                }
+            }
+            pixels.extend_from_slice(&entry);
+            previous = Some(entry);
+        }
+    }
+
+    // Delay and pixels of every frame in a GIF `GifEncoder` wrote
+    fn frames(gif: &[u8]) -> Vec<(u16, Vec<u8>)> {
+        // Header, screen descriptor, colour table and the looping extension
+        let mut at = 6 + 7 + 12 + 19;
+        let mut frames = Vec::new();
+        let mut delay = 0;
+        loop {
+            match gif[at] {
+                0x21 => {
+                    delay = u16::from_le_bytes([gif[at + 4], gif[at + 5]]);
+                    at += 8;
+                }
+                0x2C => {
+                    at += 11;
+                    let mut data = Vec::new();
+                    while gif[at] != 0 {
+                        let length = gif[at] as usize;
+                        data.extend_from_slice(&gif[at + 1..at + 1 + length]);
+                        at += 1 + length;
+                    }
+                    at += 1;
+                    frames.push((delay, unlzw(&data)));
+                }
+                0x3B => return frames,
+                other => panic!("unexpected block {:02X}", other),
+            }
+        }
+    }
+
+    fn screen(seed: u32) -> Vec<u8> {
+        // Noise, so the LZW table fills up and has to be cleared
+        let mut state = seed;
+        (0..SCREEN_WIDTH * SCREEN_HEIGHT)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                (state >> 16) as u8 & 3
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lzw_round_trips() {
+        for pixels in [Vec::new(), vec![0], vec![3; 5000], screen(1)] {
+            assert_eq!(unlzw(&lzw(&pixels)), pixels);
+        }
+    }
+
+    #[test]
+    fn header_has_the_scaled_size_and_palette() {
+        let gif = GifEncoder::new(2, PALETTE).finish();
+        assert_eq!(&gif[..6], b"GIF89a");
+        assert_eq!(u16::from_le_bytes([gif[6], gif[7]]), 320);
+        assert_eq!(u16::from_le_bytes([gif[8], gif[9]]), 288);
+        assert_eq!(&gif[13..16], &[0xFF; 3]);
+        assert_eq!(gif.last(), Some(&0x3B));
+        assert!(frames(&gif).is_empty());
+    }
+
+    #[test]
+    fn frames_too_short_to_show_are_dropped() {
+        let (a, b, c) = (screen(1), screen(2), screen(3));
+        let mut encoder = GifEncoder::new(1, PALETTE);
+        encoder.add_frame(&a);
+        encoder.add_frame(&b);
+        encoder.add_frame(&c);
+        assert_eq!(encoder.frames(), 3);
+        // A would only be up for 1/100 s, B takes its place
+        assert_eq!(frames(&encoder.finish()), [(3, b), (2, c)]);
+    }
+
+    #[test]
+    fn unchanged_frames_stay_on_screen() {
+        let mut encoder = GifEncoder::new(2, PALETTE);
+        for _ in 0..60 {
+            encoder.add_frame(&[1; SCREEN_WIDTH * SCREEN_HEIGHT]);
+        }
+        let frames = frames(&encoder.finish());
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].0, 100);
+        assert_eq!(frames[0].1, vec![1; SCREEN_WIDTH * SCREEN_HEIGHT * 4]);
+    }
+}
@@ -0,0 +1,248 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::memory::Memory;
+
+/// What a code does once it's decoded
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CheatCode {
+    /// `01VVAAAA`, writes `value` to RAM at `address` every frame
+    GameShark { bank: u8, value: u8, address: u16 },
+    /// `ABC-DEF` or `ABC-DEF-GHI`, reads of `address` in ROM return `value`,
+    /// but only while the ROM holds `compare` there when there is one
+    GameGenie { address: u16, value: u8, compare: Option<u8> },
+}
+
+/// One Game Genie code as the bus sees it, see `Memory::rom_patches`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RomPatch {
+    pub address: u16,
+    pub value: u8,
+    pub compare: Option<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Cheat {
+    /// As it was typed in, upper case
+    pub text: String,
+    pub code: CheatCode,
+    pub name: String,
+    pub enabled: bool,
+}
+
+/// The cheats for one ROM
+#[derive(Debug, Clone, Default)]
+pub struct Cheats {
+    pub cheats: Vec<Cheat>,
+}
+
+fn hex_digits(text: &str) -> Option<Vec<u8>> {
+    text.chars().map(|c| c.to_digit(16).map(|digit| digit as u8)).collect()
+}
+
+impl CheatCode {
+    /// Tells the two formats apart by shape, Game Genie codes have dashes
+    pub fn parse(text: &str) -> Result<CheatCode, String> {
+        let text = text.trim();
+        if text.contains('-') {
+            return CheatCode::parse_game_genie(text);
+        }
+        let digits = hex_digits(text).filter(|digits| digits.len() == 8).ok_or_else(|| {
+            format!("{} isn't a GameShark code (01VVAAAA) or a Game Genie code (ABC-DEF-GHI)", text)
+        })?;
+        let byte = |index: usize| digits[index] << 4 | digits[index + 1];
+        // The address is stored low byte first
+        let address = u16::from_le_bytes([byte(4), byte(6)]);
+        if address < 0x8000 {
+            return Err(format!("{} writes to ROM at {:04X}, GameShark codes only patch RAM", text, address));
+        }
+        Ok(CheatCode::GameShark { bank: byte(0), value: byte(2), address })
+    }
+
+    fn parse_game_genie(text: &str) -> Result<CheatCode, String> {
+        let invalid = || format!("{} isn't a Game Genie code (ABC-DEF or ABC-DEF-GHI)", text);
+        let groups: Vec<&str> = text.split('-').collect();
+        if !matches!(groups.len(), 2 | 3) || groups.iter().any(|group| group.len() != 3) {
+            return Err(invalid());
+        }
+        let digits = hex_digits(&groups.concat()).ok_or_else(invalid)?;
+        let digit = |index: usize| digits[index] as u16;
+        let value = digits[0] << 4 | digits[1];
+        // The top nibble comes last and is inverted
+        let address = (digit(5) ^ 0xF) << 12 | digit(2) << 8 | digit(3) << 4 | digit(4);
+        if address >= 0x8000 {
+            return Err(format!("{} patches {:04X}, Game Genie codes only patch ROM", text, address));
+        }
+        // H is thought to be a checksum and isn't checked, G and I hold the compare value scrambled
+        let compare = (digits.len() == 9).then(|| (digits[6] << 4 | digits[8]).rotate_right(2) ^ 0xBA);
+        Ok(CheatCode::GameGenie { address, value, compare })
+    }
+}
+
+/// The GameShark code that keeps `address` at `value`
+pub fn game_shark_code(address: u16, value: u8) -> String {
+    let [low, high] = address.to_le_bytes();
+    format!("01{:02X}{:02X}{:02X}", value, low, high)
+}
+
+impl Cheat {
+    pub fn new(text: &str, name: &str, enabled: bool) -> Result<Cheat, String> {
+        let code = CheatCode::parse(text)?;
+        Ok(Cheat { text: text.trim().to_uppercase(), code, name: name.trim().to_string(), enabled })
+    }
+}
+
+impl RomPatch {
+    pub fn applies(&self, address: u16, original: u8) -> bool {
+        if self.address != address {
+            return false;
+        }
+        match self.compare {
+            Some(compare) => compare == original,
+            None => true,
+        }
+    }
+}
+
+impl Cheats {
+    pub fn new() -> Cheats {
+        Cheats::default()
+    }
+
+    pub fn any_enabled(&self) -> bool {
+        self.cheats.iter().any(|cheat| cheat.enabled)
+    }
+
+    pub fn add(&mut self, text: &str, name: &str) -> Result<(), String> {
+        self.cheats.push(Cheat::new(text, name, true)?);
+        Ok(())
+    }
+
+    /// Reads what `to_text` wrote, one `on|off CODE name` per line
+    pub fn parse(text: &str) -> Result<Cheats, String> {
+        let mut cheats = Cheats::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(';') {
+                continue;
+            }
+            let mut parts = line.splitn(3, char::is_whitespace);
+            let enabled = match parts.next() {
+                Some("on") => true,
+                Some("off") => false,
+                _ => return Err(format!("Line {} doesn't start with on or off: {}", index + 1, line)),
+            };
+            let code = parts.next().unwrap_or("");
+            let cheat = Cheat::new(code, parts.next().unwrap_or(""), enabled).map_err(|err| format!("Line {}: {}", index + 1, err))?;
+            cheats.cheats.push(cheat);
+        }
+        Ok(cheats)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::from("; on|off CODE name\n");
+        for cheat in &self.cheats {
+            let _ = writeln!(text, "{} {} {}", if cheat.enabled { "on" } else { "off" }, cheat.text, cheat.name);
+        }
+        text
+    }
+
+    /// The enabled Game Genie codes, to be handed to `Memory::rom_patches`
+    pub fn rom_patches(&self) -> Vec<RomPatch> {
+        self.cheats
+            .iter()
+            .filter(|cheat| cheat.enabled)
+            .filter_map(|cheat| match cheat.code {
+                CheatCode::GameGenie { address, value, compare } => Some(RomPatch { address, value, compare }),
+                CheatCode::GameShark { .. } => None,
+            })
+            .collect()
+    }
+
+    /// Does the writes of the enabled GameShark codes, call it once per frame.
+    /// They bypass `write_byte` so watchpoints and the serial port don't see them.
+    pub fn apply_frame(&self, memory: &mut Memory) {
+        for cheat in self.cheats.iter().filter(|cheat| cheat.enabled) {
+            // There's only one WRAM bank on the DMG, so the bank byte doesn't change anything
+            if let CheatCode::GameShark { value, address, .. } = cheat.code {
+                memory.memory[address as usize] = value;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn game_shark_codes_store_the_address_low_byte_first() {
+        assert_eq!(CheatCode::parse("01FF34C1"), Ok(CheatCode::GameShark { bank: 0x01, value: 0xFF, address: 0xC134 }));
+        assert_eq!(CheatCode::parse(" 01ff34c1 "), CheatCode::parse("01FF34C1"));
+        assert_eq!(game_shark_code(0xC134, 0xFF), "01FF34C1");
+    }
+
+    #[test]
+    fn game_shark_codes_only_patch_ram() {
+        assert!(CheatCode::parse("01FF3412").is_err());
+        assert!(CheatCode::parse("01FF34C").is_err());
+        assert!(CheatCode::parse("01FF34CG").is_err());
+    }
+
+    #[test]
+    fn game_genie_codes() {
+        // 3E at 1234, the top address nibble is the inverted sixth digit
+        assert_eq!(CheatCode::parse("3E2-34E"), Ok(CheatCode::GameGenie { address: 0x1234, value: 0x3E, compare: None }));
+        // FE is 05 XORed with BA and rotated left by two
+        assert_eq!(CheatCode::parse("3E2-34E-FAE"), Ok(CheatCode::GameGenie { address: 0x1234, value: 0x3E, compare: Some(0x05) }));
+    }
+
+    #[test]
+    fn game_genie_codes_only_patch_rom() {
+        assert!(CheatCode::parse("3E2-346").is_err());
+        assert!(CheatCode::parse("3E2-34").is_err());
+        assert!(CheatCode::parse("3E2-34E-FA").is_err());
+        assert!(CheatCode::parse("3E2-34E-FAE-000").is_err());
+    }
+
+    #[test]
+    fn patches_only_apply_while_the_rom_holds_the_compare_value() {
+        let patch = RomPatch { address: 0x1234, value: 0x3E, compare: Some(0x05) };
+        assert!(patch.applies(0x1234, 0x05));
+        assert!(!patch.applies(0x1234, 0x06));
+        assert!(!patch.applies(0x1235, 0x05));
+        assert!(RomPatch { compare: None, ..patch }.applies(0x1234, 0x06));
+    }
+
+    #[test]
+    fn round_trips_through_text() {
+        let text = "; on|off CODE name\non 01FF34C1 Infinite lives\noff 3E2-34E-FAE \n";
+        let cheats = Cheats::parse(text).unwrap();
+        assert_eq!(cheats.cheats.len(), 2);
+        assert_eq!((cheats.cheats[0].name.as_str(), cheats.cheats[0].enabled), ("Infinite lives", true));
+        assert!(!cheats.cheats[1].enabled);
+        assert_eq!(cheats.to_text(), text);
+        assert!(Cheats::parse("maybe 01FF34C1").is_err());
+        assert_eq!(Cheats::parse("\non 01FF3412").unwrap_err(), "Line 2: 01FF3412 writes to ROM at 1234, GameShark codes only patch RAM");
+    }
+
+    #[test]
+    fn enabled_codes_reach_the_bus() {
+        let mut cheats = Cheats::new();
+        cheats.add("01FF34C1", "").unwrap();
+        cheats.add("3E2-34E", "").unwrap();
+        cheats.add("01AA00C0", "").unwrap();
+        cheats.cheats[2].enabled = false;
+
+        let mut memory = Memory::new();
+        memory.in_bootrom = false;
+        memory.rom_patches = cheats.rom_patches();
+        cheats.apply_frame(&mut memory);
+        assert_eq!(memory.memory[0xC134], 0xFF);
+        assert_eq!(memory.memory[0xC000], 0x00);
+        assert_eq!(memory.read_byte(0x1234), 0x3E);
+        assert_eq!(memory.memory[0x1234], 0x00);
+    }
+}
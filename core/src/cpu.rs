@@ -0,0 +1,325 @@
+mod registry;
+mod flags;
+pub mod instructions;
+#[cfg(feature = "alloc")]
+pub mod disassembler;
+mod timing;
+
+use crate::error::EmuError;
+use crate::memory::Memory;
+use crate::ppu;
+
+use self::instructions::Instructions;
+pub use self::registry::CPURegistry;
+
+/// T-cycles per second
+pub const CLOCK_HZ: u64 = 4_194_304;
+/// 154 lines of 456 T-cycles, roughly 59.73 frames per second at 4.19 MHz
+pub const FRAME_T_CYCLES: u64 = 70224;
+
+/// What the CPU does when it fetches an opcode it can't execute
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IllegalOpcodePolicy {
+    /// Hard-lock like the real hardware, only a reset gets out of this
+    Lock,
+    /// Stop in front of the opcode and hand control back to the debugger
+    Break,
+    /// Return the opcode as an error from `step`
+    Error,
+}
+
+#[derive(Clone)]
+pub struct CPU {
+    // The Main Engine of the Emulator
+    pub registry: registry::CPURegistry,
+    pub memory: Memory,
+    pub last_instruction: Instructions,
+    pub illegal_opcode_policy: IllegalOpcodePolicy,
+    // Set by the Break policy, the frontend clears it once it has stopped
+    pub break_requested: bool,
+    // Where the last break happened, stepping from there again goes past the opcode
+    resume_from: Option<u16>,
+    // T-cycles since power on
+    pub cycles: u64,
+}
+
+impl Default for CPU {
+    fn default() -> CPU {
+        CPU::new()
+    }
+}
+
+impl CPU {
+    pub fn new() -> CPU {
+        CPU {
+            registry: registry::CPURegistry::new(),
+            memory: Memory::new(),
+            last_instruction: Instructions::NOP(),
+            illegal_opcode_policy: IllegalOpcodePolicy::Lock,
+            break_requested: false,
+            resume_from: None,
+            cycles: 0,
+        }
+    }
+
+    /// Power cycles the machine, the cartridge stays in
+    pub fn reset(&mut self) {
+        self.registry = registry::CPURegistry::new();
+        self.memory.reset();
+        self.last_instruction = Instructions::NOP();
+        self.break_requested = false;
+        self.resume_from = None;
+        self.cycles = 0;
+    }
+
+    /// Frames since power on, a frame being `FRAME_T_CYCLES` long
+    pub fn frame_number(&self) -> u64 {
+        self.cycles / FRAME_T_CYCLES
+    }
+
+    /// Runs until the next frame starts
+    pub fn run_frame(&mut self) -> Result<(), EmuError> {
+        let frame = self.frame_number();
+        while self.frame_number() == frame {
+            self.step()?;
+            if self.break_requested {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    // Everything else on the bus runs off the CPU clock
+    fn tick(&mut self, cycles: u64) {
+        self.cycles += cycles;
+        ppu::update_registers(&mut self.memory, self.cycles % FRAME_T_CYCLES);
+    }
+
+    pub fn step(&mut self) -> Result<(), EmuError> {
+        if self.registry.locked {
+            // The clock keeps going, there just isn't anything executed anymore
+            self.tick(4);
+            return Ok(());
+        }
+
+        let opcode_address = self.registry.pc;
+        let resume_from = self.resume_from.take();
+        let mut opcode = self.memory.read_byte(self.registry.pc);
+        let prefixed = opcode == 0xCB;
+        if prefixed {
+          opcode = self.memory.read_byte(self.registry.pc.wrapping_add(1));
+          self.registry.pc = self.registry.pc.wrapping_add(1);
+        }
+        let previous_pc = self.registry.pc;
+        let instruction = match Instructions::read_byte(opcode, prefixed) {
+            Some(Instructions::ILLEGAL(_)) | None => return self.illegal_opcode(opcode, opcode_address, resume_from),
+            Some(instruction) => instruction,
+        };
+        let branch_taken = self.condition_met(opcode);
+        self.execution(&instruction)?;
+        self.tick(timing::cycles(opcode, prefixed, branch_taken) as u64);
+
+        if self.registry.pc == previous_pc {
+            self.registry.pc = self.registry.pc.wrapping_add(1);
+        }
+        self.last_instruction = instruction;
+
+        if self.memory.in_bootrom && self.registry.pc == 0x100 {
+            self.memory.in_bootrom = false;
+        }
+
+        Ok(())
+    }
+
+    // Only meaningful for conditional jumps, calls and returns, the condition sits in bits 3-4
+    fn condition_met(&self, opcode: u8) -> bool {
+        let flags = &self.registry.f;
+        match (opcode >> 3) & 0x03 {
+            0 => !flags.z_zero,
+            1 => flags.z_zero,
+            2 => !flags.c_carry,
+            _ => flags.c_carry,
+        }
+    }
+
+    /// A break leaves PC on the opcode, resuming from there skips it like a 1 byte NOP
+    fn illegal_opcode(&mut self, opcode: u8, address: u16, resume_from: Option<u16>) -> Result<(), EmuError> {
+        match self.illegal_opcode_policy {
+            IllegalOpcodePolicy::Lock => self.registry.locked = true,
+            IllegalOpcodePolicy::Break if resume_from == Some(address) => {
+                self.registry.pc = address.wrapping_add(1);
+                self.tick(4);
+            }
+            IllegalOpcodePolicy::Break => {
+                self.break_requested = true;
+                self.resume_from = Some(address);
+            }
+            IllegalOpcodePolicy::Error => return Err(EmuError::IllegalOpcode { opcode, address }),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A CPU past the boot ROM with `program` at `address`
+    fn cpu_running(address: u16, program: &[u8]) -> CPU {
+        let mut cpu = CPU::new();
+        cpu.memory.load_rom(&[0; 0x8000]).unwrap();
+        cpu.memory.in_bootrom = false;
+        for (offset, byte) in program.iter().enumerate() {
+            cpu.memory.memory[address.wrapping_add(offset as u16) as usize] = *byte;
+        }
+        cpu.registry.pc = address;
+        cpu
+    }
+
+    #[test]
+    fn pc_wraps_at_the_top_of_the_address_space() {
+        let mut cpu = cpu_running(0xFFFF, &[0x00]);
+        cpu.step().unwrap();
+        assert_eq!(cpu.registry.pc, 0x0000);
+    }
+
+    #[test]
+    fn prefixed_opcode_at_the_top_of_the_address_space() {
+        // SWAP A, the CB byte is at 0xFFFF and the opcode at 0x0000
+        let mut cpu = cpu_running(0xFFFF, &[0xCB, 0x37]);
+        cpu.registry.a = 0x12;
+        cpu.step().unwrap();
+        assert_eq!(cpu.registry.a, 0x21);
+        assert_eq!(cpu.registry.pc, 0x0001);
+    }
+
+    #[test]
+    fn inc_and_dec_sp_work_on_all_16_bits() {
+        let mut cpu = cpu_running(0xC000, &[0x33, 0x3B]);
+        cpu.registry.sp = 0xFFFF;
+        cpu.step().unwrap();
+        assert_eq!(cpu.registry.sp, 0x0000);
+        cpu.step().unwrap();
+        assert_eq!(cpu.registry.sp, 0xFFFF);
+    }
+
+    #[test]
+    fn stack_pointer_wraps_on_push_and_pop() {
+        // RST 38 with SP at 0x0001 stores the return address at 0xFFFF
+        let mut cpu = cpu_running(0xC000, &[0xFF]);
+        cpu.registry.sp = 0x0001;
+        cpu.step().unwrap();
+        assert_eq!(cpu.registry.sp, 0xFFFF);
+        assert_eq!(cpu.memory.read_word(0xFFFF), 0xC001);
+    }
+
+    #[test]
+    fn call_and_rst_return_after_themselves() {
+        // CALL $C010, RST 08, with a RET at both targets
+        let mut cpu = cpu_running(0xC000, &[0xCD, 0x10, 0xC0, 0xCF]);
+        cpu.memory.memory[0xC010] = 0xC9;
+        cpu.memory.memory[0x0008] = 0xC9;
+        cpu.registry.sp = 0xDFFE;
+        cpu.step().unwrap();
+        assert_eq!((cpu.registry.pc, cpu.registry.sp), (0xC010, 0xDFFC));
+        assert_eq!(cpu.memory.peek_word(0xDFFC), 0xC003);
+        cpu.step().unwrap();
+        assert_eq!((cpu.registry.pc, cpu.registry.sp), (0xC003, 0xDFFE));
+        cpu.step().unwrap();
+        assert_eq!((cpu.registry.pc, cpu.registry.sp), (0x0008, 0xDFFC));
+        cpu.step().unwrap();
+        assert_eq!((cpu.registry.pc, cpu.registry.sp), (0xC004, 0xDFFE));
+    }
+
+    #[test]
+    fn load_through_hl_and_increment() {
+        // LD (HL+),A then LD A,(HL+)
+        let mut cpu = cpu_running(0x0100, &[0x22, 0x2A]);
+        cpu.registry.set_hl(0xC000);
+        cpu.memory.memory[0xC001] = 0x99;
+        cpu.registry.a = 0x42;
+        cpu.step().unwrap();
+        assert_eq!(cpu.memory.memory[0xC000], 0x42);
+        assert_eq!(cpu.registry.get_hl(), 0xC001);
+        cpu.step().unwrap();
+        assert_eq!(cpu.registry.a, 0x99);
+        assert_eq!(cpu.registry.get_hl(), 0xC002);
+    }
+
+    #[test]
+    fn load_through_hl_and_decrement() {
+        // LD (HL-),A then LD A,(HL-)
+        let mut cpu = cpu_running(0x0100, &[0x32, 0x3A]);
+        cpu.registry.set_hl(0xC001);
+        cpu.memory.memory[0xC000] = 0x99;
+        cpu.registry.a = 0x42;
+        cpu.step().unwrap();
+        assert_eq!(cpu.memory.memory[0xC001], 0x42);
+        assert_eq!(cpu.registry.get_hl(), 0xC000);
+        cpu.step().unwrap();
+        assert_eq!(cpu.registry.a, 0x99);
+        assert_eq!(cpu.registry.get_hl(), 0xBFFF);
+    }
+
+    #[test]
+    fn conditional_jumps_take_longer_when_taken() {
+        // JR NZ
+        let mut taken = cpu_running(0x0100, &[0x20, 0x00]);
+        taken.step().unwrap();
+        let mut not_taken = cpu_running(0x0100, &[0x20, 0x00]);
+        not_taken.registry.f.z_zero = true;
+        not_taken.step().unwrap();
+        assert_eq!((taken.cycles, not_taken.cycles), (12, 8));
+    }
+
+    #[test]
+    fn illegal_opcode_locks_by_default() {
+        let mut cpu = cpu_running(0x0100, &[0xDD, 0x00]);
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert!(cpu.registry.locked);
+        assert_eq!(cpu.registry.pc, 0x0100);
+    }
+
+    #[test]
+    fn break_policy_resumes_past_the_opcode() {
+        let mut cpu = cpu_running(0x0100, &[0xDD, 0x00]);
+        cpu.illegal_opcode_policy = IllegalOpcodePolicy::Break;
+        cpu.step().unwrap();
+        assert!(cpu.break_requested);
+        assert_eq!(cpu.registry.pc, 0x0100);
+
+        cpu.break_requested = false;
+        cpu.step().unwrap();
+        assert!(!cpu.break_requested);
+        assert_eq!(cpu.registry.pc, 0x0101);
+    }
+
+    #[test]
+    fn break_policy_breaks_again_on_the_next_visit() {
+        let mut cpu = cpu_running(0x0100, &[0xDD]);
+        cpu.illegal_opcode_policy = IllegalOpcodePolicy::Break;
+        cpu.step().unwrap();
+        cpu.break_requested = false;
+        cpu.step().unwrap();
+        cpu.registry.pc = 0x0100;
+        cpu.step().unwrap();
+        assert!(cpu.break_requested);
+    }
+
+    #[test]
+    fn error_policy_returns_the_opcode() {
+        let mut cpu = cpu_running(0x0100, &[0xFD]);
+        cpu.illegal_opcode_policy = IllegalOpcodePolicy::Error;
+        assert!(matches!(cpu.step(), Err(EmuError::IllegalOpcode { opcode: 0xFD, address: 0x0100 })));
+    }
+
+    #[test]
+    fn ldh_n_a_writes_to_the_high_page() {
+        let mut cpu = cpu_running(0xC000, &[0xE0, 0x80]);
+        cpu.registry.a = 0x42;
+        cpu.step().unwrap();
+        assert_eq!(cpu.memory.memory[0xFF80], 0x42);
+        assert_eq!(cpu.memory.memory[0xFF00], 0x00);
+    }
+}
@@ -0,0 +1,298 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::memory::Memory;
+use crate::symbols::Symbols;
+
+// Decoded straight from the opcode table instead of going through `Instructions`,
+// that way the listing shows what the ROM really contains even where the core is still wrong
+
+const R8: [&str; 8] = ["B", "C", "D", "E", "H", "L", "[HL]", "A"];
+const R16: [&str; 4] = ["BC", "DE", "HL", "SP"];
+const R16_STACK: [&str; 4] = ["BC", "DE", "HL", "AF"];
+const CONDITIONS: [&str; 4] = ["NZ", "Z", "NC", "C"];
+const ALU: [&str; 8] = ["ADD A,", "ADC A,", "SUB A,", "SBC A,", "AND A,", "XOR A,", "OR A,", "CP A,"];
+const ROTATES: [&str; 8] = ["RLC", "RRC", "RL", "RR", "SLA", "SRA", "SWAP", "SRL"];
+
+/// A single decoded instruction
+pub struct Disassembly {
+    pub address: u16,
+    /// Length in bytes including the opcode and the 0xCB prefix
+    pub length: u8,
+    pub bytes: [u8; 3],
+    /// The instruction in RGBDS syntax
+    pub text: String,
+    /// The 16-bit address or immediate the instruction refers to, if it has one
+    pub operand: Option<u16>,
+}
+
+impl Disassembly {
+    /// The raw bytes that make up the instruction
+    pub fn opcode_bytes(&self) -> &[u8] {
+        &self.bytes[..self.length as usize]
+    }
+
+    /// `text` with the operand replaced by its label, if there is one
+    pub fn text_with_labels(&self, symbols: &Symbols) -> String {
+        match self.operand.and_then(|operand| Some((operand, symbols.label(operand)?))) {
+            Some((operand, label)) => self.text.replacen(&format!("${:04X}", operand), label, 1),
+            None => self.text.clone(),
+        }
+    }
+}
+
+/// Disassembles the instruction at `address`
+pub fn disassemble(memory: &Memory, address: u16) -> Disassembly {
+    let bytes = [
+        memory.peek(address),
+        memory.peek(address.wrapping_add(1)),
+        memory.peek(address.wrapping_add(2)),
+    ];
+    let text = decode(address, bytes);
+    let length = instruction_length(bytes[0]);
+    let operand = operand(address, bytes);
+
+    Disassembly { address, length, bytes, text, operand }
+}
+
+/// Disassembles `count` instructions back to back starting at `start`
+pub fn disassemble_range(memory: &Memory, start: u16, count: usize) -> Vec<Disassembly> {
+    let mut address = start;
+    let mut lines = Vec::with_capacity(count);
+    for _ in 0..count {
+        let line = disassemble(memory, address);
+        address = address.wrapping_add(line.length as u16);
+        lines.push(line);
+    }
+    lines
+}
+
+/// Length in bytes of the instruction starting with `opcode`, works without decoding the rest
+pub fn instruction_length(opcode: u8) -> u8 {
+    match opcode {
+        0x01 | 0x08 | 0x11 | 0x21 | 0x31 | 0xC2 | 0xC3 | 0xC4 | 0xCA | 0xCC | 0xCD | 0xD2 | 0xD4 | 0xDA | 0xDC | 0xEA | 0xFA => 3,
+        0x10 | 0x18 | 0x20 | 0x28 | 0x30 | 0x38 | 0xCB | 0xE0 | 0xE8 | 0xF0 | 0xF8 => 2,
+        // LD r, n8 and the ALU operations on n8
+        _ if opcode & 0xC7 == 0x06 || opcode & 0xC7 == 0xC6 => 2,
+        _ => 1,
+    }
+}
+
+fn decode(address: u16, bytes: [u8; 3]) -> String {
+    let opcode = bytes[0];
+    let n8 = bytes[1];
+    let n16 = u16::from_le_bytes([bytes[1], bytes[2]]);
+    // JR is relative to the address right after the instruction
+    let jr_target = address.wrapping_add(2).wrapping_add(n8 as i8 as u16);
+
+    let x = opcode >> 6;
+    let y = ((opcode >> 3) & 0x07) as usize;
+    let z = (opcode & 0x07) as usize;
+    let p = y >> 1;
+    let q = y & 1;
+
+    match x {
+        0 => match z {
+            0 => match y {
+                0 => "NOP".to_string(),
+                1 => format!("LD [${:04X}], SP", n16),
+                2 => "STOP".to_string(),
+                3 => format!("JR ${:04X}", jr_target),
+                _ => format!("JR {}, ${:04X}", CONDITIONS[y - 4], jr_target),
+            },
+            1 if q == 0 => format!("LD {}, ${:04X}", R16[p], n16),
+            1 => format!("ADD HL, {}", R16[p]),
+            2 => {
+                let pointer = ["[BC]", "[DE]", "[HL+]", "[HL-]"][p];
+                if q == 0 {
+                    format!("LD {}, A", pointer)
+                } else {
+                    format!("LD A, {}", pointer)
+                }
+            }
+            3 if q == 0 => format!("INC {}", R16[p]),
+            3 => format!("DEC {}", R16[p]),
+            4 => format!("INC {}", R8[y]),
+            5 => format!("DEC {}", R8[y]),
+            6 => format!("LD {}, ${:02X}", R8[y], n8),
+            _ => ["RLCA", "RRCA", "RLA", "RRA", "DAA", "CPL", "SCF", "CCF"][y].to_string(),
+        },
+        1 if opcode == 0x76 => "HALT".to_string(),
+        1 => format!("LD {}, {}", R8[y], R8[z]),
+        2 => format!("{} {}", ALU[y], R8[z]),
+        _ => match opcode {
+            0xC0 | 0xC8 | 0xD0 | 0xD8 => format!("RET {}", CONDITIONS[y]),
+            0xC2 | 0xCA | 0xD2 | 0xDA => format!("JP {}, ${:04X}", CONDITIONS[y], n16),
+            0xC4 | 0xCC | 0xD4 | 0xDC => format!("CALL {}, ${:04X}", CONDITIONS[y], n16),
+            0xC1 | 0xD1 | 0xE1 | 0xF1 => format!("POP {}", R16_STACK[p]),
+            0xC5 | 0xD5 | 0xE5 | 0xF5 => format!("PUSH {}", R16_STACK[p]),
+            0xC6 | 0xCE | 0xD6 | 0xDE | 0xE6 | 0xEE | 0xF6 | 0xFE => format!("{} ${:02X}", ALU[y], n8),
+            0xC7 | 0xCF | 0xD7 | 0xDF | 0xE7 | 0xEF | 0xF7 | 0xFF => format!("RST ${:02X}", y * 8),
+            0xC3 => format!("JP ${:04X}", n16),
+            0xC9 => "RET".to_string(),
+            0xCB => decode_prefixed(n8),
+            0xCD => format!("CALL ${:04X}", n16),
+            0xD9 => "RETI".to_string(),
+            0xE0 => format!("LDH [${:04X}], A", 0xFF00 | n8 as u16),
+            0xE2 => "LDH [C], A".to_string(),
+            0xE8 => format!("ADD SP, {}", n8 as i8),
+            0xE9 => "JP HL".to_string(),
+            0xEA => format!("LD [${:04X}], A", n16),
+            0xF0 => format!("LDH A, [${:04X}]", 0xFF00 | n8 as u16),
+            0xF2 => "LDH A, [C]".to_string(),
+            0xF3 => "DI".to_string(),
+            0xF8 => format!("LD HL, SP{:+}", n8 as i8),
+            0xF9 => "LD SP, HL".to_string(),
+            0xFA => format!("LD A, [${:04X}]", n16),
+            0xFB => "EI".to_string(),
+            // The 11 illegal opcodes, RGBDS spells them as raw data
+            _ => format!("DB ${:02X}", opcode),
+        },
+    }
+}
+
+// Whatever `decode` prints as $XXXX
+fn operand(address: u16, bytes: [u8; 3]) -> Option<u16> {
+    let n16 = u16::from_le_bytes([bytes[1], bytes[2]]);
+    match bytes[0] {
+        0x18 | 0x20 | 0x28 | 0x30 | 0x38 => Some(address.wrapping_add(2).wrapping_add(bytes[1] as i8 as u16)),
+        0x08 | 0x01 | 0x11 | 0x21 | 0x31 | 0xC2 | 0xC3 | 0xC4 | 0xCA | 0xCC | 0xCD | 0xD2 | 0xD4 | 0xDA | 0xDC | 0xEA | 0xFA => Some(n16),
+        0xE0 | 0xF0 => Some(0xFF00 | bytes[1] as u16),
+        _ => None,
+    }
+}
+
+fn decode_prefixed(opcode: u8) -> String {
+    let bit = (opcode >> 3) & 0x07;
+    let target = R8[(opcode & 0x07) as usize];
+
+    match opcode >> 6 {
+        0 => format!("{} {}", ROTATES[bit as usize], target),
+        1 => format!("BIT {}, {}", bit, target),
+        2 => format!("RES {}, {}", bit, target),
+        _ => format!("SET {}, {}", bit, target),
+    }
+}
+
+/// Instructions have different lengths so there's no way to walk backwards from `address`.
+/// This looks for the furthest address up to `max_bytes` before it that decodes back into `address`
+pub fn context_start(memory: &Memory, address: u16, max_bytes: u16) -> u16 {
+    for back in (1..=max_bytes).rev() {
+        let start = address.wrapping_sub(back);
+        let mut current = start;
+        while current.wrapping_sub(start) < back {
+            current = current.wrapping_add(disassemble(memory, current).length as u16);
+        }
+        if current == address {
+            return start;
+        }
+    }
+    address
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory_with(address: u16, bytes: &[u8]) -> Memory {
+        let mut memory = Memory::new();
+        memory.in_bootrom = false;
+        let start = address as usize;
+        memory.memory[start..start + bytes.len()].copy_from_slice(bytes);
+        memory
+    }
+
+    fn text(bytes: &[u8]) -> String {
+        disassemble(&memory_with(0x0100, bytes), 0x0100).text
+    }
+
+    #[test]
+    fn disassembling_isnt_a_memory_access() {
+        let mut memory = memory_with(0x0100, &[0x21, 0x34, 0x12]);
+        memory.record_accesses = true;
+        disassemble(&memory, 0x0100);
+        assert!(memory.take_accesses().is_empty());
+    }
+
+    #[test]
+    fn decodes_rgbds_syntax() {
+        assert_eq!(text(&[0x00]), "NOP");
+        assert_eq!(text(&[0x21, 0x34, 0x12]), "LD HL, $1234");
+        assert_eq!(text(&[0x22]), "LD [HL+], A");
+        assert_eq!(text(&[0x3A]), "LD A, [HL-]");
+        assert_eq!(text(&[0x7E]), "LD A, [HL]");
+        assert_eq!(text(&[0x76]), "HALT");
+        assert_eq!(text(&[0xAF]), "XOR A, A");
+        assert_eq!(text(&[0xFE, 0x90]), "CP A, $90");
+        assert_eq!(text(&[0xE0, 0x44]), "LDH [$FF44], A");
+        assert_eq!(text(&[0xF8, 0xFE]), "LD HL, SP-2");
+        assert_eq!(text(&[0xCD, 0x00, 0x40]), "CALL $4000");
+        assert_eq!(text(&[0xD8]), "RET C");
+        assert_eq!(text(&[0xFF]), "RST $38");
+    }
+
+    #[test]
+    fn decodes_prefixed_opcodes() {
+        assert_eq!(text(&[0xCB, 0x7C]), "BIT 7, H");
+        assert_eq!(text(&[0xCB, 0x37]), "SWAP A");
+        assert_eq!(text(&[0xCB, 0x86]), "RES 0, [HL]");
+        assert_eq!(disassemble(&memory_with(0x0100, &[0xCB, 0xFF]), 0x0100).length, 2);
+    }
+
+    #[test]
+    fn jr_targets_are_relative_to_the_next_instruction() {
+        let line = disassemble(&memory_with(0x0100, &[0x20, 0xFB]), 0x0100);
+        assert_eq!(line.text, "JR NZ, $00FD");
+        assert_eq!(line.operand, Some(0x00FD));
+        assert_eq!(line.opcode_bytes(), &[0x20, 0xFB]);
+    }
+
+    #[test]
+    fn operands_with_a_label_show_it() {
+        let symbols = Symbols::parse("00:0150 Main\n").unwrap();
+        let call = disassemble(&memory_with(0x0100, &[0xCD, 0x50, 0x01]), 0x0100);
+        assert_eq!(call.text_with_labels(&symbols), "CALL Main");
+        let jump = disassemble(&memory_with(0x0100, &[0xC3, 0x51, 0x01]), 0x0100);
+        assert_eq!(jump.text_with_labels(&symbols), "JP $0151");
+    }
+
+    #[test]
+    fn instruction_lengths() {
+        assert_eq!(instruction_length(0x00), 1);
+        assert_eq!(instruction_length(0x3E), 2);
+        assert_eq!(instruction_length(0x36), 2);
+        assert_eq!(instruction_length(0x10), 2);
+        assert_eq!(instruction_length(0xCB), 2);
+        assert_eq!(instruction_length(0xFE), 2);
+        assert_eq!(instruction_length(0x08), 3);
+        assert_eq!(instruction_length(0xCD), 3);
+        assert_eq!(instruction_length(0xDD), 1);
+    }
+
+    #[test]
+    fn illegal_opcodes_are_data() {
+        assert_eq!(text(&[0xDD]), "DB $DD");
+    }
+
+    #[test]
+    fn range_follows_instruction_lengths() {
+        // LD A, $01 / JP $0150 / NOP
+        let memory = memory_with(0x0100, &[0x3E, 0x01, 0xC3, 0x50, 0x01, 0x00]);
+        let addresses: Vec<u16> = disassemble_range(&memory, 0x0100, 3).iter().map(|line| line.address).collect();
+        assert_eq!(addresses, [0x0100, 0x0102, 0x0105]);
+    }
+
+    #[test]
+    fn context_start_lands_on_an_instruction_boundary() {
+        // Three NOPs and a 3 byte JP in front of 0x0106
+        let memory = memory_with(0x0100, &[0x00, 0x00, 0x00, 0xC3, 0x00, 0x00, 0x00]);
+        let start = context_start(&memory, 0x0106, 4);
+        assert!(start <= 0x0103);
+        let mut address = start;
+        while address < 0x0106 {
+            address += disassemble(&memory, address).length as u16;
+        }
+        assert_eq!(address, 0x0106);
+    }
+}
@@ -9,15 +9,16 @@ const SUBTRACTION_FLAG_POS: u8 = 1 << 6;
 const HALF_CARRY_FLAG_POS: u8 = 1 << 5;
 const CARRY_FLAG_POS: u8 = 1 << 4;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum FlagCondition {
     ZZero,
     NZNotZero,
     CCarry,
     NCNotCarry,
-    ALWAYS
+    Always
 }
 
+#[derive(Clone)]
 pub struct Flags {
     pub z_zero: bool,
     pub n_subtraction_bcd: bool,
@@ -35,7 +36,7 @@ impl Flags {
         }
     }
 
-    pub fn get_flags(&mut self) -> u8 {
+    pub fn get_flags(&self) -> u8 {
         let mut flags: u8 = 0x00;
 
         if self.z_zero { flags |= ZERO_FLAG_POS; }
@@ -1,3 +1,5 @@
+use crate::error::EmuError;
+
 use super::{CPU, flags::FlagCondition};
 
 mod bitshift;
@@ -9,7 +11,7 @@ mod bitop;
 mod load;
 mod jumps;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum LogicTargets {
     B,
     C,
@@ -28,7 +30,7 @@ pub enum LogicTargets {
     E8
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum Instructions {
     // LOGIC INSTRUCTIONS
     ADD(LogicTargets),
@@ -121,28 +123,31 @@ pub enum Instructions {
     STOP(),
 
     PREFIX(),
+
+    // One of the 11 opcodes that don't exist on the SM83
+    ILLEGAL(u8),
 }
 
 impl CPU {
-    pub fn execution(&mut self, instruction: &Instructions) {
-        if self.logic_execution(&instruction) {
-            return;
+    pub fn execution(&mut self, instruction: &Instructions) -> Result<(), EmuError> {
+        if self.logic_execution(instruction)? {
+            return Ok(());
         }
-        if self.misc_execution(&instruction) {
-            return;
+        if self.misc_execution(instruction) {
+            return Ok(());
         }
-        if self.execute_bitop(&instruction) {
-            return;
+        if self.execute_bitop(instruction)? {
+            return Ok(());
         }
-        if self.bitshift_execution(&instruction) {
-            return;
+        if self.bitshift_execution(instruction)? {
+            return Ok(());
         }
-        if self.execute_load(&instruction) {
-            return;
+        if self.execute_load(instruction)? {
+            return Ok(());
         }
-        if self.jump_execution(&instruction) {
-            return;
+        if self.jump_execution(instruction)? {
+            return Ok(());
         }
-        panic!("Unimplemented/Invalid instruction");
+        Err(EmuError::Unimplemented(*instruction))
     }
 }
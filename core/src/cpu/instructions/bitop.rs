@@ -1,4 +1,5 @@
 use crate::cpu::CPU;
+use crate::error::EmuError;
 
 use super::{Instructions, LogicTargets};
 
@@ -10,7 +11,7 @@ impl CPU {
         self.registry.f.h_half_carry_bcd = true;
     }
 
-    fn res(&mut self, bit: &u8, target: &LogicTargets) {
+    fn res(&mut self, bit: &u8, target: &LogicTargets) -> Result<(), EmuError> {
         match target {
             LogicTargets::A => self.registry.a &= !(1 << bit),
             LogicTargets::B => self.registry.b &= !(1 << bit),
@@ -24,11 +25,12 @@ impl CPU {
                 let value = self.memory.read_byte(address) & !(1 << bit);
                 self.memory.write_byte(address, value);
             }
-            _ => panic!("Invalid RES Instruction"),
+            _ => return Err(EmuError::InvalidTarget { instruction: "RES", target: *target }),
         }
+        Ok(())
     }
 
-    fn set(&mut self, bit: &u8, target: &LogicTargets) {
+    fn set(&mut self, bit: &u8, target: &LogicTargets) -> Result<(), EmuError> {
         match target {
             LogicTargets::A => self.registry.a |= 1 << bit,
             LogicTargets::B => self.registry.b |= 1 << bit,
@@ -42,29 +44,31 @@ impl CPU {
                 let value = self.memory.read_byte(address) | 1 << bit;
                 self.memory.write_byte(address, value);
             }
-            _ => panic!("Invalid SET Instruction"),
+            _ => return Err(EmuError::InvalidTarget { instruction: "SET", target: *target }),
         }
+        Ok(())
     }
 
-    fn swap(&mut self, target: &LogicTargets) {
+    fn swap(&mut self, target: &LogicTargets) -> Result<(), EmuError> {
         match target {
-            LogicTargets::A => self.registry.a = (self.registry.a << 4) | (self.registry.a >> 4),
-            LogicTargets::B => self.registry.b = (self.registry.b << 4) | (self.registry.b >> 4),
-            LogicTargets::C => self.registry.c = (self.registry.c << 4) | (self.registry.c >> 4),
-            LogicTargets::D => self.registry.d = (self.registry.d << 4) | (self.registry.d >> 4),
-            LogicTargets::E => self.registry.e = (self.registry.e << 4) | (self.registry.e >> 4),
-            LogicTargets::H => self.registry.h = (self.registry.h << 4) | (self.registry.h >> 4),
-            LogicTargets::L => self.registry.l = (self.registry.l << 4) | (self.registry.l >> 4),
+            LogicTargets::A => self.registry.a = self.registry.a.rotate_left(4),
+            LogicTargets::B => self.registry.b = self.registry.b.rotate_left(4),
+            LogicTargets::C => self.registry.c = self.registry.c.rotate_left(4),
+            LogicTargets::D => self.registry.d = self.registry.d.rotate_left(4),
+            LogicTargets::E => self.registry.e = self.registry.e.rotate_left(4),
+            LogicTargets::H => self.registry.h = self.registry.h.rotate_left(4),
+            LogicTargets::L => self.registry.l = self.registry.l.rotate_left(4),
             LogicTargets::HL => {
                 let address = self.registry.get_hl();
                 let value = self.memory.read_byte(address);
-                self.memory.write_byte(address, (value << 4) | (value >> 4));
+                self.memory.write_byte(address, value.rotate_left(4));
             }
-            _ => panic!("Invalid SWAP Instruction"),
+            _ => return Err(EmuError::InvalidTarget { instruction: "SWAP", target: *target }),
         }
+        Ok(())
     }
 
-    pub fn execute_bitop(&mut self, instruction: &Instructions) -> bool {
+    pub fn execute_bitop(&mut self, instruction: &Instructions) -> Result<bool, EmuError> {
         match instruction {
             Instructions::BIT(test_bit, target) => match target {
                 LogicTargets::A => self.bit(test_bit, self.registry.a),
@@ -77,13 +81,13 @@ impl CPU {
                 LogicTargets::HL => {
                     self.bit(test_bit, self.memory.read_byte(self.registry.get_hl()))
                 }
-                _ => panic!("Invalid BIT Instruction"),
+                _ => return Err(EmuError::InvalidTarget { instruction: "BIT", target: *target }),
             },
-            Instructions::RES(bit, target) => self.res(bit, target),
-            Instructions::SET(bit, target) => self.set(bit, target),
-            Instructions::SWAP(target) => self.swap(target),
-            _ => return false
+            Instructions::RES(bit, target) => self.res(bit, target)?,
+            Instructions::SET(bit, target) => self.set(bit, target)?,
+            Instructions::SWAP(target) => self.swap(target)?,
+            _ => return Ok(false)
         }
-        return true;
+        Ok(true)
     }
 }
@@ -1,9 +1,10 @@
 use crate::cpu::CPU;
+use crate::error::EmuError;
 
 use super::{LogicTargets, Instructions};
 
 impl CPU {
-    fn rotate(&mut self, target: &LogicTargets, through_carry: bool, left: bool) {
+    fn rotate(&mut self, target: &LogicTargets, through_carry: bool, left: bool) -> Result<(), EmuError> {
         let carry = self.registry.f.c_carry;
 
         let register: &mut u8 = match target {
@@ -14,7 +15,7 @@ impl CPU {
             LogicTargets::E => &mut self.registry.e,
             LogicTargets::H => &mut self.registry.h,
             LogicTargets::L => &mut self.registry.l,
-            _ => panic!("Invalid RL Instruction"),
+            _ => return Err(EmuError::InvalidTarget { instruction: "RL", target: *target }),
         };
 
         let new_carry = *register & 0x80 == 0x80;
@@ -35,6 +36,7 @@ impl CPU {
         self.registry.f.n_subtraction_bcd = false;
         self.registry.f.h_half_carry_bcd = false;
         self.registry.f.c_carry = new_carry;
+        Ok(())
     }
 
     fn rotate_hl(&mut self, through_carry: bool, left: bool) {
@@ -66,38 +68,38 @@ impl CPU {
         self.memory.write_byte(self.registry.get_hl(), target);
     }
 
-    pub fn bitshift_execution(&mut self, instructions: &Instructions) -> bool {
+    pub fn bitshift_execution(&mut self, instructions: &Instructions) -> Result<bool, EmuError> {
         match instructions {
             Instructions::RL(target) => {
                 match target {
                     LogicTargets::HL => self.rotate_hl(true, true),
-                    _ => self.rotate(&target, true, true),
+                    _ => self.rotate(target, true, true)?,
                 }
             },
-            Instructions::RLA() => self.rotate(&LogicTargets::A, true, true),
+            Instructions::RLA() => self.rotate(&LogicTargets::A, true, true)?,
             Instructions::RLC(target) => {
                 match target {
                     LogicTargets::HL => self.rotate_hl(false, true),
-                    _ => self.rotate(&target, false, true),
+                    _ => self.rotate(target, false, true)?,
                 }
             },
-            Instructions::RLCA() => self.rotate(&LogicTargets::A, false, true),
+            Instructions::RLCA() => self.rotate(&LogicTargets::A, false, true)?,
             Instructions::RR(target) => {
                 match target {
                     LogicTargets::HL => self.rotate_hl(true, false),
-                    _ => self.rotate(&target, true, false),
+                    _ => self.rotate(target, true, false)?,
                 }
             },
-            Instructions::RRA() => self.rotate(&LogicTargets::A, true, false),
+            Instructions::RRA() => self.rotate(&LogicTargets::A, true, false)?,
             Instructions::RRC(target) => {
                 match target {
                     LogicTargets::HL => self.rotate_hl(false, false),
-                    _ => self.rotate(&target, false, false),
+                    _ => self.rotate(target, false, false)?,
                 }
             },
-            Instructions::RRCA() => self.rotate(&LogicTargets::A, false, false),
-            _ => return false,
+            Instructions::RRCA() => self.rotate(&LogicTargets::A, false, false)?,
+            _ => return Ok(false),
         }
-        return true;
+        Ok(true)
     }
 }
\ No newline at end of file
@@ -2,22 +2,22 @@ use crate::cpu::flags::{FlagCondition};
 
 use super::{Instructions, LogicTargets};
 
-fn tail_to_logic_target(mut tail: u8) -> LogicTargets {
+fn tail_to_logic_target(mut tail: u8) -> Option<LogicTargets> {
     if tail >= 0x08 {
         tail -= 0x08;
     };
 
     // I'd do this via a transmute but that'd be unsafe so lets not :P
     match tail {
-        0x00 => LogicTargets::B,
-        0x01 => LogicTargets::C,
-        0x02 => LogicTargets::D,
-        0x03 => LogicTargets::E,
-        0x04 => LogicTargets::H,
-        0x05 => LogicTargets::L,
-        0x06 => LogicTargets::HL,
-        0x07 => LogicTargets::A,
-        _ => panic!("Invalid instruction")
+        0x00 => Some(LogicTargets::B),
+        0x01 => Some(LogicTargets::C),
+        0x02 => Some(LogicTargets::D),
+        0x03 => Some(LogicTargets::E),
+        0x04 => Some(LogicTargets::H),
+        0x05 => Some(LogicTargets::L),
+        0x06 => Some(LogicTargets::HL),
+        0x07 => Some(LogicTargets::A),
+        _ => None
     }
 }
 
@@ -31,7 +31,7 @@ impl Instructions {
     }
 
     fn read_byte_prefixed(byte: u8) -> Option<Instructions> {
-        let target = tail_to_logic_target(byte & 0x07);
+        let target = tail_to_logic_target(byte & 0x07)?;
 
         match byte {
             0x00..=0x07 => Some(Instructions::RLC(target)),
@@ -51,7 +51,7 @@ impl Instructions {
 
     
     fn read_byte_unprefixed(byte: u8) -> Option<Instructions> {
-        let tail = tail_to_logic_target(byte & 0x07);
+        let tail = tail_to_logic_target(byte & 0x07)?;
 
         match byte {
             0x00 => Some(Instructions::NOP()),
@@ -88,7 +88,7 @@ impl Instructions {
             0x1F => Some(Instructions::RRA()),
             0x20 => Some(Instructions::JR(LogicTargets::N8)),
             0x21 => Some(Instructions::LD(LogicTargets::HL, LogicTargets::N16)),
-            // 0x22 => Some(Instructions::LDI(LogicTargets::HL, LogicTargets::A)),
+            0x22 => Some(Instructions::LDHLIA()),
             0x23 => Some(Instructions::INC(LogicTargets::HL)),
             0x24 => Some(Instructions::INC(LogicTargets::H)),
             0x25 => Some(Instructions::DEC(LogicTargets::H)),
@@ -96,7 +96,7 @@ impl Instructions {
             0x27 => Some(Instructions::DAA()),
             0x28 => Some(Instructions::JR(LogicTargets::N8)),
             0x29 => Some(Instructions::ADDHLR16(LogicTargets::HL)),
-            // 0x2A => Some(Instructions::LDI(LogicTargets::A, LogicTargets::HL)),
+            0x2A => Some(Instructions::LDAHLI()),
             0x2B => Some(Instructions::DEC(LogicTargets::HL)),
             0x2C => Some(Instructions::INC(LogicTargets::L)),
             0x2D => Some(Instructions::DEC(LogicTargets::L)),
@@ -104,7 +104,7 @@ impl Instructions {
             0x2F => Some(Instructions::CPL()),
             0x30 => Some(Instructions::JR(LogicTargets::N8)),
             0x31 => Some(Instructions::LD(LogicTargets::SP, LogicTargets::N16)),
-            0x32 => Some(Instructions::LDAHLD()),
+            0x32 => Some(Instructions::LDHLDA()),
             0x33 => Some(Instructions::INC(LogicTargets::SP)),
             0x34 => Some(Instructions::INCHL()),
             0x35 => Some(Instructions::DECHL()),
@@ -112,7 +112,7 @@ impl Instructions {
             0x37 => Some(Instructions::SCF()),
             0x38 => Some(Instructions::JR(LogicTargets::N8)),
             0x39 => Some(Instructions::ADDHLR16(LogicTargets::SP)),
-            0x3A => Some(Instructions::LDAHLI()),
+            0x3A => Some(Instructions::LDAHLD()),
             0x3B => Some(Instructions::DEC(LogicTargets::SP)),
             0x3C => Some(Instructions::INC(LogicTargets::A)),
             0x3D => Some(Instructions::DEC(LogicTargets::A)),
@@ -188,7 +188,8 @@ impl Instructions {
             0xFB => Some(Instructions::EI()),
             0xFE => Some(Instructions::CP(LogicTargets::N8)),
             0xFF => Some(Instructions::RST(0x38)),
-            _ => None
+            // These don't exist on the SM83, the real CPU hard-locks when it fetches one
+            0xD3 | 0xDB | 0xDD | 0xE3 | 0xE4 | 0xEB..=0xED | 0xF4 | 0xFC | 0xFD => Some(Instructions::ILLEGAL(byte)),
         }
     }
 }
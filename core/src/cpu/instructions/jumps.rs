@@ -1,4 +1,5 @@
 use crate::cpu::{CPU, flags::FlagCondition};
+use crate::error::EmuError;
 
 use super::{LogicTargets, Instructions};
 
@@ -9,13 +10,13 @@ impl CPU {
             FlagCondition::NZNotZero => !self.registry.f.z_zero,
             FlagCondition::CCarry => self.registry.f.c_carry,
             FlagCondition::NCNotCarry => !self.registry.f.c_carry,
-            FlagCondition::ALWAYS => true,
+            FlagCondition::Always => true,
         }
     }
 
-    fn jp(&mut self, target: &LogicTargets, condition: &FlagCondition) {
+    fn jp(&mut self, target: &LogicTargets, condition: &FlagCondition) -> Result<(), EmuError> {
         if !self.is_cond_true(condition) {
-            return;
+            return Ok(());
         }
 
         match target {
@@ -25,27 +26,31 @@ impl CPU {
             LogicTargets::N16 => {
                 self.registry.pc = self.memory.read_word(self.registry.pc);
             }
-            _ => panic!("Invalid JP Instruction"),
+            _ => return Err(EmuError::InvalidTarget { instruction: "JP", target: *target }),
         }
+        Ok(())
     }
 
-    fn call(&mut self, target: &LogicTargets, condition: &FlagCondition) {
+    fn call(&mut self, target: &LogicTargets, condition: &FlagCondition) -> Result<(), EmuError> {
         match target {
             LogicTargets::N16 => {
                 if !self.is_cond_true(condition) {
-                    return;
+                    return Ok(());
                 }
-                self.memory.write_word(self.registry.sp - 2, self.registry.pc + 2);
-                self.registry.sp -= 2;
-                self.jp(&LogicTargets::N16, &FlagCondition::ALWAYS);
+                // PC is still on the opcode, the target follows it and the caller carries on after both
+                let target = self.memory.read_word(self.registry.pc.wrapping_add(1));
+                self.memory.write_word(self.registry.sp.wrapping_sub(2), self.registry.pc.wrapping_add(3));
+                self.registry.sp = self.registry.sp.wrapping_sub(2);
+                self.registry.pc = target;
+                Ok(())
             }
-            _ => panic!("Invalid CALL Instruction {:?} - {:?}", target, condition),
+            _ => Err(EmuError::InvalidTarget { instruction: "CALL", target: *target }),
         }
     }
 
-    fn jr(&mut self, target: &LogicTargets, condition: &FlagCondition) {
+    fn jr(&mut self, target: &LogicTargets, condition: &FlagCondition) -> Result<(), EmuError> {
         if !self.is_cond_true(condition) {
-            return;
+            return Ok(());
         }
 
         let value = match target {
@@ -55,11 +60,12 @@ impl CPU {
             LogicTargets::N8 => {
                 self.memory.read_byte(self.registry.pc) as u16
             }
-            _ => panic!("Invalid JR Instruction"),
+            _ => return Err(EmuError::InvalidTarget { instruction: "JR", target: *target }),
         };
 
         let new_addr = i32::from(self.registry.pc) + i32::from(value);
         self.registry.pc = (new_addr & 0xFFFF) as u16;
+        Ok(())
     }
 
     fn ret(&mut self, condition: &FlagCondition) {
@@ -67,33 +73,34 @@ impl CPU {
             return;
         }
         self.registry.pc = self.memory.read_word(self.registry.sp);
-        self.registry.sp += 2;
+        self.registry.sp = self.registry.sp.wrapping_add(2);
     }
 
     fn rst(&mut self, target: u8) {
-        self.memory.write_word(self.registry.sp - 2, self.registry.pc);
-        self.registry.sp -= 2;
+        // Returns to the instruction after the RST, PC is still on the opcode
+        self.memory.write_word(self.registry.sp.wrapping_sub(2), self.registry.pc.wrapping_add(1));
+        self.registry.sp = self.registry.sp.wrapping_sub(2);
         self.registry.pc = target as u16;
     }
 
-    pub fn jump_execution(&mut self, instructions: &Instructions) -> bool {
+    pub fn jump_execution(&mut self, instructions: &Instructions) -> Result<bool, EmuError> {
         match instructions {
-            Instructions::JP(target) => self.jp(target, &FlagCondition::ALWAYS),
-            Instructions::JPC(condition, target) => self.jp(target, condition),
-            Instructions::CALL(target) => self.call(target, &FlagCondition::ALWAYS),
-            Instructions::CALLC(cond, target) => self.call(target, cond),
-            Instructions::JR(target) => self.jr(target, &FlagCondition::ALWAYS),
-            Instructions::JRC(target, cond) => self.jr(target, cond),
-            Instructions::RET() => self.ret(&FlagCondition::ALWAYS),
+            Instructions::JP(target) => self.jp(target, &FlagCondition::Always)?,
+            Instructions::JPC(condition, target) => self.jp(target, condition)?,
+            Instructions::CALL(target) => self.call(target, &FlagCondition::Always)?,
+            Instructions::CALLC(cond, target) => self.call(target, cond)?,
+            Instructions::JR(target) => self.jr(target, &FlagCondition::Always)?,
+            Instructions::JRC(target, cond) => self.jr(target, cond)?,
+            Instructions::RET() => self.ret(&FlagCondition::Always),
             Instructions::RETC(cond) => self.ret(cond),
             Instructions::RETI() => {
                 self.ei();
-                self.ret(&FlagCondition::ALWAYS)
+                self.ret(&FlagCondition::Always)
                 
             },
             Instructions::RST(target) => self.rst(*target),
-            _ => return false
+            _ => return Ok(false)
         }
-        return true;
+        Ok(true)
     }
 }
\ No newline at end of file
@@ -1,11 +1,12 @@
 use crate::cpu::CPU;
+use crate::error::EmuError;
 
 use super::{Instructions, LogicTargets};
 
 
 impl CPU {
-    pub fn target_to_value_r8(&mut self, target: &LogicTargets) -> u8 {
-        match target {
+    pub fn target_to_value_r8(&mut self, target: &LogicTargets) -> Result<u8, EmuError> {
+        let value = match target {
             LogicTargets::A => self.registry.a,
             LogicTargets::B => self.registry.b,
             LogicTargets::C => self.registry.c,
@@ -18,41 +19,45 @@ impl CPU {
             LogicTargets::AF |
             LogicTargets::BC |
             LogicTargets::DE => {
-                let val = self.target_to_value_r16(target);
+                let val = self.target_to_value_r16(target)?;
                 self.memory.read_byte(val)
             },
 
-            _ => panic!("Invalid target_to_value_r8 {:#?}", target),
-        }
+            _ => return Err(EmuError::InvalidTarget { instruction: "target_to_value_r8", target: *target }),
+        };
+        Ok(value)
     }
 
-    pub fn target_to_value_r16(&mut self, target: &LogicTargets) -> u16 {
-        match target {
+    pub fn target_to_value_r16(&mut self, target: &LogicTargets) -> Result<u16, EmuError> {
+        let value = match target {
             LogicTargets::BC => self.registry.get_bc(),
             LogicTargets::DE => self.registry.get_de(),
             LogicTargets::HL => self.registry.get_hl(),
             LogicTargets::AF => self.registry.get_af(),
             LogicTargets::N16 => self.memory.read_word(self.registry.pc),
             LogicTargets::SP => self.registry.sp,
-            _ => panic!("Invalid target_to_value_r16 {:#?}", target),
-        }
+            _ => return Err(EmuError::InvalidTarget { instruction: "target_to_value_r16", target: *target }),
+        };
+        Ok(value)
     }
 
-    fn ld_r8(&mut self, target: &LogicTargets, value: &LogicTargets) {
+    fn ld_r8(&mut self, target: &LogicTargets, value: &LogicTargets) -> Result<(), EmuError> {
+        let value = self.target_to_value_r8(value)?;
         match target {
-            LogicTargets::A => self.registry.a = self.target_to_value_r8(value),
-            LogicTargets::B => self.registry.b = self.target_to_value_r8(value),
-            LogicTargets::C => self.registry.c = self.target_to_value_r8(value),
-            LogicTargets::D => self.registry.d = self.target_to_value_r8(value),
-            LogicTargets::E => self.registry.e = self.target_to_value_r8(value),
-            LogicTargets::H => self.registry.h = self.target_to_value_r8(value),
-            LogicTargets::L => self.registry.l = self.target_to_value_r8(value),
-            _ => panic!("Invalid SET Instruction"),
+            LogicTargets::A => self.registry.a = value,
+            LogicTargets::B => self.registry.b = value,
+            LogicTargets::C => self.registry.c = value,
+            LogicTargets::D => self.registry.d = value,
+            LogicTargets::E => self.registry.e = value,
+            LogicTargets::H => self.registry.h = value,
+            LogicTargets::L => self.registry.l = value,
+            _ => return Err(EmuError::InvalidTarget { instruction: "LD", target: *target }),
         }
+        Ok(())
     }
 
-    fn ld_r16(&mut self, target: &LogicTargets, value: &LogicTargets) {
-        let value = self.target_to_value_r16(value);
+    fn ld_r16(&mut self, target: &LogicTargets, value: &LogicTargets) -> Result<(), EmuError> {
+        let value = self.target_to_value_r16(value)?;
 
         match target {
             LogicTargets::BC => self.registry.set_bc(value),
@@ -60,31 +65,34 @@ impl CPU {
             LogicTargets::HL => self.registry.set_hl(value),
             LogicTargets::AF => self.registry.set_af(value),
             LogicTargets::SP => self.registry.sp = value,
-            _ => panic!("Invalid SET Instruction"),
+            _ => return Err(EmuError::InvalidTarget { instruction: "LD", target: *target }),
         }
+        Ok(())
     }
 
-    fn ld_mem_r8(&mut self, target: &LogicTargets, value: &LogicTargets) {
-        let value = self.target_to_value_r8(value);
-        let address = self.target_to_value_r16(target);
+    fn ld_mem_r8(&mut self, target: &LogicTargets, value: &LogicTargets) -> Result<(), EmuError> {
+        let value = self.target_to_value_r8(value)?;
+        let address = self.target_to_value_r16(target)?;
         self.memory.write_byte(address, value);
+        Ok(())
     }
 
     /// Store value in register A into the byte at address n16, provided the address is between $FF00 and $FFFF.
-    fn ldh_r16_mem(&mut self, target: &LogicTargets, use_c: bool) {
+    fn ldh_r16_mem(&mut self, target: &LogicTargets, use_c: bool) -> Result<(), EmuError> {
         let address = match target {
             LogicTargets::N16 => self.memory.read_word(self.registry.pc),
-            _ => panic!("Invalid LDH R16 MEM Instruction"),
+            _ => return Err(EmuError::InvalidTarget { instruction: "LDH", target: *target }),
         };
 
         if address < 0xFF00 {
-            return;
+            return Ok(());
         } else if use_c {
             let c = self.registry.c;
             self.memory.write_byte(address + c as u16, self.registry.a);
         } else { 
             self.memory.write_byte(address, self.registry.a);
         }
+        Ok(())
     }
 
     fn ldhc_mem(&mut self, use_c: bool) {
@@ -94,22 +102,22 @@ impl CPU {
             let c = self.registry.c;
             self.memory.write_byte(address + c as u16, self.registry.a);
         } else { 
-            let addr = address + self.memory.read_byte(self.registry.pc) as u16;
-            self.memory.write_byte(address, self.registry.a);
+            // PC is still on the opcode, n comes right after it
+            let addr = address + self.memory.read_byte(self.registry.pc.wrapping_add(1)) as u16;
+            self.memory.write_byte(addr, self.registry.a);
         }
     }
 
-    fn ldh_a_n16(&mut self, target: &LogicTargets) {
+    fn ldh_a_n16(&mut self, target: &LogicTargets) -> Result<(), EmuError> {
         let address = match target {
             LogicTargets::N16 => self.memory.read_word(self.registry.pc),
-            _ => panic!("Invalid LDH A N16 Instruction"),
+            _ => return Err(EmuError::InvalidTarget { instruction: "LDH", target: *target }),
         };
 
-        if address < 0xFF00 {
-            return;
-        } else {
+        if address >= 0xFF00 {
             self.registry.a = self.memory.read_byte(address);
         }
+        Ok(())
     }
 
     fn ldh_a_c(&mut self) {
@@ -117,59 +125,59 @@ impl CPU {
         self.registry.a = self.memory.read_byte(0xFF00 + c as u16);
     }
 
-    pub fn execute_load(&mut self, instruction: &Instructions) -> bool{
+    pub fn execute_load(&mut self, instruction: &Instructions) -> Result<bool, EmuError> {
         match instruction {
             Instructions::LD(target, value) => {
                 match target {
                     LogicTargets::N8 => {
                         match value {
                             LogicTargets::A => self.ldhc_mem(false),
-                            _ => panic!("Invalid SET Instruction {:?} {:?}", target, value),
+                            _ => return Err(EmuError::InvalidTarget { instruction: "LD", target: *value }),
                         }
                     },
                     LogicTargets::A | LogicTargets::B | LogicTargets::C | LogicTargets::D | LogicTargets::E | LogicTargets::H | LogicTargets::L => {
-                        self.ld_r8(target, value);
+                        self.ld_r8(target, value)?;
                     },
                     LogicTargets::BC | LogicTargets::DE | LogicTargets::HL | LogicTargets::AF | LogicTargets::SP => {
-                        self.ld_r16(target, value);
+                        self.ld_r16(target, value)?;
                     },
-                    _ => panic!("Invalid SET Instruction {:?} {:?}", target, value),
+                    _ => return Err(EmuError::InvalidTarget { instruction: "LD", target: *target }),
                 }
             },
-            Instructions::LDHL(value) => self.ld_mem_r8(&LogicTargets::HL, value),
-            Instructions::LDR16R8(target, value) => self.ld_mem_r8(target, value),
-            Instructions::LDR16(target) => self.ld_mem_r8(&LogicTargets::A, target),
-            Instructions::LDHN16A(target) => self.ldh_r16_mem(target, false),
+            Instructions::LDHL(value) => self.ld_mem_r8(&LogicTargets::HL, value)?,
+            Instructions::LDR16R8(target, value) => self.ld_mem_r8(target, value)?,
+            Instructions::LDR16(target) => self.ld_mem_r8(&LogicTargets::A, target)?,
+            Instructions::LDHN16A(target) => self.ldh_r16_mem(target, false)?,
             Instructions::LDHCA() => self.ldhc_mem( true),
-            Instructions::LDHAN16(target) => self.ldh_a_n16(target),
+            Instructions::LDHAN16(target) => self.ldh_a_n16(target)?,
             Instructions::LDHAC() => self.ldh_a_c(),
             Instructions::LDHLIA() => {
-                self.ld_mem_r8(&LogicTargets::HL, &LogicTargets::A);
-                self.registry.set_hl(self.registry.get_hl() + 1);
+                self.ld_mem_r8(&LogicTargets::HL, &LogicTargets::A)?;
+                self.registry.set_hl(self.registry.get_hl().wrapping_add(1));
             },
             Instructions::LDHLDA() => {
                 // TODO: Check if this is correct
-                self.ld_mem_r8(&LogicTargets::HL, &LogicTargets::A);
-                self.registry.set_hl(self.registry.get_hl() - 1);
+                self.ld_mem_r8(&LogicTargets::HL, &LogicTargets::A)?;
+                self.registry.set_hl(self.registry.get_hl().wrapping_sub(1));
             },
             Instructions::LDAHLD() => {
                 self.registry.a = self.memory.read_byte(self.registry.get_hl());
-                self.registry.set_hl(self.registry.get_hl() - 1);
+                self.registry.set_hl(self.registry.get_hl().wrapping_sub(1));
             },
             Instructions::LDAHLI() => {
                 self.registry.a = self.memory.read_byte(self.registry.get_hl());
-                self.registry.set_hl(self.registry.get_hl() + 1);
+                self.registry.set_hl(self.registry.get_hl().wrapping_add(1));
             },
             Instructions::LDN16SP(target) => {
                 let target = match target {
                     LogicTargets::N16 => self.memory.read_word(self.registry.pc),
-                    _ => panic!("Invalid LD N16 SP Instruction"),
+                    _ => return Err(EmuError::InvalidTarget { instruction: "LD", target: *target }),
                 };
                 self.memory.write_byte(target, (self.registry.sp & 0xFF) as u8);
-                self.memory.write_byte(target+1, (self.registry.sp >> 8) as u8);
+                self.memory.write_byte(target.wrapping_add(1), (self.registry.sp >> 8) as u8);
             }
-            _ => return false
+            _ => return Ok(false)
         }
-        return true;
+        Ok(true)
     }
 }
\ No newline at end of file
@@ -1,10 +1,11 @@
 use super::super::CPU;
+use crate::error::EmuError;
 
 use super::Instructions;
 use super::LogicTargets;
 
 impl CPU {
-    fn inc(&mut self, target: &LogicTargets) {
+    fn inc(&mut self, target: &LogicTargets) -> Result<(), EmuError> {
         let target = match target {
             LogicTargets::A => &mut self.registry.a,
             LogicTargets::B => &mut self.registry.b,
@@ -13,7 +14,7 @@ impl CPU {
             LogicTargets::E => &mut self.registry.e,
             LogicTargets::H => &mut self.registry.h,
             LogicTargets::L => &mut self.registry.l,
-            _ => panic!("Unimplemented/Invalid INC target"),
+            _ => return Err(EmuError::InvalidTarget { instruction: "INC", target: *target }),
         };
 
         let (val, _car) = target.overflowing_add(1);
@@ -22,21 +23,23 @@ impl CPU {
         self.registry.f.z_zero = *target == 0;
         self.registry.f.n_subtraction_bcd = false;
         self.registry.f.h_half_carry_bcd = (*target & 0xF) == 0;
+        Ok(())
     }
 
-    fn inc_16(&mut self, target: &LogicTargets) {
-        let _target = match target {
-            LogicTargets::BC => self.registry.set_bc(self.registry.get_bc() + 1),
-            LogicTargets::DE => self.registry.set_de(self.registry.get_de() + 1),
-            LogicTargets::HL => self.registry.set_hl(self.registry.get_hl() + 1),
+    fn inc_16(&mut self, target: &LogicTargets) -> Result<(), EmuError> {
+        match target {
+            LogicTargets::BC => self.registry.set_bc(self.registry.get_bc().wrapping_add(1)),
+            LogicTargets::DE => self.registry.set_de(self.registry.get_de().wrapping_add(1)),
+            LogicTargets::HL => self.registry.set_hl(self.registry.get_hl().wrapping_add(1)),
             LogicTargets::SP => self.registry.sp = self.registry.sp.overflowing_add(1).0,
-            _ => panic!("Unimplemented/Invalid DEC target"),
+            _ => return Err(EmuError::InvalidTarget { instruction: "INC", target: *target }),
         };
+        Ok(())
     }
 
     fn inc_hl(&mut self) {
         let hl = self.registry.get_hl();
-        let target = self.memory.read_byte(hl) + 1;
+        let target = self.memory.read_byte(hl).wrapping_add(1);
         self.registry.f.z_zero = target == 0;
         self.registry.f.n_subtraction_bcd = false;
         self.registry.f.h_half_carry_bcd = (target & 0xF) == 0;
@@ -48,7 +51,7 @@ impl CPU {
         let (add_val, overflowed) = self.registry.a.overflowing_add(value);
         // @TODO: Might be wrong way to do this?
         if plus_carry && overflowed {
-            self.registry.a += 1
+            self.registry.a = self.registry.a.wrapping_add(1)
         };
         self.registry.a = add_val;
         self.registry.f.z_zero = add_val == 0;
@@ -70,13 +73,12 @@ impl CPU {
         self.registry.f.n_subtraction_bcd = false;
         self.registry.f.h_half_carry_bcd = true;
         self.registry.f.c_carry = false;
-        self.registry.a;
     }
 
     fn sub_and_cp(&mut self, value: u8, minus_carry: bool, dont_store: bool) {
         let (sub_val, overflowed) = self.registry.a.overflowing_sub(value);
         if minus_carry && overflowed {
-            self.registry.a -= 1
+            self.registry.a = self.registry.a.wrapping_sub(1)
         };
         if !dont_store {
             self.registry.a = sub_val
@@ -117,17 +119,18 @@ impl CPU {
         self.registry.f.h_half_carry_bcd = (val & 0xF) == 0;
     }
 
-    fn dec_16(&mut self, target: &LogicTargets) {
-        let _target = match target {
-            LogicTargets::BC => self.registry.set_bc(self.registry.get_bc() - 1),
-            LogicTargets::DE => self.registry.set_de(self.registry.get_de() - 1),
-            LogicTargets::HL => self.registry.set_hl(self.registry.get_hl() - 1),
-            LogicTargets::SP => self.registry.sp -= 1,
-            _ => panic!("Unimplemented/Invalid DEC target"),
+    fn dec_16(&mut self, target: &LogicTargets) -> Result<(), EmuError> {
+        match target {
+            LogicTargets::BC => self.registry.set_bc(self.registry.get_bc().wrapping_sub(1)),
+            LogicTargets::DE => self.registry.set_de(self.registry.get_de().wrapping_sub(1)),
+            LogicTargets::HL => self.registry.set_hl(self.registry.get_hl().wrapping_sub(1)),
+            LogicTargets::SP => self.registry.sp = self.registry.sp.wrapping_sub(1),
+            _ => return Err(EmuError::InvalidTarget { instruction: "DEC", target: *target }),
         };
+        Ok(())
     }
 
-    fn dec(&mut self, target: &LogicTargets) {
+    fn dec(&mut self, target: &LogicTargets) -> Result<(), EmuError> {
         let target = match target {
             LogicTargets::A => &mut self.registry.a,
             LogicTargets::B => &mut self.registry.b,
@@ -136,7 +139,7 @@ impl CPU {
             LogicTargets::E => &mut self.registry.e,
             LogicTargets::H => &mut self.registry.h,
             LogicTargets::L => &mut self.registry.l,
-            _ => panic!("Unimplemented/Invalid DEC target"),
+            _ => return Err(EmuError::InvalidTarget { instruction: "DEC", target: *target }),
         };
 
         let (value, _overflow) = target.overflowing_sub(1);
@@ -144,15 +147,16 @@ impl CPU {
         self.registry.f.z_zero = *target == 0;
         self.registry.f.n_subtraction_bcd = true;
         self.registry.f.h_half_carry_bcd = (*target & 0xF) == 0xF;
+        Ok(())
     }
 
-    fn add_hl_r16(&mut self, target: &LogicTargets) {
+    fn add_hl_r16(&mut self, target: &LogicTargets) -> Result<(), EmuError> {
         let target = match target {
             LogicTargets::BC => self.registry.get_bc(),
             LogicTargets::DE => self.registry.get_de(),
             LogicTargets::HL => self.registry.get_hl(),
             LogicTargets::SP => self.registry.sp,
-            _ => panic!("Unimplemented/Invalid ADD target"),
+            _ => return Err(EmuError::InvalidTarget { instruction: "ADD", target: *target }),
         };
 
         let (add_val, overflowed) = self.registry.get_hl().overflowing_add(target);
@@ -161,12 +165,13 @@ impl CPU {
         self.registry.f.c_carry = overflowed;
         self.registry.f.h_half_carry_bcd =
             (self.registry.get_hl() & 0xFFF) + (target & 0xFFF) > 0xFFF;
+        Ok(())
     }
 
-    fn add_sp_e8(&mut self, target: &LogicTargets) {
+    fn add_sp_e8(&mut self, target: &LogicTargets) -> Result<(), EmuError> {
         let target = match target {
             LogicTargets::E8 => self.memory.read_byte(self.registry.sp) as i16,
-            _ => panic!("Unimplemented/Invalid ADD target"),
+            _ => return Err(EmuError::InvalidTarget { instruction: "ADD", target: *target }),
         };
 
         let sp = self.registry.sp as i16;
@@ -177,10 +182,11 @@ impl CPU {
         self.registry.f.n_subtraction_bcd = false;
         self.registry.f.c_carry = overflowed;
         self.registry.f.h_half_carry_bcd = (self.registry.sp & 0xF) + (target as u16 & 0xF) > 0xF;
+        Ok(())
     }
 
     /// Returns true if the instruction is found, false if not
-    pub fn logic_execution(&mut self, instruction: &Instructions) -> bool {
+    pub fn logic_execution(&mut self, instruction: &Instructions) -> Result<bool, EmuError> {
         match instruction {
             Instructions::ADD(target) => {
                 match target {
@@ -192,13 +198,13 @@ impl CPU {
                     LogicTargets::E => self.add(self.registry.e, false),
                     LogicTargets::H => self.add(self.registry.h, false),
                     LogicTargets::L => self.add(self.registry.l, false),
-                    _ => panic!("Unimplemented/Invalid ADD target"),
+                    _ => return Err(EmuError::InvalidTarget { instruction: "ADD", target: *target }),
                 };
             }
             Instructions::ADDAHL() => self.add_hl(false),
-            Instructions::ADDHLR16(target) => self.add_hl_r16(target),
-            Instructions::ADDHLSP() => self.add_hl_r16(&LogicTargets::SP),
-            Instructions::ADDSPE8(target) => self.add_sp_e8(target),
+            Instructions::ADDHLR16(target) => self.add_hl_r16(target)?,
+            Instructions::ADDHLSP() => self.add_hl_r16(&LogicTargets::SP)?,
+            Instructions::ADDSPE8(target) => self.add_sp_e8(target)?,
             Instructions::ADC(target) => {
                 match target {
                     LogicTargets::N8 => self.add(self.memory.read_byte(self.registry.sp), true),
@@ -209,7 +215,7 @@ impl CPU {
                     LogicTargets::E => self.add(self.registry.e, true),
                     LogicTargets::H => self.add(self.registry.h, true),
                     LogicTargets::L => self.add(self.registry.l, true),
-                    _ => panic!("Unimplemented/Invalid ADC target"),
+                    _ => return Err(EmuError::InvalidTarget { instruction: "ADC", target: *target }),
                 };
             }
             Instructions::ADCHL() => self.add_hl(true),
@@ -223,7 +229,7 @@ impl CPU {
                     LogicTargets::E => self.and(self.registry.e),
                     LogicTargets::H => self.and(self.registry.h),
                     LogicTargets::L => self.and(self.registry.l),
-                    _ => panic!("Unimplemented/Invalid AND target"),
+                    _ => return Err(EmuError::InvalidTarget { instruction: "AND", target: *target }),
                 };
             }
             Instructions::ANDAHL() => self.and(self.memory.read_byte(self.registry.get_hl())),
@@ -237,7 +243,7 @@ impl CPU {
                     LogicTargets::E => self.or(self.registry.e),
                     LogicTargets::H => self.or(self.registry.h),
                     LogicTargets::L => self.or(self.registry.l),
-                    _ => panic!("Unimplemented/Invalid OR target"),
+                    _ => return Err(EmuError::InvalidTarget { instruction: "OR", target: *target }),
                 };
             }
             Instructions::ORHL() => {
@@ -253,7 +259,7 @@ impl CPU {
                     LogicTargets::E => self.xor(self.registry.e),
                     LogicTargets::H => self.xor(self.registry.h),
                     LogicTargets::L => self.xor(self.registry.l),
-                    _ => panic!("Unimplemented/Invalid XOR target"),
+                    _ => return Err(EmuError::InvalidTarget { instruction: "XOR", target: *target }),
                 };
             }
             Instructions::XORHL() => {
@@ -269,7 +275,7 @@ impl CPU {
                     LogicTargets::E => self.sub_and_cp(self.registry.e, false, true),
                     LogicTargets::H => self.sub_and_cp(self.registry.h, false, true),
                     LogicTargets::L => self.sub_and_cp(self.registry.l, false, true),
-                    _ => panic!("Unimplemented/Invalid CP target"),
+                    _ => return Err(EmuError::InvalidTarget { instruction: "CP", target: *target }),
                 };
             }
             Instructions::CPAHL() => {
@@ -285,7 +291,7 @@ impl CPU {
                     LogicTargets::E => self.sub_and_cp(self.registry.e, false, false),
                     LogicTargets::H => self.sub_and_cp(self.registry.h, false, false),
                     LogicTargets::L => self.sub_and_cp(self.registry.l, false, false),
-                    _ => panic!("Unimplemented/Invalid SUB target"),
+                    _ => return Err(EmuError::InvalidTarget { instruction: "SUB", target: *target }),
                 };
             }
             Instructions::SUBHL() => {
@@ -301,7 +307,7 @@ impl CPU {
                     LogicTargets::E => self.sub_and_cp(self.registry.e, true, false),
                     LogicTargets::H => self.sub_and_cp(self.registry.h, true, false),
                     LogicTargets::L => self.sub_and_cp(self.registry.l, true, false),
-                    _ => panic!("Unimplemented/Invalid SBC target"),
+                    _ => return Err(EmuError::InvalidTarget { instruction: "SBC", target: *target }),
                 };
             }
             Instructions::SBCHL() => {
@@ -309,22 +315,22 @@ impl CPU {
             }
             Instructions::INC(target) => {
                 match target {
-                    LogicTargets::BC | LogicTargets::DE | LogicTargets::HL => self.inc_16(target),
-                    _ => self.inc(target),
+                    LogicTargets::BC | LogicTargets::DE | LogicTargets::HL | LogicTargets::SP => self.inc_16(target)?,
+                    _ => self.inc(target)?,
                 };
             }
             Instructions::INCHL() => self.inc_hl(),
-            Instructions::INCSP() => self.inc_16(&LogicTargets::SP),
+            Instructions::INCSP() => self.inc_16(&LogicTargets::SP)?,
             Instructions::DEC(target) => {
                 match target {
-                    LogicTargets::BC | LogicTargets::DE | LogicTargets::HL => self.dec_16(target),
-                    _ => self.dec(target),
+                    LogicTargets::BC | LogicTargets::DE | LogicTargets::HL | LogicTargets::SP => self.dec_16(target)?,
+                    _ => self.dec(target)?,
                 };
             }
             Instructions::DECHL() => self.dec_hl(),
-            Instructions::DECSP() => self.dec_16(&LogicTargets::SP),
-            _ => return false,
+            Instructions::DECSP() => self.dec_16(&LogicTargets::SP)?,
+            _ => return Ok(false),
         };
-        return true;
+        Ok(true)
     }
 }
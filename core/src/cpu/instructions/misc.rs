@@ -64,6 +64,6 @@ impl CPU {
             Instructions::STOP() => self.stop(),
             _ => return false,
         };
-        return true;
+        true
     } 
 }
\ No newline at end of file
@@ -1,5 +1,6 @@
 use super::flags::Flags;
 
+#[derive(Clone)]
 pub struct CPURegistry {
     // 16-bit	Hi	Lo	Name/Function
     // AF	A	-	Accumulator & Flags 
@@ -22,6 +23,14 @@ pub struct CPURegistry {
     pub interrupts_enabled: bool,
     pub halted: bool,
     pub verylowpowermode: bool,
+    // Set after an illegal opcode, only a reset gets the CPU going again
+    pub locked: bool,
+}
+
+impl Default for CPURegistry {
+    fn default() -> CPURegistry {
+        CPURegistry::new()
+    }
 }
 
 impl CPURegistry { 
@@ -40,11 +49,12 @@ impl CPURegistry {
             interrupts_enabled: false,
             halted: false,
             verylowpowermode: false,
+            locked: false,
         }
     }
     
     /// Get Accumulator & Flags 
-    pub fn get_af(&mut self) -> u16 {
+    pub fn get_af(&self) -> u16 {
         ((self.a as u16) << 8) | (self.f.get_flags() as u16)
     }
 
@@ -0,0 +1,72 @@
+// T-cycles per opcode, conditional instructions are listed with the branch not taken.
+// The illegal opcodes are 0, the CPU locks up on those anyway
+const UNPREFIXED: [u8; 256] = [
+//  x0  x1  x2  x3  x4  x5  x6  x7  x8  x9  xA  xB  xC  xD  xE  xF
+     4, 12,  8,  8,  4,  4,  8,  4, 20,  8,  8,  8,  4,  4,  8,  4, // 0x
+     4, 12,  8,  8,  4,  4,  8,  4, 12,  8,  8,  8,  4,  4,  8,  4, // 1x
+     8, 12,  8,  8,  4,  4,  8,  4,  8,  8,  8,  8,  4,  4,  8,  4, // 2x
+     8, 12,  8,  8, 12, 12, 12,  4,  8,  8,  8,  8,  4,  4,  8,  4, // 3x
+     4,  4,  4,  4,  4,  4,  8,  4,  4,  4,  4,  4,  4,  4,  8,  4, // 4x
+     4,  4,  4,  4,  4,  4,  8,  4,  4,  4,  4,  4,  4,  4,  8,  4, // 5x
+     4,  4,  4,  4,  4,  4,  8,  4,  4,  4,  4,  4,  4,  4,  8,  4, // 6x
+     8,  8,  8,  8,  8,  8,  4,  8,  4,  4,  4,  4,  4,  4,  8,  4, // 7x
+     4,  4,  4,  4,  4,  4,  8,  4,  4,  4,  4,  4,  4,  4,  8,  4, // 8x
+     4,  4,  4,  4,  4,  4,  8,  4,  4,  4,  4,  4,  4,  4,  8,  4, // 9x
+     4,  4,  4,  4,  4,  4,  8,  4,  4,  4,  4,  4,  4,  4,  8,  4, // Ax
+     4,  4,  4,  4,  4,  4,  8,  4,  4,  4,  4,  4,  4,  4,  8,  4, // Bx
+     8, 12, 12, 16, 12, 16,  8, 16,  8, 16, 12,  4, 12, 24,  8, 16, // Cx
+     8, 12, 12,  0, 12, 16,  8, 16,  8, 16, 12,  0, 12,  0,  8, 16, // Dx
+    12, 12,  8,  0,  0, 16,  8, 16, 16,  4, 16,  0,  0,  0,  8, 16, // Ex
+    12, 12,  8,  4,  0, 16,  8, 16, 12,  8, 16,  4,  0,  0,  8, 16, // Fx
+];
+
+/// T-cycles the instruction takes, `branch_taken` only matters for JR/JP/CALL/RET with a condition
+pub fn cycles(opcode: u8, prefixed: bool, branch_taken: bool) -> u8 {
+    if prefixed {
+        // The prefix byte is included, BIT only reads [HL] so it's faster than the others
+        return match (opcode & 0x07, opcode >> 6) {
+            (0x06, 1) => 12,
+            (0x06, _) => 16,
+            _ => 8,
+        };
+    }
+
+    let extra = match opcode {
+        0x20 | 0x28 | 0x30 | 0x38 | 0xC2 | 0xCA | 0xD2 | 0xDA if branch_taken => 4,
+        0xC0 | 0xC8 | 0xD0 | 0xD8 | 0xC4 | 0xCC | 0xD4 | 0xDC if branch_taken => 12,
+        _ => 0,
+    };
+    UNPREFIXED[opcode as usize] + extra
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_instructions() {
+        assert_eq!(cycles(0x00, false, false), 4);
+        assert_eq!(cycles(0x01, false, false), 12);
+        assert_eq!(cycles(0x08, false, false), 20);
+        assert_eq!(cycles(0xCD, false, false), 24);
+        assert_eq!(cycles(0xC9, false, false), 16);
+    }
+
+    #[test]
+    fn taken_branches_cost_more() {
+        // JR NZ, JP Z, RET NC and CALL C
+        assert_eq!((cycles(0x20, false, false), cycles(0x20, false, true)), (8, 12));
+        assert_eq!((cycles(0xCA, false, false), cycles(0xCA, false, true)), (12, 16));
+        assert_eq!((cycles(0xD0, false, false), cycles(0xD0, false, true)), (8, 20));
+        assert_eq!((cycles(0xDC, false, false), cycles(0xDC, false, true)), (12, 24));
+        // Unconditional ones don't care
+        assert_eq!(cycles(0x18, false, true), 12);
+    }
+
+    #[test]
+    fn prefixed_instructions() {
+        assert_eq!(cycles(0x37, true, false), 8);
+        assert_eq!(cycles(0x46, true, false), 12);
+        assert_eq!(cycles(0x86, true, false), 16);
+    }
+}
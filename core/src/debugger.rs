@@ -0,0 +1,769 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::callstack::CallStack;
+use crate::cpu::CPU;
+use crate::cpu::disassembler;
+use crate::error::EmuError;
+use crate::memory::MemoryAccess;
+use crate::profiler::Profiler;
+
+// How long a step over/out or run to cursor may take before it gives up
+const MAX_GOAL_INSTRUCTIONS: u32 = 10_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Register {
+    A, F, B, C, D, E, H, L,
+    AF, BC, DE, HL, SP, PC,
+    ZF, NF, HF, CF,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    Register(Register),
+    Constant(u16),
+    /// The byte at the address the inner operand evaluates to
+    Memory(Box<Operand>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+/// Breakpoint condition, e.g. `A == $10 && !ZF` or `[HL] >= 0x80`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    /// True when the operand isn't zero
+    Value(Operand),
+    Not(Box<Condition>),
+    Compare(Operand, Comparison, Operand),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+}
+
+#[derive(Clone)]
+pub struct Breakpoint {
+    pub address: u16,
+    pub condition: Option<Condition>,
+    // Kept around so the UI can show what was typed in
+    pub condition_text: String,
+    pub enabled: bool,
+    pub hits: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WatchKind {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+#[derive(Clone)]
+pub struct Watchpoint {
+    pub start: u16,
+    // Inclusive
+    pub end: u16,
+    pub kind: WatchKind,
+    pub enabled: bool,
+    pub hits: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StopReason {
+    Breakpoint(u16),
+    Watchpoint { address: u16, write: bool },
+    /// A step over, step out or run to cursor reached its target
+    GoalReached,
+    /// The CPU hit an illegal opcode with the Break policy
+    IllegalOpcode(u16),
+    /// Step over/out took longer than `MAX_GOAL_INSTRUCTIONS`, most likely it never returns
+    GaveUp,
+}
+
+// What the debugger runs towards while it isn't paused
+#[derive(Clone)]
+enum Goal {
+    Address(u16),
+    // Stops after the RET that leaves the shadow call stack shallower than `depth`. Without
+    // any frames to go by it's the first RET that takes SP above `stack_pointer`.
+    Return { depth: usize, stack_pointer: u16 },
+}
+
+#[derive(Clone)]
+pub struct Debugger {
+    pub breakpoints: Vec<Breakpoint>,
+    pub watchpoints: Vec<Watchpoint>,
+    pub paused: bool,
+    pub last_stop: Option<StopReason>,
+    pub call_stack: CallStack,
+    /// Only there while profiling, so it costs nothing otherwise
+    pub profiler: Option<Box<Profiler>>,
+    goal: Option<Goal>,
+    goal_instructions: u32,
+}
+
+impl Default for Debugger {
+    fn default() -> Debugger {
+        Debugger::new()
+    }
+}
+
+impl Debugger {
+    pub fn new() -> Debugger {
+        Debugger {
+            breakpoints: Vec::new(),
+            watchpoints: Vec::new(),
+            paused: false,
+            last_stop: None,
+            call_stack: CallStack::new(),
+            profiler: None,
+            goal: None,
+            goal_instructions: 0,
+        }
+    }
+
+    pub fn add_breakpoint(&mut self, address: u16, condition_text: &str) -> Result<(), String> {
+        let condition_text = condition_text.trim();
+        let condition = if condition_text.is_empty() {
+            None
+        } else {
+            Some(Condition::parse(condition_text)?)
+        };
+
+        self.breakpoints.push(Breakpoint {
+            address,
+            condition,
+            condition_text: condition_text.to_string(),
+            enabled: true,
+            hits: 0,
+        });
+        Ok(())
+    }
+
+    /// Adds an unconditional breakpoint or removes every breakpoint on the address
+    pub fn toggle_breakpoint(&mut self, address: u16) {
+        if self.has_breakpoint(address) {
+            self.breakpoints.retain(|breakpoint| breakpoint.address != address);
+        } else {
+            self.breakpoints.push(Breakpoint {
+                address,
+                condition: None,
+                condition_text: String::new(),
+                enabled: true,
+                hits: 0,
+            });
+        }
+    }
+
+    pub fn has_breakpoint(&self, address: u16) -> bool {
+        self.breakpoints.iter().any(|breakpoint| breakpoint.address == address)
+    }
+
+    pub fn add_watchpoint(&mut self, start: u16, end: u16, kind: WatchKind) {
+        self.watchpoints.push(Watchpoint {
+            start: start.min(end),
+            end: start.max(end),
+            kind,
+            enabled: true,
+            hits: 0,
+        });
+    }
+
+    /// True while a step over, step out or run to cursor is in progress
+    pub fn has_goal(&self) -> bool {
+        self.goal.is_some()
+    }
+
+    // Anything that could stop the CPU after an instruction, without any there's nothing to check
+    fn has_stops(&self) -> bool {
+        self.goal.is_some()
+            || self.breakpoints.iter().any(|breakpoint| breakpoint.enabled)
+            || self.watchpoints.iter().any(|watchpoint| watchpoint.enabled)
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+        self.goal = None;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+        self.last_stop = None;
+    }
+
+    /// Steps over CALL and RST, anything else is a normal single step
+    pub fn step_over(&mut self, cpu: &mut CPU) -> Result<(), EmuError> {
+        let pc = cpu.registry.pc;
+        let opcode = cpu.memory.peek(pc);
+        if is_call(opcode) {
+            let next = pc.wrapping_add(disassembler::instruction_length(opcode) as u16);
+            self.start_goal(Goal::Address(next));
+            Ok(())
+        } else {
+            self.single_step(cpu)
+        }
+    }
+
+    /// Runs until the current function returns to its caller
+    pub fn step_out(&mut self, cpu: &CPU) {
+        self.start_goal(Goal::Return { depth: self.call_stack.frames.len(), stack_pointer: cpu.registry.sp });
+    }
+
+    pub fn run_to(&mut self, address: u16) {
+        self.start_goal(Goal::Address(address));
+    }
+
+    fn start_goal(&mut self, goal: Goal) {
+        self.goal = Some(goal);
+        self.goal_instructions = 0;
+        self.resume();
+    }
+
+    /// Executes exactly one instruction and stays paused, breakpoints don't matter here
+    pub fn single_step(&mut self, cpu: &mut CPU) -> Result<(), EmuError> {
+        self.step(cpu)?;
+        self.paused = true;
+        Ok(())
+    }
+
+    /// Executes one instruction while running, pauses and returns the reason if anything wants to stop
+    pub fn step(&mut self, cpu: &mut CPU) -> Result<Option<StopReason>, EmuError> {
+        cpu.memory.record_accesses = self.profiler.is_some() || self.watchpoints.iter().any(|watchpoint| watchpoint.enabled);
+        cpu.memory.take_accesses();
+
+        let pc = cpu.registry.pc;
+        let stack_pointer = cpu.registry.sp;
+        let cycles = cpu.cycles;
+        let opcode = cpu.memory.peek(pc);
+        let opcode_length = disassembler::instruction_length(opcode) as u16;
+
+        let stop = match cpu.step() {
+            Ok(()) => {
+                let accesses = cpu.memory.take_accesses();
+                if let Some(profiler) = &mut self.profiler {
+                    profiler.record(pc, opcode_length, cpu.cycles - cycles, &accesses);
+                }
+                self.call_stack.update(cpu, pc, opcode, stack_pointer);
+                self.check_stop(cpu, pc, opcode, opcode_length, &accesses)
+            }
+            Err(err) => {
+                self.pause();
+                return Err(err);
+            }
+        };
+
+        if let Some(reason) = stop {
+            self.pause();
+            self.last_stop = Some(reason);
+        }
+        Ok(stop)
+    }
+
+    fn check_stop(&mut self, cpu: &mut CPU, pc: u16, opcode: u8, opcode_length: u16, accesses: &[MemoryAccess]) -> Option<StopReason> {
+        if cpu.break_requested {
+            cpu.break_requested = false;
+            return Some(StopReason::IllegalOpcode(cpu.registry.pc));
+        }
+        if !self.has_stops() {
+            return None;
+        }
+
+        for access in accesses {
+            // Fetching the instruction itself isn't interesting
+            if !access.write && access.address.wrapping_sub(pc) < opcode_length {
+                continue;
+            }
+            for watchpoint in self.watchpoints.iter_mut().filter(|watchpoint| watchpoint.enabled) {
+                let kind_matches = match watchpoint.kind {
+                    WatchKind::Read => !access.write,
+                    WatchKind::Write => access.write,
+                    WatchKind::ReadWrite => true,
+                };
+                if kind_matches && (watchpoint.start..=watchpoint.end).contains(&access.address) {
+                    watchpoint.hits += 1;
+                    return Some(StopReason::Watchpoint { address: access.address, write: access.write });
+                }
+            }
+        }
+
+        if let Some(goal) = &self.goal {
+            let reached = match goal {
+                Goal::Address(address) => cpu.registry.pc == *address,
+                Goal::Return { depth: 0, stack_pointer } => is_return(opcode) && cpu.registry.sp > *stack_pointer,
+                Goal::Return { depth, .. } => is_return(opcode) && self.call_stack.frames.len() < *depth,
+            };
+            if reached {
+                return Some(StopReason::GoalReached);
+            }
+            self.goal_instructions += 1;
+            if self.goal_instructions >= MAX_GOAL_INSTRUCTIONS {
+                return Some(StopReason::GaveUp);
+            }
+        }
+
+        let new_pc = cpu.registry.pc;
+        for index in 0..self.breakpoints.len() {
+            let breakpoint = &self.breakpoints[index];
+            if !breakpoint.enabled || breakpoint.address != new_pc {
+                continue;
+            }
+            let triggered = match &breakpoint.condition {
+                Some(condition) => condition.evaluate(cpu),
+                None => true,
+            };
+            if triggered {
+                self.breakpoints[index].hits += 1;
+                return Some(StopReason::Breakpoint(new_pc));
+            }
+        }
+
+        None
+    }
+}
+
+fn is_call(opcode: u8) -> bool {
+    matches!(opcode, 0xC4 | 0xCC | 0xCD | 0xD4 | 0xDC | 0xC7 | 0xCF | 0xD7 | 0xDF | 0xE7 | 0xEF | 0xF7 | 0xFF)
+}
+
+fn is_return(opcode: u8) -> bool {
+    matches!(opcode, 0xC0 | 0xC8 | 0xC9 | 0xD0 | 0xD8 | 0xD9)
+}
+
+impl Register {
+    fn parse(name: &str) -> Option<Register> {
+        let register = match name.to_ascii_uppercase().as_str() {
+            "A" => Register::A,
+            "F" => Register::F,
+            "B" => Register::B,
+            "C" => Register::C,
+            "D" => Register::D,
+            "E" => Register::E,
+            "H" => Register::H,
+            "L" => Register::L,
+            "AF" => Register::AF,
+            "BC" => Register::BC,
+            "DE" => Register::DE,
+            "HL" => Register::HL,
+            "SP" => Register::SP,
+            "PC" => Register::PC,
+            "ZF" => Register::ZF,
+            "NF" => Register::NF,
+            "HF" => Register::HF,
+            "CF" => Register::CF,
+            _ => return None,
+        };
+        Some(register)
+    }
+
+    fn value(&self, cpu: &mut CPU) -> u16 {
+        let registry = &mut cpu.registry;
+        match self {
+            Register::A => registry.a as u16,
+            Register::F => registry.f.get_flags() as u16,
+            Register::B => registry.b as u16,
+            Register::C => registry.c as u16,
+            Register::D => registry.d as u16,
+            Register::E => registry.e as u16,
+            Register::H => registry.h as u16,
+            Register::L => registry.l as u16,
+            Register::AF => registry.get_af(),
+            Register::BC => registry.get_bc(),
+            Register::DE => registry.get_de(),
+            Register::HL => registry.get_hl(),
+            Register::SP => registry.sp,
+            Register::PC => registry.pc,
+            Register::ZF => registry.f.z_zero as u16,
+            Register::NF => registry.f.n_subtraction_bcd as u16,
+            Register::HF => registry.f.h_half_carry_bcd as u16,
+            Register::CF => registry.f.c_carry as u16,
+        }
+    }
+}
+
+impl Operand {
+    fn value(&self, cpu: &mut CPU) -> u16 {
+        match self {
+            Operand::Register(register) => register.value(cpu),
+            Operand::Constant(value) => *value,
+            Operand::Memory(address) => {
+                let address = address.value(cpu);
+                cpu.memory.read_byte(address) as u16
+            }
+        }
+    }
+}
+
+impl Condition {
+    /// Parses conditions made of registers (`A`, `HL`, ...), flags (`ZF`, `NF`, `HF`, `CF`),
+    /// numbers (`$FF`, `0xFF`, `255`), memory (`[HL]`, `[$C000]`), comparisons, `!`, `&&`, `||` and parentheses
+    pub fn parse(text: &str) -> Result<Condition, String> {
+        let tokens = tokenize(text)?;
+        let mut parser = Parser { tokens, position: 0 };
+        let condition = parser.or()?;
+        match parser.peek() {
+            None => Ok(condition),
+            Some(token) => Err(format!("Unexpected '{}'", token)),
+        }
+    }
+
+    pub fn evaluate(&self, cpu: &mut CPU) -> bool {
+        match self {
+            Condition::Value(operand) => operand.value(cpu) != 0,
+            Condition::Not(condition) => !condition.evaluate(cpu),
+            Condition::Compare(left, comparison, right) => {
+                let left = left.value(cpu);
+                let right = right.value(cpu);
+                match comparison {
+                    Comparison::Equal => left == right,
+                    Comparison::NotEqual => left != right,
+                    Comparison::Less => left < right,
+                    Comparison::LessEqual => left <= right,
+                    Comparison::Greater => left > right,
+                    Comparison::GreaterEqual => left >= right,
+                }
+            }
+            Condition::And(left, right) => left.evaluate(cpu) && right.evaluate(cpu),
+            Condition::Or(left, right) => left.evaluate(cpu) || right.evaluate(cpu),
+        }
+    }
+}
+
+/// Parses `$FF`, `0xFF` and plain decimal numbers
+pub fn parse_number(text: &str) -> Option<u16> {
+    if let Some(hex) = text.strip_prefix('$').or_else(|| text.strip_prefix("0x")).or_else(|| text.strip_prefix("0X")) {
+        u16::from_str_radix(hex, 16).ok()
+    } else {
+        text.parse().ok()
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<String>, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_alphanumeric() || c == '$' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '$') {
+                i += 1;
+            }
+            tokens.push(chars[start..i].iter().collect());
+        } else {
+            let pair: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+            if ["==", "!=", "<=", ">=", "&&", "||"].contains(&pair.as_str()) {
+                tokens.push(pair);
+                i += 2;
+            } else if "<>![]()".contains(c) {
+                tokens.push(c.to_string());
+                i += 1;
+            } else {
+                return Err(format!("Unexpected character '{}'", c));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<String>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.position).map(|token| token.as_str())
+    }
+
+    fn next(&mut self) -> Option<String> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), String> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(format!("Expected '{}' but found '{}'", expected, token)),
+            None => Err(format!("Expected '{}'", expected)),
+        }
+    }
+
+    fn or(&mut self) -> Result<Condition, String> {
+        let mut condition = self.and()?;
+        while self.peek() == Some("||") {
+            self.next();
+            condition = Condition::Or(Box::new(condition), Box::new(self.and()?));
+        }
+        Ok(condition)
+    }
+
+    fn and(&mut self) -> Result<Condition, String> {
+        let mut condition = self.unary()?;
+        while self.peek() == Some("&&") {
+            self.next();
+            condition = Condition::And(Box::new(condition), Box::new(self.unary()?));
+        }
+        Ok(condition)
+    }
+
+    fn unary(&mut self) -> Result<Condition, String> {
+        match self.peek() {
+            Some("!") => {
+                self.next();
+                Ok(Condition::Not(Box::new(self.unary()?)))
+            }
+            Some("(") => {
+                self.next();
+                let condition = self.or()?;
+                self.expect(")")?;
+                Ok(condition)
+            }
+            _ => self.comparison(),
+        }
+    }
+
+    fn comparison(&mut self) -> Result<Condition, String> {
+        let left = self.operand()?;
+        let comparison = match self.peek() {
+            Some("==") => Comparison::Equal,
+            Some("!=") => Comparison::NotEqual,
+            Some("<") => Comparison::Less,
+            Some("<=") => Comparison::LessEqual,
+            Some(">") => Comparison::Greater,
+            Some(">=") => Comparison::GreaterEqual,
+            _ => return Ok(Condition::Value(left)),
+        };
+        self.next();
+        let right = self.operand()?;
+        Ok(Condition::Compare(left, comparison, right))
+    }
+
+    fn operand(&mut self) -> Result<Operand, String> {
+        let token = self.next().ok_or_else(|| "Expected a register, flag, number or [address]".to_string())?;
+        if token == "[" {
+            let address = self.operand()?;
+            self.expect("]")?;
+            return Ok(Operand::Memory(Box::new(address)));
+        }
+        if let Some(register) = Register::parse(&token) {
+            return Ok(Operand::Register(register));
+        }
+        parse_number(&token)
+            .map(Operand::Constant)
+            .ok_or_else(|| format!("'{}' isn't a register, flag or number", token))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn register(register: Register) -> Box<Operand> {
+        Box::new(Operand::Register(register))
+    }
+
+    fn compare(left: Register, comparison: Comparison, right: u16) -> Condition {
+        Condition::Compare(Operand::Register(left), comparison, Operand::Constant(right))
+    }
+
+    // A CPU past the boot ROM with `program` at 0x0100
+    fn cpu_running(program: &[u8]) -> CPU {
+        let mut cpu = CPU::new();
+        cpu.memory.load_rom(&[0; 0x8000]).unwrap();
+        cpu.memory.in_bootrom = false;
+        cpu.memory.memory[0x0100..0x0100 + program.len()].copy_from_slice(program);
+        cpu.registry.pc = 0x0100;
+        cpu
+    }
+
+    fn run(debugger: &mut Debugger, cpu: &mut CPU, instructions: usize) -> Option<StopReason> {
+        for _ in 0..instructions {
+            if let Some(reason) = debugger.step(cpu).unwrap() {
+                return Some(reason);
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn parses_comparisons_and_flags() {
+        let expected = Condition::And(
+            Box::new(compare(Register::A, Comparison::Equal, 0x10)),
+            Box::new(Condition::Not(Box::new(Condition::Value(Operand::Register(Register::ZF))))),
+        );
+        assert_eq!(Condition::parse("A == $10 && !ZF"), Ok(expected));
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let expected = Condition::Or(
+            Box::new(compare(Register::A, Comparison::Equal, 1)),
+            Box::new(Condition::And(
+                Box::new(compare(Register::B, Comparison::Less, 2)),
+                Box::new(compare(Register::C, Comparison::GreaterEqual, 3)),
+            )),
+        );
+        assert_eq!(Condition::parse("a == 1 || b < 2 && c >= 3"), Ok(expected.clone()));
+        assert_eq!(Condition::parse("a == 1 || (b < 2 && c >= 3)"), Ok(expected));
+    }
+
+    #[test]
+    fn parses_memory_operands() {
+        let expected = Condition::Compare(Operand::Memory(register(Register::HL)), Comparison::GreaterEqual, Operand::Constant(0x80));
+        assert_eq!(Condition::parse("[HL] >= 0x80"), Ok(expected));
+        assert_eq!(
+            Condition::parse("[$C000] != 255"),
+            Ok(Condition::Compare(Operand::Memory(Box::new(Operand::Constant(0xC000))), Comparison::NotEqual, Operand::Constant(255)))
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_conditions() {
+        assert!(Condition::parse("A ==").is_err());
+        assert!(Condition::parse("(A == 1").is_err());
+        assert!(Condition::parse("[HL == 1").is_err());
+        assert!(Condition::parse("A # 1").is_err());
+        assert!(Condition::parse("Q == 1").is_err());
+        assert_eq!(Condition::parse("A B"), Err("Unexpected 'B'".to_string()));
+    }
+
+    #[test]
+    fn parses_numbers() {
+        assert_eq!(parse_number("$FF"), Some(0xFF));
+        assert_eq!(parse_number("0xC000"), Some(0xC000));
+        assert_eq!(parse_number("42"), Some(42));
+        assert_eq!(parse_number("$10000"), None);
+        assert_eq!(parse_number("HL"), None);
+    }
+
+    #[test]
+    fn evaluates_against_the_cpu() {
+        let mut cpu = cpu_running(&[]);
+        cpu.registry.a = 0x10;
+        cpu.registry.set_hl(0xC000);
+        cpu.memory.memory[0xC000] = 0x90;
+        assert!(Condition::parse("A == $10 && [HL] >= $80").unwrap().evaluate(&mut cpu));
+        assert!(!Condition::parse("A != $10 || ZF").unwrap().evaluate(&mut cpu));
+    }
+
+    #[test]
+    fn stops_on_breakpoints() {
+        let mut cpu = cpu_running(&[0x00; 8]);
+        let mut debugger = Debugger::new();
+        debugger.toggle_breakpoint(0x0103);
+        assert_eq!(run(&mut debugger, &mut cpu, 8), Some(StopReason::Breakpoint(0x0103)));
+        assert!(debugger.paused);
+        assert_eq!(debugger.breakpoints[0].hits, 1);
+    }
+
+    #[test]
+    fn skips_breakpoints_whose_condition_is_false() {
+        let mut cpu = cpu_running(&[0x00; 8]);
+        let mut debugger = Debugger::new();
+        debugger.add_breakpoint(0x0103, "A == 1").unwrap();
+        assert_eq!(run(&mut debugger, &mut cpu, 6), None);
+        assert_eq!(cpu.registry.pc, 0x0106);
+    }
+
+    #[test]
+    fn stops_on_watched_writes() {
+        // LD [HL], A
+        let mut cpu = cpu_running(&[0x00, 0x77, 0x00]);
+        cpu.registry.set_hl(0xC000);
+        let mut debugger = Debugger::new();
+        debugger.add_watchpoint(0xC000, 0xC0FF, WatchKind::Write);
+        assert_eq!(run(&mut debugger, &mut cpu, 3), Some(StopReason::Watchpoint { address: 0xC000, write: true }));
+        assert_eq!(cpu.registry.pc, 0x0102);
+    }
+
+    #[test]
+    fn run_to_reaches_its_goal() {
+        let mut cpu = cpu_running(&[0x00; 8]);
+        let mut debugger = Debugger::new();
+        debugger.run_to(0x0105);
+        assert!(debugger.has_goal());
+        assert_eq!(run(&mut debugger, &mut cpu, 8), Some(StopReason::GoalReached));
+        assert!(!debugger.has_goal());
+    }
+
+    #[test]
+    fn step_over_lands_after_calls_and_rsts() {
+        // CALL $0110, RST 08, NOP, with $0110 and $0008 going through a NOP before their RET
+        let mut cpu = cpu_running(&[0xCD, 0x10, 0x01, 0xCF, 0x00]);
+        cpu.memory.memory[0x0110..0x0112].copy_from_slice(&[0x00, 0xC9]);
+        cpu.memory.memory[0x0008..0x000A].copy_from_slice(&[0x00, 0xC9]);
+        cpu.registry.sp = 0xDFFE;
+        let mut debugger = Debugger::new();
+
+        debugger.step_over(&mut cpu).unwrap();
+        assert_eq!(run(&mut debugger, &mut cpu, 8), Some(StopReason::GoalReached));
+        assert_eq!(cpu.registry.pc, 0x0103);
+        debugger.step_over(&mut cpu).unwrap();
+        assert_eq!(run(&mut debugger, &mut cpu, 8), Some(StopReason::GoalReached));
+        assert_eq!((cpu.registry.pc, cpu.registry.sp), (0x0104, 0xDFFE));
+
+        // Anything else is a single step that stays paused
+        debugger.step_over(&mut cpu).unwrap();
+        assert_eq!(cpu.registry.pc, 0x0105);
+        assert!(debugger.paused && !debugger.has_goal());
+    }
+
+    #[test]
+    fn step_out_of_a_nested_call_stops_in_its_caller() {
+        // CALL $0110, at $0110 CALL $0120 and RET, at $0120 NOP and RET
+        let mut program = [0x00; 0x22];
+        program[..3].copy_from_slice(&[0xCD, 0x10, 0x01]);
+        program[0x10..0x14].copy_from_slice(&[0xCD, 0x20, 0x01, 0xC9]);
+        program[0x20..0x22].copy_from_slice(&[0x00, 0xC9]);
+        let mut cpu = cpu_running(&program);
+        cpu.registry.sp = 0xDFFE;
+        let mut debugger = Debugger::new();
+        run(&mut debugger, &mut cpu, 2);
+        assert_eq!(cpu.registry.pc, 0x0120);
+        assert_eq!(debugger.call_stack.frames.len(), 2);
+
+        debugger.step_out(&cpu);
+        assert_eq!(run(&mut debugger, &mut cpu, 8), Some(StopReason::GoalReached));
+        assert_eq!((cpu.registry.pc, cpu.registry.sp), (0x0113, 0xDFFC));
+    }
+
+    #[test]
+    fn step_out_waits_for_the_callers_ret() {
+        // CALL $0110, then at $0110 two DEC SP and two INC SP standing in for PUSH and POP,
+        // CALL $0120, RET and at $0120 another RET
+        let mut program = [0x00; 0x21];
+        program[..3].copy_from_slice(&[0xCD, 0x10, 0x01]);
+        program[0x10..0x18].copy_from_slice(&[0x3B, 0x3B, 0x33, 0x33, 0xCD, 0x20, 0x01, 0xC9]);
+        program[0x20] = 0xC9;
+        let mut cpu = cpu_running(&program);
+        cpu.registry.sp = 0xDFFE;
+        let mut debugger = Debugger::new();
+        run(&mut debugger, &mut cpu, 3);
+        assert_eq!((cpu.registry.pc, cpu.registry.sp), (0x0112, 0xDFFA));
+
+        // The inner RET leaves SP above where it was when stepping out, that's not the way out
+        debugger.step_out(&cpu);
+        assert_eq!(run(&mut debugger, &mut cpu, 8), Some(StopReason::GoalReached));
+        assert_eq!((cpu.registry.pc, cpu.registry.sp), (0x0103, 0xDFFE));
+    }
+
+    #[test]
+    fn nothing_stops_without_breakpoints_watchpoints_or_goals() {
+        let mut cpu = cpu_running(&[0x00; 8]);
+        let mut debugger = Debugger::new();
+        assert_eq!(run(&mut debugger, &mut cpu, 8), None);
+        assert!(!cpu.memory.record_accesses);
+    }
+}
@@ -0,0 +1,45 @@
+use core::fmt;
+
+use crate::cpu::instructions::{Instructions, LogicTargets};
+
+/// Everything the emulator core can fail with
+#[derive(Debug)]
+pub enum EmuError {
+    /// An undefined opcode was fetched while the policy is set to Error
+    IllegalOpcode { opcode: u8, address: u16 },
+    /// The instruction decoded fine but isn't executed by the core (yet)
+    Unimplemented(Instructions),
+    /// The instruction was decoded with an operand it can't work on
+    InvalidTarget { instruction: &'static str, target: LogicTargets },
+    /// The ROM is empty
+    EmptyRom,
+    /// The save state was written by a different version of the format
+    SaveStateVersion { found: u16, expected: u16 },
+    /// The data isn't a save state or is cut off
+    InvalidSaveState(&'static str),
+    /// The save state was made with a different ROM than the one loaded
+    SaveStateRomMismatch { found: u16, expected: u16 },
+    /// The movie was written by a different version of the format
+    MovieVersion { found: u16, expected: u16 },
+    /// The data isn't a movie or is cut off
+    InvalidMovie(&'static str),
+    /// The movie was recorded with a different ROM than the one loaded
+    MovieRomMismatch { found: u16, expected: u16 },
+}
+
+impl fmt::Display for EmuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EmuError::IllegalOpcode { opcode, address } => write!(f, "Illegal opcode {:02X} at {:04X}", opcode, address),
+            EmuError::Unimplemented(instruction) => write!(f, "Unimplemented instruction {:?}", instruction),
+            EmuError::InvalidTarget { instruction, target } => write!(f, "Invalid target {:?} for {}", target, instruction),
+            EmuError::EmptyRom => write!(f, "The ROM is empty"),
+            EmuError::SaveStateVersion { found, expected } => write!(f, "Save state is version {} but only version {} can be loaded", found, expected),
+            EmuError::InvalidSaveState(reason) => write!(f, "Invalid save state: {}", reason),
+            EmuError::SaveStateRomMismatch { found, expected } => write!(f, "Save state was made with ROM checksum {:04X}, the loaded ROM has {:04X}", expected, found),
+            EmuError::MovieVersion { found, expected } => write!(f, "Movie is version {} but only version {} can be played", found, expected),
+            EmuError::InvalidMovie(reason) => write!(f, "Invalid movie: {}", reason),
+            EmuError::MovieRomMismatch { found, expected } => write!(f, "Movie was recorded with ROM checksum {:04X}, the loaded ROM has {:04X}", expected, found),
+        }
+    }
+}
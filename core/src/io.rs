@@ -0,0 +1,194 @@
+// Names and bit layouts of the hardware registers in 0xFF00-0xFF7F and IE, for debuggers to decode.
+// Only the description lives here, the registers themselves are plain bytes in `Memory`.
+
+/// A run of bits inside a register
+pub struct Field {
+    pub name: &'static str,
+    pub shift: u8,
+    pub width: u8,
+    /// Names for each value, empty when the field is just a number
+    pub values: &'static [&'static str],
+}
+
+impl Field {
+    const fn bit(name: &'static str, bit: u8) -> Field {
+        Field { name, shift: bit, width: 1, values: &[] }
+    }
+
+    const fn number(name: &'static str, shift: u8, width: u8) -> Field {
+        Field { name, shift, width, values: &[] }
+    }
+
+    const fn choice(name: &'static str, shift: u8, width: u8, values: &'static [&'static str]) -> Field {
+        Field { name, shift, width, values }
+    }
+
+    pub fn max(&self) -> u8 {
+        ((1u16 << self.width) - 1) as u8
+    }
+
+    pub fn get(&self, register: u8) -> u8 {
+        (register >> self.shift) & self.max()
+    }
+
+    /// `register` with this field replaced by `value`
+    pub fn set(&self, register: u8, value: u8) -> u8 {
+        let mask = self.max() << self.shift;
+        (register & !mask) | ((value << self.shift) & mask)
+    }
+
+    /// Name of the value, if it has one
+    pub fn value_name(&self, value: u8) -> Option<&'static str> {
+        self.values.get(value as usize).copied()
+    }
+}
+
+pub struct IoRegister {
+    pub address: u16,
+    pub name: &'static str,
+    pub fields: &'static [Field],
+}
+
+const fn register(address: u16, name: &'static str, fields: &'static [Field]) -> IoRegister {
+    IoRegister { address, name, fields }
+}
+
+const INTERRUPTS: &[Field] = &[
+    Field::bit("VBlank", 0),
+    Field::bit("STAT", 1),
+    Field::bit("Timer", 2),
+    Field::bit("Serial", 3),
+    Field::bit("Joypad", 4),
+];
+const SHADES: &[&str] = &["White", "Light grey", "Dark grey", "Black"];
+const PALETTE: &[Field] = &[
+    Field::choice("Colour 0", 0, 2, SHADES),
+    Field::choice("Colour 1", 2, 2, SHADES),
+    Field::choice("Colour 2", 4, 2, SHADES),
+    Field::choice("Colour 3", 6, 2, SHADES),
+];
+const DUTY_LENGTH: &[Field] = &[
+    Field::choice("Duty", 6, 2, &["12.5%", "25%", "50%", "75%"]),
+    Field::number("Length", 0, 6),
+];
+const ENVELOPE: &[Field] = &[
+    Field::number("Volume", 4, 4),
+    Field::choice("Envelope", 3, 1, &["Down", "Up"]),
+    Field::number("Pace", 0, 3),
+];
+const PERIOD_LOW: &[Field] = &[Field::number("Period low", 0, 8)];
+const PERIOD_HIGH: &[Field] = &[
+    Field::bit("Trigger", 7),
+    Field::bit("Length enable", 6),
+    Field::number("Period high", 0, 3),
+];
+const BYTE: &[Field] = &[Field::number("Value", 0, 8)];
+
+pub const REGISTERS: &[IoRegister] = &[
+    register(0xFF00, "P1", &[
+        Field::choice("Buttons", 5, 1, &["Selected", "Off"]),
+        Field::choice("D-pad", 4, 1, &["Selected", "Off"]),
+        // Read as 0 while pressed
+        Field::number("Inputs", 0, 4),
+    ]),
+    register(0xFF01, "SB", BYTE),
+    register(0xFF02, "SC", &[Field::bit("Transfer", 7), Field::choice("Clock", 0, 1, &["External", "Internal"])]),
+    register(0xFF04, "DIV", BYTE),
+    register(0xFF05, "TIMA", BYTE),
+    register(0xFF06, "TMA", BYTE),
+    register(0xFF07, "TAC", &[
+        Field::bit("Enable", 2),
+        Field::choice("Frequency", 0, 2, &["4096 Hz", "262144 Hz", "65536 Hz", "16384 Hz"]),
+    ]),
+    register(0xFF0F, "IF", INTERRUPTS),
+    register(0xFF10, "NR10", &[
+        Field::number("Sweep pace", 4, 3),
+        Field::choice("Direction", 3, 1, &["Up", "Down"]),
+        Field::number("Step", 0, 3),
+    ]),
+    register(0xFF11, "NR11", DUTY_LENGTH),
+    register(0xFF12, "NR12", ENVELOPE),
+    register(0xFF13, "NR13", PERIOD_LOW),
+    register(0xFF14, "NR14", PERIOD_HIGH),
+    register(0xFF16, "NR21", DUTY_LENGTH),
+    register(0xFF17, "NR22", ENVELOPE),
+    register(0xFF18, "NR23", PERIOD_LOW),
+    register(0xFF19, "NR24", PERIOD_HIGH),
+    register(0xFF1A, "NR30", &[Field::bit("DAC on", 7)]),
+    register(0xFF1B, "NR31", &[Field::number("Length", 0, 8)]),
+    register(0xFF1C, "NR32", &[Field::choice("Output level", 5, 2, &["Mute", "100%", "50%", "25%"])]),
+    register(0xFF1D, "NR33", PERIOD_LOW),
+    register(0xFF1E, "NR34", PERIOD_HIGH),
+    register(0xFF20, "NR41", &[Field::number("Length", 0, 6)]),
+    register(0xFF21, "NR42", ENVELOPE),
+    register(0xFF22, "NR43", &[
+        Field::number("Clock shift", 4, 4),
+        Field::choice("LFSR width", 3, 1, &["15 bit", "7 bit"]),
+        Field::number("Clock divider", 0, 3),
+    ]),
+    register(0xFF23, "NR44", &[Field::bit("Trigger", 7), Field::bit("Length enable", 6)]),
+    register(0xFF24, "NR50", &[
+        Field::bit("VIN left", 7),
+        Field::number("Left volume", 4, 3),
+        Field::bit("VIN right", 3),
+        Field::number("Right volume", 0, 3),
+    ]),
+    register(0xFF25, "NR51", &[
+        Field::bit("CH4 left", 7),
+        Field::bit("CH3 left", 6),
+        Field::bit("CH2 left", 5),
+        Field::bit("CH1 left", 4),
+        Field::bit("CH4 right", 3),
+        Field::bit("CH3 right", 2),
+        Field::bit("CH2 right", 1),
+        Field::bit("CH1 right", 0),
+    ]),
+    register(0xFF26, "NR52", &[
+        Field::bit("Audio on", 7),
+        Field::bit("CH4 on", 3),
+        Field::bit("CH3 on", 2),
+        Field::bit("CH2 on", 1),
+        Field::bit("CH1 on", 0),
+    ]),
+    register(0xFF40, "LCDC", &[
+        Field::bit("LCD enable", 7),
+        Field::choice("Window map", 6, 1, &["9800", "9C00"]),
+        Field::bit("Window enable", 5),
+        Field::choice("Tile data", 4, 1, &["8800", "8000"]),
+        Field::choice("BG map", 3, 1, &["9800", "9C00"]),
+        Field::choice("OBJ size", 2, 1, &["8x8", "8x16"]),
+        Field::bit("OBJ enable", 1),
+        Field::bit("BG enable", 0),
+    ]),
+    register(0xFF41, "STAT", &[
+        Field::bit("LYC interrupt", 6),
+        Field::bit("Mode 2 interrupt", 5),
+        Field::bit("Mode 1 interrupt", 4),
+        Field::bit("Mode 0 interrupt", 3),
+        Field::bit("LYC == LY", 2),
+        Field::choice("Mode", 0, 2, &["HBlank", "VBlank", "OAM scan", "Drawing"]),
+    ]),
+    register(0xFF42, "SCY", BYTE),
+    register(0xFF43, "SCX", BYTE),
+    register(0xFF44, "LY", BYTE),
+    register(0xFF45, "LYC", BYTE),
+    register(0xFF46, "DMA", &[Field::number("Source page", 0, 8)]),
+    register(0xFF47, "BGP", PALETTE),
+    register(0xFF48, "OBP0", PALETTE),
+    register(0xFF49, "OBP1", PALETTE),
+    register(0xFF4A, "WY", BYTE),
+    register(0xFF4B, "WX", BYTE),
+    register(0xFF50, "BANK", &[Field::bit("Boot ROM off", 0)]),
+    register(0xFFFF, "IE", INTERRUPTS),
+];
+
+// The 32 4-bit samples of channel 3, two to a byte
+static WAVE_RAM: IoRegister = register(0xFF30, "WAVE", &[Field::number("Sample 1", 4, 4), Field::number("Sample 2", 0, 4)]);
+
+/// What's known about the register at `address`, None for unused addresses
+pub fn describe(address: u16) -> Option<&'static IoRegister> {
+    match address {
+        0xFF30..=0xFF3F => Some(&WAVE_RAM),
+        _ => REGISTERS.iter().find(|register| register.address == address),
+    }
+}
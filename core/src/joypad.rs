@@ -0,0 +1,28 @@
+// Pressed buttons are kept as one byte, directions in the low nibble and buttons in the high one.
+// That's the same order P1 reports them in, which keeps the register read trivial.
+pub const RIGHT: u8 = 1 << 0;
+pub const LEFT: u8 = 1 << 1;
+pub const UP: u8 = 1 << 2;
+pub const DOWN: u8 = 1 << 3;
+pub const A: u8 = 1 << 4;
+pub const B: u8 = 1 << 5;
+pub const SELECT: u8 = 1 << 6;
+pub const START: u8 = 1 << 7;
+
+pub const JOYPAD_REGISTER: u16 = 0xFF00;
+
+const SELECT_DIRECTIONS: u8 = 1 << 4;
+const SELECT_BUTTONS: u8 = 1 << 5;
+
+/// Value of P1 (0xFF00), `select` is what the game last wrote, everything is active low
+pub fn register_value(select: u8, pressed: u8) -> u8 {
+    let select = select & (SELECT_DIRECTIONS | SELECT_BUTTONS);
+    let mut lines = 0x0F;
+    if select & SELECT_DIRECTIONS == 0 {
+        lines &= !(pressed & 0x0F);
+    }
+    if select & SELECT_BUTTONS == 0 {
+        lines &= !(pressed >> 4);
+    }
+    0xC0 | select | lines
+}
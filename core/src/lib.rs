@@ -0,0 +1,37 @@
+#![no_std]
+
+// Everything that needs a heap (debugger, save states, rewind, movies, disassembly text)
+// is behind the default `alloc` feature, the rest only needs core.
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
+pub mod callstack;
+#[cfg(feature = "alloc")]
+pub mod capture;
+#[cfg(feature = "alloc")]
+pub mod cheats;
+pub mod cpu;
+#[cfg(feature = "alloc")]
+pub mod debugger;
+pub mod error;
+pub mod io;
+pub mod joypad;
+pub mod memory;
+#[cfg(feature = "alloc")]
+pub mod movie;
+pub mod pacing;
+#[cfg(feature = "alloc")]
+pub mod palette;
+pub mod ppu;
+#[cfg(feature = "alloc")]
+pub mod profiler;
+#[cfg(feature = "alloc")]
+pub mod rewind;
+#[cfg(feature = "alloc")]
+pub mod savestate;
+#[cfg(feature = "alloc")]
+pub mod search;
+#[cfg(feature = "alloc")]
+pub mod symbols;
+pub mod trace;
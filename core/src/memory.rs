@@ -0,0 +1,306 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "alloc")]
+use core::cell::RefCell;
+use core::fmt;
+
+#[cfg(feature = "alloc")]
+use crate::cheats::RomPatch;
+use crate::error::EmuError;
+use crate::joypad::{self, JOYPAD_REGISTER};
+
+const BOOTROM: &[u8; 256] = include_bytes!("../dmg_boot.bin");
+/// 0x0000-0x7FFF, without an MBC only banks 0 and 1 of the cartridge ROM are mapped
+pub const ROM_SIZE: usize = 0x8000;
+
+#[cfg(feature = "alloc")]
+const SERIAL_DATA: u16 = 0xFF01;
+const SERIAL_CONTROL: u16 = 0xFF02;
+const INTERRUPT_FLAG: usize = 0xFF0F;
+const SERIAL_INTERRUPT: u8 = 1 << 3;
+
+/// Where an address ends up on the bus
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Region {
+    /// There's no MBC yet, so 0x4000-0x7FFF always holds bank 1
+    Rom(u8),
+    Vram,
+    ExternalRam,
+    Wram,
+    // Mirrors 0xC000-0xDDFF
+    Echo,
+    Oam,
+    Unusable,
+    Io,
+    Hram,
+    InterruptEnable,
+}
+
+impl Region {
+    pub fn of(address: u16) -> Region {
+        match address {
+            0x0000..=0x3FFF => Region::Rom(0),
+            0x4000..=0x7FFF => Region::Rom(1),
+            0x8000..=0x9FFF => Region::Vram,
+            0xA000..=0xBFFF => Region::ExternalRam,
+            0xC000..=0xDFFF => Region::Wram,
+            0xE000..=0xFDFF => Region::Echo,
+            0xFE00..=0xFE9F => Region::Oam,
+            0xFEA0..=0xFEFF => Region::Unusable,
+            0xFF00..=0xFF7F => Region::Io,
+            0xFF80..=0xFFFE => Region::Hram,
+            0xFFFF => Region::InterruptEnable,
+        }
+    }
+
+    /// First address of the region
+    pub fn start(self) -> u16 {
+        match self {
+            Region::Rom(0) => 0x0000,
+            Region::Rom(_) => 0x4000,
+            Region::Vram => 0x8000,
+            Region::ExternalRam => 0xA000,
+            Region::Wram => 0xC000,
+            Region::Echo => 0xE000,
+            Region::Oam => 0xFE00,
+            Region::Unusable => 0xFEA0,
+            Region::Io => 0xFF00,
+            Region::Hram => 0xFF80,
+            Region::InterruptEnable => 0xFFFF,
+        }
+    }
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Region::Rom(bank) => write!(f, "ROM bank {}", bank),
+            Region::Vram => write!(f, "VRAM"),
+            Region::ExternalRam => write!(f, "External RAM"),
+            Region::Wram => write!(f, "WRAM"),
+            Region::Echo => write!(f, "Echo RAM"),
+            Region::Oam => write!(f, "OAM"),
+            Region::Unusable => write!(f, "Unusable"),
+            Region::Io => write!(f, "IO"),
+            Region::Hram => write!(f, "HRAM"),
+            Region::InterruptEnable => write!(f, "IE"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MemoryAccess {
+    pub address: u16,
+    pub write: bool,
+}
+
+#[derive(Clone)]
+pub struct Memory {
+    // The Memory of the Emulator
+    pub memory: [u8; 0x10000],
+    pub bootrom: [u8; 256],
+    pub in_bootrom: bool,
+    // Kept so a reset can put the cartridge back
+    #[cfg(feature = "alloc")]
+    pub rom: Vec<u8>,
+    // Buttons held right now, see joypad.rs for the bits
+    pub joypad: u8,
+    // Every byte the game sent over the link cable, test ROMs report their results this way
+    #[cfg(feature = "alloc")]
+    pub serial: Vec<u8>,
+    // Only collected while the debugger has watchpoints, reads go through &self so this needs a RefCell
+    #[cfg(feature = "alloc")]
+    pub record_accesses: bool,
+    #[cfg(feature = "alloc")]
+    accesses: RefCell<Vec<MemoryAccess>>,
+    // Enabled Game Genie codes, they change what ROM reads return without touching the ROM
+    #[cfg(feature = "alloc")]
+    pub rom_patches: Vec<RomPatch>,
+}
+
+impl Default for Memory {
+    fn default() -> Memory {
+        Memory::new()
+    }
+}
+
+impl Memory {
+    pub fn new() -> Memory {
+        let mut mem = Memory {
+            memory: [0; 0x10000],
+            bootrom: [0; 256],
+            in_bootrom: true,
+            #[cfg(feature = "alloc")]
+            rom: Vec::new(),
+            joypad: 0,
+            #[cfg(feature = "alloc")]
+            serial: Vec::new(),
+            #[cfg(feature = "alloc")]
+            record_accesses: false,
+            #[cfg(feature = "alloc")]
+            accesses: RefCell::new(Vec::new()),
+            #[cfg(feature = "alloc")]
+            rom_patches: Vec::new(),
+        };
+
+        mem.bootrom = *BOOTROM;
+
+        mem
+    }
+
+    pub fn load_rom(&mut self, file: &[u8]) -> Result<(), EmuError> {
+        if file.is_empty() {
+            return Err(EmuError::EmptyRom);
+        }
+
+        // Bigger carts need an MBC to reach the other banks, until there is one they stay unmapped
+        let mapped = file.len().min(ROM_SIZE);
+        self.memory[..mapped].copy_from_slice(&file[..mapped]);
+        #[cfg(feature = "alloc")]
+        {
+            self.rom = file.to_vec();
+        }
+        Ok(())
+    }
+
+    /// Puts banks 0 and 1 of the loaded ROM back over 0x0000-0x7FFF
+    #[cfg(feature = "alloc")]
+    pub(crate) fn map_rom(&mut self) {
+        let mapped = self.rom.len().min(ROM_SIZE);
+        self.memory[..ROM_SIZE].fill(0);
+        self.memory[..mapped].copy_from_slice(&self.rom[..mapped]);
+    }
+
+    /// Clears everything back to power on, the ROM stays
+    #[cfg(feature = "alloc")]
+    pub fn reset(&mut self) {
+        self.memory.fill(0);
+        self.map_rom();
+        self.in_bootrom = true;
+        self.joypad = 0;
+        self.serial.clear();
+    }
+
+    /// Clears everything back to power on. Without a copy of the ROM only what's past
+    /// the cartridge ROM is cleared, anything the game wrote below 0x8000 stays.
+    #[cfg(not(feature = "alloc"))]
+    pub fn reset(&mut self) {
+        self.memory[0x8000..].fill(0);
+        self.in_bootrom = true;
+        self.joypad = 0;
+    }
+
+    /// Hands out every access recorded since the last call
+    #[cfg(feature = "alloc")]
+    pub fn take_accesses(&self) -> Vec<MemoryAccess> {
+        self.accesses.take()
+    }
+
+    #[cfg(feature = "alloc")]
+    fn record(&self, address: u16, write: bool) {
+        if self.record_accesses {
+            self.accesses.borrow_mut().push(MemoryAccess { address, write });
+        }
+    }
+
+    #[cfg(not(feature = "alloc"))]
+    fn record(&self, _address: u16, _write: bool) {}
+
+    pub fn read_byte(&self, address: u16) -> u8 {
+        self.record(address, false);
+        self.peek(address)
+    }
+
+    /// What `read_byte` would return, without it showing up as an access
+    pub fn peek(&self, address: u16) -> u8 {
+        if address < 0x100 && self.in_bootrom {
+            return self.bootrom[address as usize];
+        }
+        if address == JOYPAD_REGISTER {
+            return joypad::register_value(self.memory[address as usize], self.joypad);
+        }
+        let value = self.memory[address as usize];
+        #[cfg(feature = "alloc")]
+        if address < 0x8000 {
+            if let Some(patch) = self.rom_patches.iter().find(|patch| patch.applies(address, value)) {
+                return patch.value;
+            }
+        }
+
+        value
+    }
+    
+    /// `peek` for the little endian word at `address`
+    pub fn peek_word(&self, address: u16) -> u16 {
+        u16::from_le_bytes([self.peek(address), self.peek(address.wrapping_add(1))])
+    }
+
+    pub fn read_word(&self, address: u16) -> u16 {
+        self.record(address, false);
+        self.record(address.wrapping_add(1), false);
+        self.peek_word(address)
+    }
+
+    pub fn write_word(&mut self, address: u16, value: u16) {
+        self.record(address, true);
+        self.record(address.wrapping_add(1), true);
+        self.memory[address as usize] = (value & 0xFF) as u8;
+        self.memory[address.wrapping_add(1) as usize] = ((value >> 8) & 0xFF) as u8;
+    }
+
+    pub fn write_byte(&mut self, address: u16, value: u8) {
+        self.record(address, true);
+        if address == SERIAL_CONTROL && value & 0x81 == 0x81 {
+            // Nothing is plugged in, so a transfer on the internal clock finishes right away
+            #[cfg(feature = "alloc")]
+            self.serial.push(self.memory[SERIAL_DATA as usize]);
+            self.memory[address as usize] = value & 0x7F;
+            self.memory[INTERRUPT_FLAG] |= SERIAL_INTERRUPT;
+            return;
+        }
+        self.memory[address as usize] = value;
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_rom_fills_the_rom_area() {
+        let mut memory = Memory::new();
+        memory.load_rom(&[0x12; ROM_SIZE]).unwrap();
+        assert_eq!(memory.memory[ROM_SIZE - 1], 0x12);
+        assert_eq!(memory.memory[ROM_SIZE], 0x00);
+    }
+
+    #[test]
+    fn load_rom_rejects_an_empty_rom() {
+        let mut memory = Memory::new();
+        assert!(matches!(memory.load_rom(&[]), Err(EmuError::EmptyRom)));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn banked_carts_map_their_first_two_banks() {
+        let rom = include_bytes!("../../tests/cpu_instrs.gb");
+        assert!(rom.len() > ROM_SIZE);
+        let mut memory = Memory::new();
+        memory.load_rom(rom).unwrap();
+        assert_eq!(memory.memory[..ROM_SIZE], rom[..ROM_SIZE]);
+        assert_eq!(memory.rom, rom);
+
+        memory.memory[0x4000] ^= 0xFF;
+        memory.reset();
+        assert_eq!(memory.memory[..ROM_SIZE], rom[..ROM_SIZE]);
+    }
+
+    #[test]
+    fn write_word_wraps_at_the_top_of_the_address_space() {
+        let mut memory = Memory::new();
+        memory.in_bootrom = false;
+        memory.write_word(0xFFFF, 0xBEEF);
+        assert_eq!(memory.memory[0xFFFF], 0xEF);
+        assert_eq!(memory.memory[0x0000], 0xBE);
+        assert_eq!(memory.read_word(0xFFFF), 0xBEEF);
+    }
+}
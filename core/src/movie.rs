@@ -0,0 +1,291 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::cpu::CPU;
+use crate::error::EmuError;
+
+// Movie file layout, everything little endian:
+//   "RGBM"  magic
+//   u16     format version
+//   u16     global checksum from the ROM header (0x014E-0x014F) the movie was recorded with
+//   u8      start, 0 = power on, 1 = save state
+//   u32     save state length followed by the save state, only when starting from a save state
+//   u32     frame count
+//   u8 * n  buttons held during each frame, bits as in joypad.rs
+//
+// Frame 0 is whatever is left of the frame the recording started in, every following one is a
+// full `FRAME_T_CYCLES` frame. The input is applied at the start of the frame and held until its end.
+const MAGIC: &[u8; 4] = b"RGBM";
+pub const MOVIE_VERSION: u16 = 1;
+
+const START_POWER_ON: u8 = 0;
+const START_SAVE_STATE: u8 = 1;
+
+pub enum MovieStart {
+    PowerOn,
+    SaveState(Vec<u8>),
+}
+
+pub struct Movie {
+    pub rom_checksum: u16,
+    pub start: MovieStart,
+    pub inputs: Vec<u8>,
+}
+
+impl Movie {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&MOVIE_VERSION.to_le_bytes());
+        data.extend_from_slice(&self.rom_checksum.to_le_bytes());
+        match &self.start {
+            MovieStart::PowerOn => data.push(START_POWER_ON),
+            MovieStart::SaveState(state) => {
+                data.push(START_SAVE_STATE);
+                data.extend_from_slice(&(state.len() as u32).to_le_bytes());
+                data.extend_from_slice(state);
+            }
+        }
+        data.extend_from_slice(&(self.inputs.len() as u32).to_le_bytes());
+        data.extend_from_slice(&self.inputs);
+        data
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Movie, EmuError> {
+        let mut reader = Reader { data };
+        if reader.take(4)? != MAGIC {
+            return Err(EmuError::InvalidMovie("not a movie"));
+        }
+        let version = reader.u16()?;
+        if version != MOVIE_VERSION {
+            return Err(EmuError::MovieVersion { found: version, expected: MOVIE_VERSION });
+        }
+        let rom_checksum = reader.u16()?;
+        let start = match reader.take(1)?[0] {
+            START_POWER_ON => MovieStart::PowerOn,
+            START_SAVE_STATE => {
+                let length = reader.u32()? as usize;
+                MovieStart::SaveState(reader.take(length)?.to_vec())
+            }
+            _ => return Err(EmuError::InvalidMovie("unknown start")),
+        };
+        let frames = reader.u32()? as usize;
+        let inputs = reader.take(frames)?.to_vec();
+
+        Ok(Movie { rom_checksum, start, inputs })
+    }
+
+    /// Puts the machine into the state the recording started from
+    pub fn start(&self, cpu: &mut CPU) -> Result<(), EmuError> {
+        let checksum = rom_checksum(cpu);
+        if checksum != self.rom_checksum {
+            return Err(EmuError::MovieRomMismatch { found: checksum, expected: self.rom_checksum });
+        }
+        match &self.start {
+            MovieStart::PowerOn => cpu.reset(),
+            MovieStart::SaveState(state) => cpu.load_state(state)?,
+        }
+        Ok(())
+    }
+
+    /// Replays the whole movie without a frontend, `on_frame` is called after every frame
+    pub fn play(&self, cpu: &mut CPU, mut on_frame: impl FnMut(usize, &CPU)) -> Result<(), EmuError> {
+        self.start(cpu)?;
+        for (frame, input) in self.inputs.iter().enumerate() {
+            cpu.memory.joypad = *input;
+            cpu.run_frame()?;
+            on_frame(frame, cpu);
+        }
+        Ok(())
+    }
+}
+
+/// Checksum the cartridge header carries for the whole ROM
+pub fn rom_checksum(cpu: &CPU) -> u16 {
+    u16::from_be_bytes([cpu.memory.memory[0x014E], cpu.memory.memory[0x014F]])
+}
+
+/// Collects the input of every frame while the frontend runs the emulator
+pub struct MovieRecorder {
+    movie: Movie,
+    frame: u64,
+}
+
+impl MovieRecorder {
+    pub fn power_on(cpu: &mut CPU) -> MovieRecorder {
+        cpu.reset();
+        MovieRecorder::new(cpu, MovieStart::PowerOn)
+    }
+
+    pub fn from_save_state(cpu: &CPU) -> MovieRecorder {
+        MovieRecorder::new(cpu, MovieStart::SaveState(cpu.save_state()))
+    }
+
+    fn new(cpu: &CPU, start: MovieStart) -> MovieRecorder {
+        MovieRecorder {
+            movie: Movie {
+                rom_checksum: rom_checksum(cpu),
+                start,
+                inputs: vec![cpu.memory.joypad],
+            },
+            frame: cpu.frame_number(),
+        }
+    }
+
+    pub fn frames(&self) -> usize {
+        self.movie.inputs.len()
+    }
+
+    /// Latches `input` when a new frame started, call it after every step
+    pub fn record(&mut self, cpu: &mut CPU, input: u8) {
+        while self.frame < cpu.frame_number() {
+            self.frame += 1;
+            cpu.memory.joypad = input;
+            self.movie.inputs.push(input);
+        }
+    }
+
+    pub fn finish(self) -> Movie {
+        self.movie
+    }
+}
+
+/// Feeds a movie into the emulator while the frontend runs it
+pub struct MoviePlayer {
+    movie: Movie,
+    frame: u64,
+    index: usize,
+}
+
+impl MoviePlayer {
+    pub fn start(movie: Movie, cpu: &mut CPU) -> Result<MoviePlayer, EmuError> {
+        movie.start(cpu)?;
+        cpu.memory.joypad = movie.inputs.first().copied().unwrap_or(0);
+        Ok(MoviePlayer { movie, frame: cpu.frame_number(), index: 0 })
+    }
+
+    /// Current frame and total frames
+    pub fn progress(&self) -> (usize, usize) {
+        (self.index, self.movie.inputs.len())
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.index >= self.movie.inputs.len()
+    }
+
+    /// Applies the input of a new frame, call it after every step
+    pub fn play(&mut self, cpu: &mut CPU) {
+        while self.frame < cpu.frame_number() {
+            self.frame += 1;
+            self.index += 1;
+            cpu.memory.joypad = self.movie.inputs.get(self.index).copied().unwrap_or(0);
+        }
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [u8], EmuError> {
+        if self.data.len() < length {
+            return Err(EmuError::InvalidMovie("truncated"));
+        }
+        let (taken, rest) = self.data.split_at(length);
+        self.data = rest;
+        Ok(taken)
+    }
+
+    fn u16(&mut self) -> Result<u16, EmuError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, EmuError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::joypad;
+    use crate::memory::ROM_SIZE;
+
+    const INPUTS: [u8; 6] = [0, joypad::A, joypad::A | joypad::RIGHT, 0, joypad::START, 0];
+
+    fn cpu() -> CPU {
+        let mut rom = vec![0; ROM_SIZE];
+        rom[0x014E..0x0150].copy_from_slice(&[0xAB, 0xCD]);
+        let mut cpu = CPU::new();
+        cpu.memory.load_rom(&rom).unwrap();
+        cpu
+    }
+
+    // Steps like the frontend does, the recorder latching each input when its frame starts.
+    // Returns the movie and the machine at the end of its last frame.
+    fn record(cpu: &mut CPU, mut recorder: MovieRecorder) -> (Movie, Vec<u8>) {
+        let start = cpu.frame_number();
+        for (frame, input) in INPUTS.iter().enumerate().skip(1) {
+            while cpu.frame_number() < start + frame as u64 {
+                cpu.step().unwrap();
+                recorder.record(cpu, *input);
+            }
+        }
+        cpu.run_frame().unwrap();
+        (recorder.finish(), cpu.save_state())
+    }
+
+    fn replay(movie: &Movie) -> (Vec<u8>, Vec<u8>) {
+        let mut cpu = cpu();
+        let mut held = Vec::new();
+        movie.play(&mut cpu, |_, cpu| held.push(cpu.memory.joypad)).unwrap();
+        (held, cpu.save_state())
+    }
+
+    #[test]
+    fn plays_back_a_recording_from_power_on() {
+        let mut cpu = cpu();
+        let recorder = MovieRecorder::power_on(&mut cpu);
+        let (movie, recorded) = record(&mut cpu, recorder);
+        assert_eq!(movie.inputs, INPUTS);
+
+        let (held, played) = replay(&Movie::from_bytes(&movie.to_bytes()).unwrap());
+        assert_eq!(held, INPUTS);
+        assert_eq!(played, recorded);
+    }
+
+    #[test]
+    fn plays_back_a_recording_from_a_save_state() {
+        let mut cpu = cpu();
+        cpu.memory.in_bootrom = false;
+        cpu.registry.pc = 0x0100;
+        for _ in 0..1000 {
+            cpu.step().unwrap();
+        }
+        let recorder = MovieRecorder::from_save_state(&cpu);
+        let (movie, recorded) = record(&mut cpu, recorder);
+
+        let (held, played) = replay(&Movie::from_bytes(&movie.to_bytes()).unwrap());
+        assert_eq!(held, INPUTS);
+        assert_eq!(played, recorded);
+    }
+
+    #[test]
+    fn refuses_a_different_rom() {
+        let movie = Movie { rom_checksum: 0x1234, start: MovieStart::PowerOn, inputs: vec![0] };
+        assert!(matches!(movie.start(&mut cpu()), Err(EmuError::MovieRomMismatch { found: 0xABCD, expected: 0x1234 })));
+    }
+
+    #[test]
+    fn rejects_broken_files() {
+        let data = Movie { rom_checksum: 0xABCD, start: MovieStart::PowerOn, inputs: vec![0; 10] }.to_bytes();
+        assert!(matches!(Movie::from_bytes(b"RGBS"), Err(EmuError::InvalidMovie(_))));
+        assert!(matches!(Movie::from_bytes(&data[..data.len() - 1]), Err(EmuError::InvalidMovie("truncated"))));
+        let mut newer = data.clone();
+        newer[4] = 9;
+        assert!(matches!(Movie::from_bytes(&newer), Err(EmuError::MovieVersion { found: 9, .. })));
+    }
+}
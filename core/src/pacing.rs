@@ -0,0 +1,48 @@
+use crate::cpu::{CLOCK_HZ, FRAME_T_CYCLES};
+
+// A host that can't keep up drops whatever is owed beyond this and runs slower, instead of
+// trying to catch up with ever longer bursts
+const MAX_LAG_FRAMES: u64 = 4;
+
+/// Turns wall-clock time into T-cycles to run, so the game sees 59.73 frames per second times `speed`
+pub struct FramePacer {
+    /// 1.0 is real time, below is slow motion and above fast forward
+    pub speed: f64,
+    // Cycles owed to the emulation, fractions and overshoot carry over to the next call
+    owed: f64,
+}
+
+impl Default for FramePacer {
+    fn default() -> FramePacer {
+        FramePacer::new()
+    }
+}
+
+impl FramePacer {
+    pub fn new() -> FramePacer {
+        FramePacer { speed: 1.0, owed: 0.0 }
+    }
+
+    /// T-cycles to run for `elapsed` seconds of wall-clock time
+    pub fn budget(&mut self, elapsed: f64) -> u64 {
+        let speed = self.speed.max(0.0);
+        self.owed += elapsed.max(0.0) * CLOCK_HZ as f64 * speed;
+        let max = (MAX_LAG_FRAMES * FRAME_T_CYCLES) as f64 * speed.max(1.0);
+        if self.owed > max {
+            self.owed = max;
+        }
+        let cycles = self.owed as u64;
+        self.owed -= cycles as f64;
+        cycles
+    }
+
+    /// Instructions don't stop exactly on the budget, what ran past it is taken from the next one
+    pub fn overshot(&mut self, cycles: u64) {
+        self.owed -= cycles as f64;
+    }
+
+    /// Forgets what's owed, after a pause nothing should be caught up on
+    pub fn reset(&mut self) {
+        self.owed = 0.0;
+    }
+}
@@ -0,0 +1,166 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::ppu::GREY_SHADES;
+
+/// RGB of each shade, lightest to darkest
+pub type Palette = [[u8; 3]; 4];
+
+pub const GREY_PALETTE: Palette = [
+    [GREY_SHADES[0]; 3],
+    [GREY_SHADES[1]; 3],
+    [GREY_SHADES[2]; 3],
+    [GREY_SHADES[3]; 3],
+];
+
+/// The original pea soup green screen
+pub const CLASSIC_GREEN: Palette = [[0x9B, 0xBC, 0x0F], [0x8B, 0xAC, 0x0F], [0x30, 0x62, 0x30], [0x0F, 0x38, 0x0F]];
+/// Game Boy Pocket, close to grey with a hint of yellow
+pub const POCKET: Palette = [[0xC4, 0xCF, 0xA1], [0x8B, 0x95, 0x6D], [0x4D, 0x53, 0x3C], [0x1F, 0x1F, 0x1F]];
+/// Game Boy Light with the backlight on
+pub const LIGHT: Palette = [[0x00, 0xB5, 0x81], [0x00, 0x9A, 0x71], [0x00, 0x69, 0x4A], [0x00, 0x4F, 0x3B]];
+
+pub const PRESETS: [(&str, Palette); 4] = [
+    ("Grey", GREY_PALETTE),
+    ("Classic green", CLASSIC_GREEN),
+    ("Pocket", POCKET),
+    ("Light", LIGHT),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NamedPalette {
+    pub name: String,
+    pub colours: Palette,
+}
+
+/// The presets followed by `custom`, what a palette picker offers
+pub fn all(custom: &[NamedPalette]) -> Vec<NamedPalette> {
+    PRESETS
+        .iter()
+        .map(|(name, colours)| NamedPalette { name: name.to_string(), colours: *colours })
+        .chain(custom.iter().cloned())
+        .collect()
+}
+
+/// Reads palettes from a config file, one per line, lightest colour first:
+///
+///   # comment
+///   Ocean: #E0F8D0 #88C070 #346856 #081820
+pub fn parse(text: &str) -> Result<Vec<NamedPalette>, String> {
+    let mut palettes = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid = || format!("Line {} isn't a name followed by four #RRGGBB colours: {}", index + 1, line);
+        let (name, colours) = line.split_once(':').ok_or_else(invalid)?;
+        let colours: Vec<[u8; 3]> = colours.split_whitespace().map(parse_colour).collect::<Option<_>>().ok_or_else(invalid)?;
+        let colours: Palette = colours.try_into().map_err(|_| invalid())?;
+        palettes.push(NamedPalette { name: name.trim().to_string(), colours });
+    }
+    Ok(palettes)
+}
+
+fn parse_colour(text: &str) -> Option<[u8; 3]> {
+    let hex = text.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    let [_, red, green, blue] = value.to_be_bytes();
+    Some([red, green, blue])
+}
+
+/// Writes palettes the way `parse` reads them
+pub fn to_text(palettes: &[NamedPalette]) -> String {
+    let mut text = String::from("# name: four colours, lightest first\n");
+    for palette in palettes {
+        let _ = write!(text, "{}:", palette.name);
+        for [red, green, blue] in palette.colours {
+            let _ = write!(text, " #{:02X}{:02X}{:02X}", red, green, blue);
+        }
+        text.push('\n');
+    }
+    text
+}
+
+/// What a colour looks like on the CGB's LCD, which is darker and mixes the channels a bit.
+/// The colour is taken down to the CGB's 5 bits per channel first.
+pub fn cgb_corrected(colour: [u8; 3]) -> [u8; 3] {
+    let [red, green, blue] = colour.map(|channel| channel as u32 >> 3);
+    let mix = |value: u32| (value.min(960) >> 2) as u8;
+    [
+        mix(red * 26 + green * 4 + blue * 2),
+        mix(green * 24 + blue * 8),
+        mix(red * 6 + green * 4 + blue * 22),
+    ]
+}
+
+/// True if the cartridge header says the game only runs on the CGB. Dual mode games have
+/// DMG palettes of their own and look right without the correction.
+pub fn is_cgb_title(rom: &[u8]) -> bool {
+    rom.get(0x0143).is_some_and(|&flag| flag == 0xC0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_what_it_writes() {
+        let text = "# comment\n\n  Ocean: #E0F8D0 #88c070 #346856 #081820  \n";
+        let palettes = parse(text).unwrap();
+        assert_eq!(
+            palettes,
+            [NamedPalette {
+                name: "Ocean".to_string(),
+                colours: [[0xE0, 0xF8, 0xD0], [0x88, 0xC0, 0x70], [0x34, 0x68, 0x56], [0x08, 0x18, 0x20]],
+            }]
+        );
+        assert_eq!(parse(&to_text(&palettes)).unwrap(), palettes);
+    }
+
+    #[test]
+    fn rejects_bad_lines() {
+        for line in [
+            "Ocean #E0F8D0 #88C070 #346856 #081820",
+            "Ocean: #E0F8D0 #88C070 #346856",
+            "Ocean: E0F8D0 #88C070 #346856 #081820",
+            "Ocean: #E0F8D #88C070 #346856 #081820",
+            "Ocean: #E0F8DG #88C070 #346856 #081820",
+        ] {
+            assert!(parse(&format!("# fine\n{}", line)).unwrap_err().starts_with("Line 2 "), "{}", line);
+        }
+    }
+
+    #[test]
+    fn custom_palettes_come_after_the_presets() {
+        let custom = NamedPalette { name: "Mine".to_string(), colours: GREY_PALETTE };
+        let palettes = all(core::slice::from_ref(&custom));
+        assert_eq!(palettes.len(), PRESETS.len() + 1);
+        assert_eq!(palettes[0].name, "Grey");
+        assert_eq!(palettes.last(), Some(&custom));
+    }
+
+    #[test]
+    fn correction_darkens_and_mixes() {
+        assert_eq!(cgb_corrected([0, 0, 0]), [0, 0, 0]);
+        assert_eq!(cgb_corrected([0xFF, 0xFF, 0xFF]), [240, 240, 240]);
+        let [red, green, blue] = cgb_corrected([0xFF, 0, 0]);
+        assert!(red > 0 && green == 0 && blue > 0 && red > blue);
+    }
+
+    #[test]
+    fn only_cgb_only_carts_are_cgb_titles() {
+        let mut rom = [0; 0x150];
+        assert!(!is_cgb_title(&rom));
+        rom[0x143] = 0x80;
+        assert!(!is_cgb_title(&rom));
+        rom[0x143] = 0xC0;
+        assert!(is_cgb_title(&rom));
+        assert!(!is_cgb_title(&[]));
+    }
+}
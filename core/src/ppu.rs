@@ -0,0 +1,368 @@
+#[cfg(feature = "alloc")]
+use alloc::vec;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::memory::Memory;
+
+pub const SCREEN_WIDTH: usize = 160;
+pub const SCREEN_HEIGHT: usize = 144;
+/// Grey level of each shade, lightest to darkest
+pub const GREY_SHADES: [u8; 4] = [0xFF, 0xAA, 0x55, 0x00];
+
+const LINE_T_CYCLES: u64 = 456;
+const VBLANK_LINE: u8 = 144;
+
+pub const LCDC: usize = 0xFF40;
+pub const STAT: usize = 0xFF41;
+pub const SCY: usize = 0xFF42;
+pub const SCX: usize = 0xFF43;
+pub const LY: usize = 0xFF44;
+pub const LYC: usize = 0xFF45;
+pub const BGP: usize = 0xFF47;
+pub const OBP0: usize = 0xFF48;
+pub const OBP1: usize = 0xFF49;
+pub const WY: usize = 0xFF4A;
+pub const WX: usize = 0xFF4B;
+const IF: usize = 0xFF0F;
+pub const OAM: usize = 0xFE00;
+
+pub const LCD_ENABLE: u8 = 1 << 7;
+pub const WINDOW_MAP: u8 = 1 << 6;
+pub const WINDOW_ENABLE: u8 = 1 << 5;
+pub const TILE_DATA: u8 = 1 << 4;
+pub const BG_MAP: u8 = 1 << 3;
+pub const OBJ_SIZE: u8 = 1 << 2;
+pub const OBJ_ENABLE: u8 = 1 << 1;
+pub const BG_ENABLE: u8 = 1 << 0;
+
+const VBLANK_INTERRUPT: u8 = 1 << 0;
+const SPRITES_PER_LINE: usize = 10;
+pub const SPRITE_COUNT: usize = 40;
+pub const TILE_COUNT: usize = 384;
+/// Size of `render_tiles`, 16 tiles to a row
+pub const TILES_WIDTH: usize = 16 * 8;
+pub const TILES_HEIGHT: usize = TILE_COUNT / 16 * 8;
+/// Size of `render_tile_map`
+pub const MAP_SIZE: usize = 256;
+
+/// One OAM entry as it's stored, x and y are offset by 8 and 16 like on the hardware
+#[derive(Debug, Clone, Copy)]
+pub struct Sprite {
+    pub y: u8,
+    pub x: u8,
+    pub tile: u8,
+    pub attributes: u8,
+}
+
+impl Sprite {
+    pub fn read(memory: &Memory, index: usize) -> Sprite {
+        let entry = &memory.memory[OAM + index * 4..OAM + index * 4 + 4];
+        Sprite { y: entry[0], x: entry[1], tile: entry[2], attributes: entry[3] }
+    }
+
+    pub fn behind_background(&self) -> bool {
+        self.attributes & 0x80 != 0
+    }
+
+    pub fn y_flip(&self) -> bool {
+        self.attributes & 0x40 != 0
+    }
+
+    pub fn x_flip(&self) -> bool {
+        self.attributes & 0x20 != 0
+    }
+
+    /// 0 for OBP0, 1 for OBP1
+    pub fn palette(&self) -> u8 {
+        (self.attributes >> 4) & 1
+    }
+}
+
+/// Keeps LY and the STAT mode in step with the clock, `frame_cycle` is the T-cycle within the current frame
+pub fn update_registers(memory: &mut Memory, frame_cycle: u64) {
+    let mem = &mut memory.memory;
+    if mem[LCDC] & LCD_ENABLE == 0 {
+        mem[LY] = 0;
+        mem[STAT] &= !0x07;
+        return;
+    }
+
+    let line = (frame_cycle / LINE_T_CYCLES) as u8;
+    let dot = frame_cycle % LINE_T_CYCLES;
+    if line == VBLANK_LINE && mem[LY] != VBLANK_LINE {
+        mem[IF] |= VBLANK_INTERRUPT;
+    }
+    let mode = match (line, dot) {
+        (VBLANK_LINE.., _) => 1,
+        (_, 0..=79) => 2,
+        (_, 80..=251) => 3,
+        _ => 0,
+    };
+    let coincidence = if line == mem[LYC] { 1 << 2 } else { 0 };
+    mem[LY] = line;
+    mem[STAT] = (mem[STAT] & !0x07) | coincidence | mode;
+}
+
+/// Draws the whole screen from VRAM/OAM as they are right now, one shade (0 = lightest, 3 = darkest) per pixel.
+/// There's no per-scanline rendering yet, so mid-frame register changes (raster effects) aren't visible.
+#[cfg(feature = "alloc")]
+pub fn render(memory: &Memory) -> Vec<u8> {
+    let mut frame = vec![0; SCREEN_WIDTH * SCREEN_HEIGHT];
+    render_into(memory, &mut frame);
+    frame
+}
+
+/// `render` into a buffer of `SCREEN_WIDTH * SCREEN_HEIGHT` shades
+pub fn render_into(memory: &Memory, frame: &mut [u8]) {
+    let mem = &memory.memory;
+    frame.fill(0);
+    let lcdc = mem[LCDC];
+    if lcdc & LCD_ENABLE == 0 {
+        return;
+    }
+
+    // Colour before the palette, sprites behind the background only show through colour 0
+    let mut background = [0; SCREEN_WIDTH * SCREEN_HEIGHT];
+    let (scx, scy) = (mem[SCX] as usize, mem[SCY] as usize);
+    let (wx, wy) = (mem[WX] as usize, mem[WY] as usize);
+    for y in 0..SCREEN_HEIGHT {
+        for x in 0..SCREEN_WIDTH {
+            if lcdc & BG_ENABLE == 0 {
+                continue;
+            }
+            let in_window = lcdc & WINDOW_ENABLE != 0 && y >= wy && x + 7 >= wx;
+            let color = if in_window {
+                let map = if lcdc & WINDOW_MAP != 0 { 0x9C00 } else { 0x9800 };
+                map_pixel(mem, lcdc, map, x + 7 - wx, y - wy)
+            } else {
+                let map = if lcdc & BG_MAP != 0 { 0x9C00 } else { 0x9800 };
+                map_pixel(mem, lcdc, map, (x + scx) & 0xFF, (y + scy) & 0xFF)
+            };
+            background[y * SCREEN_WIDTH + x] = color;
+            frame[y * SCREEN_WIDTH + x] = shade(mem[BGP], color);
+        }
+    }
+
+    if lcdc & OBJ_ENABLE != 0 {
+        let height = if lcdc & OBJ_SIZE != 0 { 16 } else { 8 };
+        for y in 0..SCREEN_HEIGHT {
+            // The first ten sprites in OAM order get drawn, the one further left wins on overlap
+            let mut sprites = [0; SPRITES_PER_LINE];
+            let mut count = 0;
+            for sprite in (OAM..OAM + 160).step_by(4) {
+                if count < SPRITES_PER_LINE && (y + 16).wrapping_sub(mem[sprite] as usize) < height {
+                    sprites[count] = sprite;
+                    count += 1;
+                }
+            }
+            let sprites = &mut sprites[..count];
+            sprites.sort_unstable_by_key(|sprite| (mem[sprite + 1], *sprite));
+            for sprite in sprites.iter().rev() {
+                draw_sprite_line(mem, &mem[*sprite..*sprite + 4], height, y, &background, frame);
+            }
+        }
+    }
+}
+
+/// Every tile in 0x8000-0x97FF through `palette`, `TILES_WIDTH` by `TILES_HEIGHT` shades
+#[cfg(feature = "alloc")]
+pub fn render_tiles(memory: &Memory, palette: u8) -> Vec<u8> {
+    let mut tiles = vec![0; TILES_WIDTH * TILES_HEIGHT];
+    for y in 0..TILES_HEIGHT {
+        for x in 0..TILES_WIDTH {
+            let tile = 0x8000 + ((y / 8) * 16 + x / 8) * 16;
+            tiles[y * TILES_WIDTH + x] = shade(palette, tile_pixel(&memory.memory, tile, x % 8, y % 8));
+        }
+    }
+    tiles
+}
+
+/// The 32x32 tile map at `map` (0x9800 or 0x9C00) with the tile data LCDC selects and BGP,
+/// `MAP_SIZE` by `MAP_SIZE` shades
+#[cfg(feature = "alloc")]
+pub fn render_tile_map(memory: &Memory, map: usize) -> Vec<u8> {
+    let mem = &memory.memory;
+    let mut pixels = vec![0; MAP_SIZE * MAP_SIZE];
+    for y in 0..MAP_SIZE {
+        for x in 0..MAP_SIZE {
+            pixels[y * MAP_SIZE + x] = shade(mem[BGP], map_pixel(mem, mem[LCDC], map, x, y));
+        }
+    }
+    pixels
+}
+
+/// 8 pixel wide preview of a sprite with its palette and flips, as tall as the sprite size in LCDC
+#[cfg(feature = "alloc")]
+pub fn render_sprite(memory: &Memory, sprite: &Sprite) -> Vec<u8> {
+    let mem = &memory.memory;
+    let height = sprite_height(memory);
+    let tile = if height == 16 { sprite.tile & 0xFE } else { sprite.tile };
+    let palette = if sprite.palette() == 1 { mem[OBP1] } else { mem[OBP0] };
+    let mut pixels = vec![0; 8 * height];
+    for row in 0..height {
+        for column in 0..8 {
+            let y = if sprite.y_flip() { height - 1 - row } else { row };
+            let x = if sprite.x_flip() { 7 - column } else { column };
+            pixels[row * 8 + column] = shade(palette, tile_pixel(mem, 0x8000 + tile as usize * 16, x, y));
+        }
+    }
+    pixels
+}
+
+/// 8 or 16, depending on LCDC
+pub fn sprite_height(memory: &Memory) -> usize {
+    if memory.memory[LCDC] & OBJ_SIZE != 0 { 16 } else { 8 }
+}
+
+fn draw_sprite_line(mem: &[u8], sprite: &[u8], height: usize, y: usize, background: &[u8], frame: &mut [u8]) {
+    let attributes = sprite[3];
+    let mut row = y + 16 - sprite[0] as usize;
+    if attributes & 0x40 != 0 {
+        row = height - 1 - row;
+    }
+    let tile = if height == 16 { sprite[2] & 0xFE } else { sprite[2] };
+    let palette = if attributes & 0x10 != 0 { mem[OBP1] } else { mem[OBP0] };
+
+    for column in 0..8 {
+        let x = (sprite[1] as usize + column).wrapping_sub(8);
+        if x >= SCREEN_WIDTH {
+            continue;
+        }
+        let column = if attributes & 0x20 != 0 { 7 - column } else { column };
+        let color = tile_pixel(mem, 0x8000 + tile as usize * 16, column, row);
+        let behind = attributes & 0x80 != 0 && background[y * SCREEN_WIDTH + x] != 0;
+        if color != 0 && !behind {
+            frame[y * SCREEN_WIDTH + x] = shade(palette, color);
+        }
+    }
+}
+
+// Colour of a pixel in a 256x256 tile map
+fn map_pixel(mem: &[u8], lcdc: u8, map: usize, x: usize, y: usize) -> u8 {
+    let index = mem[map + (y / 8) * 32 + x / 8];
+    let tile = if lcdc & TILE_DATA != 0 {
+        0x8000 + index as usize * 16
+    } else {
+        (0x9000 + (index as i8 as isize) * 16) as usize
+    };
+    tile_pixel(mem, tile, x % 8, y % 8)
+}
+
+/// Colour number (0-3) of a pixel inside the tile at `tile`
+pub fn tile_pixel(mem: &[u8], tile: usize, x: usize, y: usize) -> u8 {
+    let low = mem[tile + y * 2];
+    let high = mem[tile + y * 2 + 1];
+    let bit = 7 - x;
+    ((high >> bit) & 1) << 1 | ((low >> bit) & 1)
+}
+
+/// Shade a palette register (BGP/OBP0/OBP1) assigns to a colour number
+pub fn shade(palette: u8, color: u8) -> u8 {
+    (palette >> (color * 2)) & 0x03
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // LCD on, background on, tile data at 0x8000, map at 0x9800, BGP the identity palette
+    fn lcd_on() -> Memory {
+        let mut memory = Memory::new();
+        memory.memory[LCDC] = LCD_ENABLE | TILE_DATA | BG_ENABLE;
+        memory.memory[BGP] = 0b11_10_01_00;
+        memory
+    }
+
+    #[test]
+    fn registers_follow_the_frame() {
+        let mut memory = lcd_on();
+        memory.memory[LYC] = 2;
+        update_registers(&mut memory, 0);
+        assert_eq!((memory.memory[LY], memory.memory[STAT] & 0x07), (0, 2));
+        update_registers(&mut memory, 100);
+        assert_eq!(memory.memory[STAT] & 0x07, 3);
+        update_registers(&mut memory, 300);
+        assert_eq!(memory.memory[STAT] & 0x07, 0);
+        update_registers(&mut memory, 2 * LINE_T_CYCLES);
+        assert_eq!((memory.memory[LY], memory.memory[STAT] & 0x07), (2, 1 << 2 | 2));
+    }
+
+    #[test]
+    fn vblank_is_requested_once() {
+        let mut memory = lcd_on();
+        update_registers(&mut memory, VBLANK_LINE as u64 * LINE_T_CYCLES);
+        assert_eq!(memory.memory[STAT] & 0x03, 1);
+        assert_eq!(memory.memory[IF] & VBLANK_INTERRUPT, VBLANK_INTERRUPT);
+        memory.memory[IF] = 0;
+        update_registers(&mut memory, VBLANK_LINE as u64 * LINE_T_CYCLES + 4);
+        assert_eq!(memory.memory[IF], 0);
+    }
+
+    #[test]
+    fn lcd_off_resets_ly_and_draws_nothing() {
+        let mut memory = lcd_on();
+        memory.memory[0x8000..0x8010].fill(0xFF);
+        update_registers(&mut memory, 10 * LINE_T_CYCLES);
+        memory.memory[LCDC] = 0;
+        update_registers(&mut memory, 20 * LINE_T_CYCLES);
+        assert_eq!((memory.memory[LY], memory.memory[STAT] & 0x07), (0, 0));
+        assert!(render(&memory).iter().all(|shade| *shade == 0));
+    }
+
+    #[test]
+    fn background_scrolls_through_the_palette() {
+        let mut memory = lcd_on();
+        // Tile 1 is colour 3 everywhere, it sits second in the top row of the map
+        memory.memory[0x8010..0x8020].fill(0xFF);
+        memory.memory[0x9801] = 1;
+        memory.memory[BGP] = 0b01_00_00_00;
+        let frame = render(&memory);
+        assert_eq!((frame[7], frame[8]), (0, 1));
+        memory.memory[SCX] = 4;
+        let frame = render(&memory);
+        assert_eq!((frame[3], frame[4]), (0, 1));
+    }
+
+    #[test]
+    fn sprites_draw_over_the_background_unless_behind_it() {
+        let mut memory = lcd_on();
+        memory.memory[LCDC] |= OBJ_ENABLE;
+        memory.memory[OBP0] = 0b10_00_00_00;
+        memory.memory[0x8010..0x8020].fill(0xFF);
+        // Sprite 0 at the top left corner of the screen using tile 1
+        memory.memory[OAM..OAM + 4].copy_from_slice(&[16, 8, 1, 0]);
+        assert_eq!(render(&memory)[0], 2);
+
+        memory.memory[OAM + 3] = 0x80;
+        assert_eq!(render(&memory)[0], 2);
+        // Background colour 3 under it now
+        memory.memory[0x8000..0x8010].fill(0xFF);
+        assert_eq!(render(&memory)[0], 3);
+    }
+
+    #[test]
+    fn only_ten_sprites_per_line() {
+        let mut memory = lcd_on();
+        memory.memory[LCDC] |= OBJ_ENABLE;
+        memory.memory[OBP0] = 0b11_00_00_00;
+        memory.memory[0x8010..0x8020].fill(0xFF);
+        for index in 0..11 {
+            let x = 8 + index as u8 * 8;
+            memory.memory[OAM + index * 4..OAM + index * 4 + 4].copy_from_slice(&[16, x, 1, 0]);
+        }
+        let frame = render(&memory);
+        assert_eq!(frame[9 * 8], 3);
+        assert_eq!(frame[10 * 8], 0);
+    }
+
+    #[test]
+    fn shades_and_tile_pixels() {
+        assert_eq!(shade(0b11_10_01_00, 2), 2);
+        assert_eq!(shade(0b00_01_10_11, 0), 3);
+        let mut mem = [0; 16];
+        mem[0] = 0b1000_0000;
+        mem[1] = 0b1100_0000;
+        assert_eq!((tile_pixel(&mem, 0, 0, 0), tile_pixel(&mem, 0, 1, 0), tile_pixel(&mem, 0, 2, 0)), (3, 2, 0));
+    }
+}
@@ -0,0 +1,151 @@
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+use core::fmt::Write;
+
+use crate::memory::MemoryAccess;
+use crate::symbols::Symbols;
+
+const ADDRESSES: usize = 0x10000;
+
+/// Counters for every address in the 16 bit address space, only kept while profiling is on
+#[derive(Clone)]
+pub struct Profiler {
+    /// Instructions started at the address
+    pub executions: Vec<u32>,
+    /// T-cycles spent on the instruction at the address
+    pub cycles: Vec<u64>,
+    /// Data reads, instruction fetches don't count
+    pub reads: Vec<u32>,
+    pub writes: Vec<u32>,
+}
+
+/// Time spent between one label and the next
+pub struct FunctionTotal {
+    pub name: String,
+    pub cycles: u64,
+    pub instructions: u64,
+}
+
+impl Default for Profiler {
+    fn default() -> Profiler {
+        Profiler::new()
+    }
+}
+
+impl Profiler {
+    pub fn new() -> Profiler {
+        Profiler {
+            executions: vec![0; ADDRESSES],
+            cycles: vec![0; ADDRESSES],
+            reads: vec![0; ADDRESSES],
+            writes: vec![0; ADDRESSES],
+        }
+    }
+
+    /// Counts one instruction at `pc` that was `length` bytes long, took `cycles` and made `accesses`
+    pub fn record(&mut self, pc: u16, length: u16, cycles: u64, accesses: &[MemoryAccess]) {
+        let pc_index = pc as usize;
+        self.executions[pc_index] = self.executions[pc_index].saturating_add(1);
+        self.cycles[pc_index] = self.cycles[pc_index].saturating_add(cycles);
+        for access in accesses {
+            let index = access.address as usize;
+            if access.write {
+                self.writes[index] = self.writes[index].saturating_add(1);
+            } else if access.address.wrapping_sub(pc) >= length {
+                self.reads[index] = self.reads[index].saturating_add(1);
+            }
+        }
+    }
+
+    /// Cycles per function, most expensive first. Every address counts towards the closest
+    /// label before it, so this is self time, calls into other functions aren't included.
+    pub fn function_totals(&self, symbols: &Symbols) -> Vec<FunctionTotal> {
+        let mut totals: BTreeMap<String, (u64, u64)> = BTreeMap::new();
+        for address in 0..ADDRESSES {
+            if self.executions[address] == 0 {
+                continue;
+            }
+            let name = match symbols.nearest(address as u16) {
+                Some((label, _)) => String::from(label),
+                None => String::from("(no label)"),
+            };
+            let total = totals.entry(name).or_default();
+            total.0 += self.cycles[address];
+            total.1 += self.executions[address] as u64;
+        }
+        let mut totals: Vec<FunctionTotal> = totals
+            .into_iter()
+            .map(|(name, (cycles, instructions))| FunctionTotal { name, cycles, instructions })
+            .collect();
+        totals.sort_by_key(|total| Reverse(total.cycles));
+        totals
+    }
+
+    /// Function totals followed by every address that was touched, as two CSV tables
+    pub fn to_csv(&self, symbols: &Symbols) -> String {
+        let mut csv = String::from("function,cycles,instructions\n");
+        for total in self.function_totals(symbols) {
+            let _ = writeln!(csv, "{},{},{}", total.name, total.cycles, total.instructions);
+        }
+        csv.push_str("\naddress,label,executions,cycles,reads,writes\n");
+        for address in 0..ADDRESSES {
+            let counters = (self.executions[address], self.cycles[address], self.reads[address], self.writes[address]);
+            if counters == (0, 0, 0, 0) {
+                continue;
+            }
+            let label = symbols.label(address as u16).unwrap_or("");
+            let _ = writeln!(csv, "{:04X},{},{},{},{},{}", address, label, counters.0, counters.1, counters.2, counters.3);
+        }
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(address: u16) -> MemoryAccess {
+        MemoryAccess { address, write: false }
+    }
+
+    #[test]
+    fn counts_executions_cycles_and_data_accesses() {
+        let mut profiler = Profiler::new();
+        // LD A, [$C000] reads its own three bytes and then the data
+        let accesses = [read(0x0150), read(0x0151), read(0x0152), read(0xC000)];
+        profiler.record(0x0150, 3, 16, &accesses);
+        profiler.record(0x0150, 3, 16, &accesses);
+        profiler.record(0x0153, 1, 8, &[MemoryAccess { address: 0xC001, write: true }]);
+        assert_eq!((profiler.executions[0x0150], profiler.cycles[0x0150]), (2, 32));
+        assert_eq!(profiler.reads[0x0151], 0);
+        assert_eq!(profiler.reads[0xC000], 2);
+        assert_eq!((profiler.writes[0xC001], profiler.reads[0xC001]), (1, 0));
+    }
+
+    #[test]
+    fn totals_go_to_the_nearest_label_most_expensive_first() {
+        let symbols = Symbols::parse("00:0150 Main\n00:0200 Busy\n").unwrap();
+        let mut profiler = Profiler::new();
+        profiler.record(0x0150, 1, 4, &[]);
+        profiler.record(0x0151, 1, 4, &[]);
+        profiler.record(0x0205, 1, 24, &[]);
+        profiler.record(0x0100, 1, 4, &[]);
+        let totals = profiler.function_totals(&symbols);
+        let totals: Vec<(&str, u64, u64)> = totals.iter().map(|total| (total.name.as_str(), total.cycles, total.instructions)).collect();
+        assert_eq!(totals, [("Busy", 24, 1), ("Main", 8, 2), ("(no label)", 4, 1)]);
+    }
+
+    #[test]
+    fn csv_lists_functions_then_touched_addresses() {
+        let symbols = Symbols::parse("00:0150 Main\n").unwrap();
+        let mut profiler = Profiler::new();
+        profiler.record(0x0150, 1, 4, &[read(0xC000)]);
+        assert_eq!(
+            profiler.to_csv(&symbols),
+            "function,cycles,instructions\nMain,4,1\n\naddress,label,executions,cycles,reads,writes\n0150,Main,1,4,0,0\nC000,,0,0,1,0\n",
+        );
+    }
+}
@@ -0,0 +1,252 @@
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+use crate::cpu::CPU;
+use crate::error::EmuError;
+
+/// Ring buffer of save states for stepping backwards.
+/// Only the newest snapshot is kept in full, every older one is stored as the run length
+/// encoded XOR against its successor. Frames barely change the machine so those are tiny.
+pub struct Rewind {
+    /// Take a snapshot every `interval` frames
+    pub interval: u32,
+    /// The oldest snapshots get dropped once the deltas use more than this
+    pub capacity_bytes: usize,
+    latest: Option<Vec<u8>>,
+    deltas: VecDeque<Vec<u8>>,
+    used_bytes: usize,
+    frames_since_snapshot: u32,
+}
+
+impl Rewind {
+    pub fn new(interval: u32, capacity_bytes: usize) -> Rewind {
+        Rewind {
+            interval,
+            capacity_bytes,
+            latest: None,
+            deltas: VecDeque::new(),
+            used_bytes: 0,
+            frames_since_snapshot: 0,
+        }
+    }
+
+    /// Number of snapshots that can be rewound to
+    pub fn len(&self) -> usize {
+        self.deltas.len() + self.latest.is_some() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Memory used by the buffer including the full newest snapshot
+    pub fn used_bytes(&self) -> usize {
+        self.used_bytes + self.latest.as_ref().map_or(0, |latest| latest.len())
+    }
+
+    pub fn clear(&mut self) {
+        self.latest = None;
+        self.deltas.clear();
+        self.used_bytes = 0;
+        self.frames_since_snapshot = 0;
+    }
+
+    /// Call once per emulated frame, takes a snapshot every `interval` frames
+    pub fn record_frame(&mut self, cpu: &CPU) {
+        self.frames_since_snapshot += 1;
+        if self.frames_since_snapshot < self.interval.max(1) {
+            return;
+        }
+        self.frames_since_snapshot = 0;
+
+        let state = cpu.save_state();
+        if let Some(latest) = self.latest.take() {
+            let delta = encode_delta(&latest, &state);
+            self.used_bytes += delta.len();
+            self.deltas.push_back(delta);
+        }
+        self.latest = Some(state);
+
+        while self.used_bytes > self.capacity_bytes {
+            match self.deltas.pop_front() {
+                Some(oldest) => self.used_bytes -= oldest.len(),
+                None => break,
+            }
+        }
+    }
+
+    /// Loads the previous snapshot, false once there's nothing left to go back to.
+    /// Each step goes back `interval` frames, the "Frames per snapshot" slider in the UI,
+    /// so it's frame by frame only with an interval of 1.
+    pub fn step_back(&mut self, cpu: &mut CPU) -> Result<bool, EmuError> {
+        // Frames ran since the newest snapshot, going back to it is the first step
+        if self.frames_since_snapshot > 0 {
+            self.frames_since_snapshot = 0;
+            if let Some(latest) = &self.latest {
+                cpu.load_state(latest)?;
+                return Ok(true);
+            }
+        }
+
+        let (Some(latest), Some(delta)) = (self.latest.as_mut(), self.deltas.pop_back()) else {
+            return Ok(false);
+        };
+        self.used_bytes -= delta.len();
+        apply_delta(latest, &delta);
+        cpu.load_state(latest)?;
+        Ok(true)
+    }
+}
+
+// The XOR of both states as <zero run><literal length><literal bytes> groups, lengths as LEB128.
+// States grow with the serial output, so the delta starts with the length of `old` followed by
+// whatever part of `old` lies past the end of `new`, the XOR only covers what both have.
+fn encode_delta(old: &[u8], new: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    write_length(&mut encoded, old.len());
+    let common = old.len().min(new.len());
+    encoded.extend_from_slice(&old[common..]);
+
+    let mut position = 0;
+    while position < common {
+        let zero_start = position;
+        while position < common && old[position] == new[position] {
+            position += 1;
+        }
+        let literal_start = position;
+        while position < common && old[position] != new[position] {
+            position += 1;
+        }
+
+        write_length(&mut encoded, literal_start - zero_start);
+        write_length(&mut encoded, position - literal_start);
+        encoded.extend((literal_start..position).map(|index| old[index] ^ new[index]));
+    }
+    encoded
+}
+
+// Turns `new` back into `old`
+fn apply_delta(state: &mut Vec<u8>, delta: &[u8]) {
+    let mut cursor = 0;
+    let length = read_length(delta, &mut cursor);
+    let tail = length.saturating_sub(state.len());
+    state.truncate(length);
+    state.extend_from_slice(&delta[cursor..cursor + tail]);
+    cursor += tail;
+
+    let mut position = 0;
+    while cursor < delta.len() {
+        position += read_length(delta, &mut cursor);
+        let literal = read_length(delta, &mut cursor);
+        for byte in &delta[cursor..cursor + literal] {
+            state[position] ^= byte;
+            position += 1;
+        }
+        cursor += literal;
+    }
+}
+
+fn write_length(data: &mut Vec<u8>, mut length: usize) {
+    loop {
+        let byte = (length & 0x7F) as u8;
+        length >>= 7;
+        if length == 0 {
+            data.push(byte);
+            return;
+        }
+        data.push(byte | 0x80);
+    }
+}
+
+fn read_length(data: &[u8], cursor: &mut usize) -> usize {
+    let mut length = 0;
+    let mut shift = 0;
+    loop {
+        let byte = data[*cursor];
+        *cursor += 1;
+        length |= ((byte & 0x7F) as usize) << shift;
+        if byte & 0x80 == 0 {
+            return length;
+        }
+        shift += 7;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delta_round_trip() {
+        let old = [1, 2, 3, 4, 5, 6, 7, 8];
+        let new = [1, 2, 9, 4, 5, 0, 0, 8];
+        let mut state = old.to_vec();
+        apply_delta(&mut state, &encode_delta(&old, &new));
+        assert_eq!(state, new);
+        // XOR works both ways
+        apply_delta(&mut state, &encode_delta(&old, &new));
+        assert_eq!(state, old);
+    }
+
+    #[test]
+    fn unchanged_state_is_a_tiny_delta() {
+        let state = [0x55; 4096];
+        // The length of the state plus one zero run
+        assert!(encode_delta(&state, &state).len() <= 5);
+    }
+
+    #[test]
+    fn delta_round_trip_with_a_length_change() {
+        let short = [1, 2, 3, 4];
+        let long = [1, 9, 3, 4, 5, 6];
+        let mut state = long.to_vec();
+        apply_delta(&mut state, &encode_delta(&short, &long));
+        assert_eq!(state, short);
+        apply_delta(&mut state, &encode_delta(&long, &short));
+        assert_eq!(state, long);
+    }
+
+    #[test]
+    fn lengths_round_trip() {
+        let mut data = Vec::new();
+        for length in [0, 127, 128, 300, 70_000] {
+            write_length(&mut data, length);
+        }
+        let mut cursor = 0;
+        for length in [0, 127, 128, 300, 70_000] {
+            assert_eq!(read_length(&data, &mut cursor), length);
+        }
+        assert_eq!(cursor, data.len());
+    }
+
+    #[test]
+    fn drops_the_oldest_snapshots_past_the_capacity() {
+        let mut cpu = CPU::new();
+        cpu.memory.load_rom(&[0; 0x8000]).unwrap();
+        let mut rewind = Rewind::new(1, 64);
+        assert!(rewind.is_empty());
+        for value in 0..20 {
+            cpu.memory.memory[0xC000..0xC010].fill(value);
+            rewind.record_frame(&cpu);
+        }
+        assert!(rewind.used_bytes() - cpu.save_state().len() <= 64);
+        assert!(rewind.len() < 20);
+    }
+
+    #[test]
+    fn steps_back_over_serial_output() {
+        let mut cpu = CPU::new();
+        cpu.memory.load_rom(&[0; 0x8000]).unwrap();
+        let mut rewind = Rewind::new(1, 1 << 20);
+        rewind.record_frame(&cpu);
+        cpu.memory.serial.push(b'P');
+        rewind.record_frame(&cpu);
+        cpu.memory.serial.push(b'a');
+        rewind.record_frame(&cpu);
+        assert!(rewind.step_back(&mut cpu).unwrap());
+        assert_eq!(cpu.memory.serial, b"P");
+        assert!(rewind.step_back(&mut cpu).unwrap());
+        assert!(cpu.memory.serial.is_empty());
+        assert!(!rewind.step_back(&mut cpu).unwrap());
+    }
+}
@@ -0,0 +1,229 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::cpu::CPU;
+use crate::error::EmuError;
+use crate::memory::ROM_SIZE;
+use crate::movie::rom_checksum;
+
+// Layout, everything little endian:
+//   "RGBS"  magic
+//   u16     format version
+//   chunks  4 byte tag, u32 payload length, payload
+//
+// "CART" holds the header checksum of the ROM the state belongs to, "CPU " the registers and
+// the T-cycle counter, "BUS " the boot ROM flag, the held buttons and 0x8000-0xFFFF. The ROM
+// itself isn't saved, it comes from the cartridge. "SERI" is everything sent over the link cable.
+// Cartridge RAM and the PPU/timer/APU/serial registers live on the bus for now, so they're
+// covered by "BUS ". Once they become components of their own they get their own chunk and a version bump.
+const MAGIC: &[u8; 4] = b"RGBS";
+pub const SAVE_STATE_VERSION: u16 = 3;
+
+const CART_CHUNK: &[u8; 4] = b"CART";
+const CPU_CHUNK: &[u8; 4] = b"CPU ";
+const BUS_CHUNK: &[u8; 4] = b"BUS ";
+const SERIAL_CHUNK: &[u8; 4] = b"SERI";
+// The boot ROM flag and the joypad come before the memory
+const BUS_HEADER: usize = 2;
+const BUS_SIZE: usize = BUS_HEADER + 0x10000 - ROM_SIZE;
+
+const IME_BIT: u8 = 1 << 0;
+const HALTED_BIT: u8 = 1 << 1;
+const STOPPED_BIT: u8 = 1 << 2;
+const LOCKED_BIT: u8 = 1 << 3;
+
+impl CPU {
+    /// Snapshots the whole machine
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(BUS_SIZE + self.memory.serial.len() + 64);
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&SAVE_STATE_VERSION.to_le_bytes());
+        write_chunk(&mut data, CART_CHUNK, &rom_checksum(self).to_le_bytes());
+
+        let registry = &self.registry;
+        let mut state_bits = 0;
+        if registry.interrupts_enabled { state_bits |= IME_BIT; }
+        if registry.halted { state_bits |= HALTED_BIT; }
+        if registry.verylowpowermode { state_bits |= STOPPED_BIT; }
+        if registry.locked { state_bits |= LOCKED_BIT; }
+        let mut cpu = vec![
+            registry.a, registry.f.get_flags(),
+            registry.b, registry.c,
+            registry.d, registry.e,
+            registry.h, registry.l,
+        ];
+        cpu.extend_from_slice(&registry.sp.to_le_bytes());
+        cpu.extend_from_slice(&registry.pc.to_le_bytes());
+        cpu.push(state_bits);
+        cpu.extend_from_slice(&self.cycles.to_le_bytes());
+        write_chunk(&mut data, CPU_CHUNK, &cpu);
+
+        let mut bus = Vec::with_capacity(BUS_SIZE);
+        bus.push(self.memory.in_bootrom as u8);
+        bus.push(self.memory.joypad);
+        bus.extend_from_slice(&self.memory.memory[ROM_SIZE..]);
+        write_chunk(&mut data, BUS_CHUNK, &bus);
+        write_chunk(&mut data, SERIAL_CHUNK, &self.memory.serial);
+
+        data
+    }
+
+    /// Restores a snapshot taken with `save_state` with the same ROM loaded,
+    /// nothing is touched if it can't be read
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), EmuError> {
+        if data.len() < 6 || &data[..4] != MAGIC {
+            return Err(EmuError::InvalidSaveState("not a save state"));
+        }
+        let version = u16::from_le_bytes([data[4], data[5]]);
+        if version != SAVE_STATE_VERSION {
+            return Err(EmuError::SaveStateVersion { found: version, expected: SAVE_STATE_VERSION });
+        }
+
+        let mut cart = None;
+        let mut cpu = None;
+        let mut bus = None;
+        let mut serial = None;
+        let mut rest = &data[6..];
+        while !rest.is_empty() {
+            if rest.len() < 8 {
+                return Err(EmuError::InvalidSaveState("truncated chunk header"));
+            }
+            let tag = &rest[..4];
+            let length = u32::from_le_bytes([rest[4], rest[5], rest[6], rest[7]]) as usize;
+            if rest.len() < 8 + length {
+                return Err(EmuError::InvalidSaveState("truncated chunk"));
+            }
+            let payload = &rest[8..8 + length];
+            match tag {
+                _ if tag == CART_CHUNK => cart = Some(payload),
+                _ if tag == CPU_CHUNK => cpu = Some(payload),
+                _ if tag == BUS_CHUNK => bus = Some(payload),
+                _ if tag == SERIAL_CHUNK => serial = Some(payload),
+                _ => return Err(EmuError::InvalidSaveState("unknown chunk")),
+            }
+            rest = &rest[8 + length..];
+        }
+
+        let cart = cart.ok_or(EmuError::InvalidSaveState("missing CART chunk"))?;
+        let cpu = cpu.ok_or(EmuError::InvalidSaveState("missing CPU chunk"))?;
+        let bus = bus.ok_or(EmuError::InvalidSaveState("missing BUS chunk"))?;
+        let serial = serial.ok_or(EmuError::InvalidSaveState("missing SERI chunk"))?;
+        if cart.len() != 2 {
+            return Err(EmuError::InvalidSaveState("CART chunk has the wrong size"));
+        }
+        if cpu.len() != 21 {
+            return Err(EmuError::InvalidSaveState("CPU chunk has the wrong size"));
+        }
+        if bus.len() != BUS_SIZE {
+            return Err(EmuError::InvalidSaveState("BUS chunk has the wrong size"));
+        }
+        let checksum = u16::from_le_bytes([cart[0], cart[1]]);
+        if checksum != rom_checksum(self) {
+            return Err(EmuError::SaveStateRomMismatch { found: rom_checksum(self), expected: checksum });
+        }
+
+        let registry = &mut self.registry;
+        registry.a = cpu[0];
+        registry.f.set_flags(cpu[1]);
+        registry.b = cpu[2];
+        registry.c = cpu[3];
+        registry.d = cpu[4];
+        registry.e = cpu[5];
+        registry.h = cpu[6];
+        registry.l = cpu[7];
+        registry.sp = u16::from_le_bytes([cpu[8], cpu[9]]);
+        registry.pc = u16::from_le_bytes([cpu[10], cpu[11]]);
+        registry.interrupts_enabled = cpu[12] & IME_BIT != 0;
+        registry.halted = cpu[12] & HALTED_BIT != 0;
+        registry.verylowpowermode = cpu[12] & STOPPED_BIT != 0;
+        registry.locked = cpu[12] & LOCKED_BIT != 0;
+        let mut cycles = [0; 8];
+        cycles.copy_from_slice(&cpu[13..21]);
+        self.cycles = u64::from_le_bytes(cycles);
+
+        self.memory.in_bootrom = bus[0] != 0;
+        self.memory.joypad = bus[1];
+        // Anything written over the ROM goes away, the same as on a reset
+        self.memory.map_rom();
+        self.memory.memory[ROM_SIZE..].copy_from_slice(&bus[BUS_HEADER..]);
+        self.memory.serial = serial.to_vec();
+        Ok(())
+    }
+}
+
+fn write_chunk(data: &mut Vec<u8>, tag: &[u8; 4], payload: &[u8]) {
+    data.extend_from_slice(tag);
+    data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    data.extend_from_slice(payload);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cpu_with_checksum(checksum: u16) -> CPU {
+        let mut rom = vec![0; ROM_SIZE];
+        rom[0x014E..0x0150].copy_from_slice(&checksum.to_be_bytes());
+        let mut cpu = CPU::new();
+        cpu.memory.load_rom(&rom).unwrap();
+        cpu
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut cpu = cpu_with_checksum(0x1234);
+        cpu.registry.a = 0x42;
+        cpu.registry.f.set_flags(0xB0);
+        cpu.registry.set_hl(0xC0DE);
+        cpu.registry.sp = 0xFFFE;
+        cpu.registry.pc = 0x0150;
+        cpu.registry.interrupts_enabled = true;
+        cpu.cycles = 123_456_789;
+        cpu.memory.in_bootrom = false;
+        cpu.memory.joypad = 0x81;
+        cpu.memory.serial = b"Passed".to_vec();
+        cpu.memory.memory[0xC000] = 0x99;
+        cpu.memory.memory[0xFFFF] = 0x1F;
+        let state = cpu.save_state();
+
+        let mut restored = cpu_with_checksum(0x1234);
+        restored.load_state(&state).unwrap();
+        assert_eq!(restored.registry.a, 0x42);
+        assert_eq!(restored.registry.f.get_flags(), 0xB0);
+        assert_eq!(restored.registry.get_hl(), 0xC0DE);
+        assert_eq!(restored.registry.sp, 0xFFFE);
+        assert_eq!(restored.registry.pc, 0x0150);
+        assert!(restored.registry.interrupts_enabled);
+        assert_eq!(restored.cycles, 123_456_789);
+        assert!(!restored.memory.in_bootrom);
+        assert_eq!(restored.memory.joypad, 0x81);
+        assert_eq!(restored.memory.serial, b"Passed");
+        assert_eq!(restored.memory.memory[..], cpu.memory.memory[..]);
+        assert_eq!(restored.save_state(), state);
+    }
+
+    #[test]
+    fn leaves_the_rom_out() {
+        let state = cpu_with_checksum(0x1234).save_state();
+        assert!(state.len() < 0x10000 - ROM_SIZE + 64);
+    }
+
+    #[test]
+    fn rejects_a_different_rom() {
+        let state = cpu_with_checksum(0x1234).save_state();
+        let mut other = cpu_with_checksum(0x5678);
+        other.registry.a = 0x42;
+        assert!(matches!(other.load_state(&state), Err(EmuError::SaveStateRomMismatch { found: 0x5678, expected: 0x1234 })));
+        assert_eq!(other.registry.a, 0x42);
+    }
+
+    #[test]
+    fn rejects_broken_data() {
+        let mut cpu = cpu_with_checksum(0x1234);
+        let mut state = cpu.save_state();
+        assert!(matches!(cpu.load_state(b"nope"), Err(EmuError::InvalidSaveState(_))));
+        assert!(matches!(cpu.load_state(&state[..state.len() - 1]), Err(EmuError::InvalidSaveState(_))));
+        state[4] = 2;
+        assert!(matches!(cpu.load_state(&state), Err(EmuError::SaveStateVersion { found: 2, .. })));
+    }
+}
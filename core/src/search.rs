@@ -0,0 +1,145 @@
+use alloc::vec::Vec;
+use core::ops::RangeInclusive;
+
+use crate::memory::Memory;
+
+/// Where a game keeps its variables, cartridge RAM, WRAM and HRAM
+pub const SEARCH_RANGES: [RangeInclusive<u16>; 3] = [0xA000..=0xBFFF, 0xC000..=0xDFFF, 0xFF80..=0xFFFE];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Width {
+    Byte,
+    /// Little endian, like the CPU stores them
+    Word,
+}
+
+/// How a candidate's value has to compare to the last time it was looked at to stay in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Filter {
+    Equal,
+    Changed,
+    Increased,
+    Decreased,
+    Value(u16),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Candidate {
+    pub address: u16,
+    /// The value when the last filter ran, or when the search started
+    pub previous: u16,
+}
+
+/// Narrows RAM down to the addresses that behave like the value being looked for,
+/// e.g. start, lose a life, filter by Decreased, repeat
+#[derive(Debug, Clone)]
+pub struct RamSearch {
+    pub width: Width,
+    pub candidates: Vec<Candidate>,
+}
+
+impl Width {
+    /// Reads through `peek` so searching doesn't trip watchpoints
+    pub fn read(self, memory: &Memory, address: u16) -> u16 {
+        match self {
+            Width::Byte => memory.peek(address) as u16,
+            Width::Word => memory.peek_word(address),
+        }
+    }
+
+    fn bytes(self) -> u16 {
+        match self {
+            Width::Byte => 1,
+            Width::Word => 2,
+        }
+    }
+}
+
+impl Filter {
+    fn keeps(self, previous: u16, current: u16) -> bool {
+        match self {
+            Filter::Equal => current == previous,
+            Filter::Changed => current != previous,
+            Filter::Increased => current > previous,
+            Filter::Decreased => current < previous,
+            Filter::Value(value) => current == value,
+        }
+    }
+}
+
+impl RamSearch {
+    /// Every address in `SEARCH_RANGES` with its value right now, words don't cross the end of a range
+    pub fn start(memory: &Memory, width: Width) -> RamSearch {
+        let candidates = SEARCH_RANGES
+            .iter()
+            .flat_map(|range| *range.start()..=*range.end() + 1 - width.bytes())
+            .map(|address| Candidate { address, previous: width.read(memory, address) })
+            .collect();
+        RamSearch { width, candidates }
+    }
+
+    /// Drops every candidate `filter` doesn't keep and remembers the new values of the rest
+    pub fn filter(&mut self, memory: &Memory, filter: Filter) {
+        let width = self.width;
+        self.candidates.retain_mut(|candidate| {
+            let current = width.read(memory, candidate.address);
+            let keep = filter.keeps(candidate.previous, current);
+            candidate.previous = current;
+            keep
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addresses(search: &RamSearch) -> Vec<u16> {
+        search.candidates.iter().map(|candidate| candidate.address).collect()
+    }
+
+    #[test]
+    fn starts_with_every_ram_address() {
+        let memory = Memory::new();
+        assert_eq!(RamSearch::start(&memory, Width::Byte).candidates.len(), 0x2000 + 0x2000 + 0x7F);
+        // Words don't start on the last byte of a range
+        let words = RamSearch::start(&memory, Width::Word);
+        assert_eq!(words.candidates.len(), 0x1FFF + 0x1FFF + 0x7E);
+        assert!(!addresses(&words).contains(&0xDFFF));
+    }
+
+    #[test]
+    fn narrows_down_to_the_value_that_went_down() {
+        let mut memory = Memory::new();
+        memory.memory[0xC100] = 3;
+        memory.memory[0xC200] = 3;
+        let mut search = RamSearch::start(&memory, Width::Byte);
+        search.filter(&memory, Filter::Value(3));
+        assert_eq!(addresses(&search), [0xC100, 0xC200]);
+
+        memory.memory[0xC100] = 2;
+        memory.memory[0xC200] = 4;
+        search.filter(&memory, Filter::Decreased);
+        assert_eq!(addresses(&search), [0xC100]);
+
+        search.filter(&memory, Filter::Equal);
+        assert_eq!(addresses(&search), [0xC100]);
+        memory.memory[0xC100] = 1;
+        search.filter(&memory, Filter::Changed);
+        assert_eq!(search.candidates[0].previous, 1);
+    }
+
+    #[test]
+    fn words_are_little_endian() {
+        let mut memory = Memory::new();
+        memory.memory[0xC000] = 0x34;
+        memory.memory[0xC001] = 0x12;
+        let mut search = RamSearch::start(&memory, Width::Word);
+        search.filter(&memory, Filter::Value(0x1234));
+        assert_eq!(addresses(&search), [0xC000]);
+
+        memory.memory[0xC001] = 0x13;
+        search.filter(&memory, Filter::Increased);
+        assert_eq!(addresses(&search), [0xC000]);
+    }
+}
@@ -0,0 +1,137 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+
+/// Labels from a `.sym` file as RGBDS and WLA write them, one `bank:address name` per line
+#[derive(Clone, Default)]
+pub struct Symbols {
+    labels: BTreeMap<(u8, u16), String>,
+    addresses: BTreeMap<String, (u8, u16)>,
+}
+
+// There's no MBC yet, so 0x4000-0x7FFF always holds ROM bank 1. 0xD000-0xDFFF is WRAM bank 1,
+// the only one a DMG has. Everything else is bank 0.
+fn bank_of(address: u16) -> u8 {
+    match address {
+        0x4000..=0x7FFF | 0xD000..=0xDFFF => 1,
+        _ => 0,
+    }
+}
+
+impl Symbols {
+    pub fn new() -> Symbols {
+        Symbols::default()
+    }
+
+    pub fn parse(text: &str) -> Result<Symbols, String> {
+        let mut symbols = Symbols::new();
+        // WLA puts other things in sections like [definitions], only [labels] has addresses
+        let mut in_labels = true;
+        for (index, line) in text.lines().enumerate() {
+            let line = line.split(';').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') {
+                in_labels = line == "[labels]";
+                continue;
+            }
+            if !in_labels {
+                continue;
+            }
+            let invalid = || format!("Line {} isn't a symbol: {}", index + 1, line);
+            let (location, name) = line.split_once(char::is_whitespace).ok_or_else(invalid)?;
+            let (bank, address) = location.split_once(':').ok_or_else(invalid)?;
+            let bank = u8::from_str_radix(bank, 16).map_err(|_| invalid())?;
+            let address = u16::from_str_radix(address, 16).map_err(|_| invalid())?;
+            symbols.insert(bank, address, name.trim());
+        }
+        Ok(symbols)
+    }
+
+    fn insert(&mut self, bank: u8, address: u16, name: &str) {
+        // The first label on an address wins, that's usually the global one before its locals
+        self.labels.entry((bank, address)).or_insert_with(|| name.to_string());
+        self.addresses.insert(name.to_string(), (bank, address));
+    }
+
+    /// Label on `address` as the CPU sees it right now
+    pub fn label(&self, address: u16) -> Option<&str> {
+        self.labels.get(&(bank_of(address), address)).map(|name| name.as_str())
+    }
+
+    /// The closest label at or before `address` in the same bank and how far past it `address` is
+    pub fn nearest(&self, address: u16) -> Option<(&str, u16)> {
+        let bank = bank_of(address);
+        let ((_, start), name) = self.labels.range((bank, 0)..=(bank, address)).next_back()?;
+        Some((name.as_str(), address - start))
+    }
+
+    pub fn address_of(&self, name: &str) -> Option<u16> {
+        self.addresses.get(name).map(|(_, address)| *address)
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RGBDS: &str = "; File generated by rgblink\n00:0150 Main\n00:0150 Main.loop\n00:0160 Main.end ; trailing comment\n01:4000 BankedCode\n00:C000 wBuffer\n01:D000 wBankedBuffer\n";
+
+    #[test]
+    fn parses_rgbds_files() {
+        let symbols = Symbols::parse(RGBDS).unwrap();
+        assert_eq!(symbols.len(), 5);
+        assert_eq!(symbols.label(0x0150), Some("Main"));
+        assert_eq!(symbols.label(0x0160), Some("Main.end"));
+        assert_eq!(symbols.label(0x4000), Some("BankedCode"));
+        assert_eq!(symbols.label(0x0151), None);
+        assert_eq!(symbols.label(0xD000), Some("wBankedBuffer"));
+        assert_eq!(symbols.address_of("Main.loop"), Some(0x0150));
+        assert_eq!(symbols.address_of("wBuffer"), Some(0xC000));
+        assert_eq!(symbols.address_of("Missing"), None);
+    }
+
+    #[test]
+    fn only_reads_the_labels_section_of_wla_files() {
+        let symbols = Symbols::parse("[labels]\n00:0150 Start\n\n[definitions]\n00000010 _sizeof_x\n").unwrap();
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols.address_of("_sizeof_x"), None);
+    }
+
+    #[test]
+    fn labels_only_match_the_bank_that_is_mapped() {
+        // Bank 2 never shows up at 0x4000 without an MBC
+        let symbols = Symbols::parse("02:4000 OtherBank\n").unwrap();
+        assert_eq!(symbols.label(0x4000), None);
+        assert_eq!(symbols.nearest(0x4010), None);
+    }
+
+    #[test]
+    fn nearest_label_and_offset() {
+        let symbols = Symbols::parse(RGBDS).unwrap();
+        assert_eq!(symbols.nearest(0x0150), Some(("Main", 0)));
+        assert_eq!(symbols.nearest(0x0158), Some(("Main", 8)));
+        assert_eq!(symbols.nearest(0x0163), Some(("Main.end", 3)));
+        assert_eq!(symbols.nearest(0x0100), None);
+        // Nothing in bank 1 comes before 0x4000
+        assert_eq!(symbols.nearest(0x4002), Some(("BankedCode", 2)));
+        // RGBDS puts WRAMX labels in bank 1 as well
+        assert_eq!(symbols.nearest(0xD010), Some(("wBankedBuffer", 0x10)));
+    }
+
+    #[test]
+    fn rejects_lines_that_arent_symbols() {
+        assert_eq!(Symbols::parse("00:0150 Main\nMain\n").err(), Some("Line 2 isn't a symbol: Main".to_string()));
+        assert!(Symbols::parse("0150 Main\n").is_err());
+        assert!(Symbols::parse("00:XYZW Main\n").is_err());
+    }
+}
@@ -0,0 +1,172 @@
+use core::fmt;
+
+use crate::cpu::CPU;
+
+/// The machine right before an instruction, printed in the Gameboy Doctor log format:
+///
+///   A:01 F:B0 B:00 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0100 PCMEM:00,C3,13,02
+///
+/// Reference logs start after the boot ROM, so compare from PC 0100 on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TraceLine {
+    pub a: u8,
+    pub f: u8,
+    pub b: u8,
+    pub c: u8,
+    pub d: u8,
+    pub e: u8,
+    pub h: u8,
+    pub l: u8,
+    pub sp: u16,
+    pub pc: u16,
+    pub pc_memory: [u8; 4],
+}
+
+impl TraceLine {
+    pub fn capture(cpu: &CPU) -> TraceLine {
+        let registry = &cpu.registry;
+        let pc = registry.pc;
+        // Peeked so tracing doesn't trip watchpoints
+        let byte = |offset| cpu.memory.peek(pc.wrapping_add(offset));
+        TraceLine {
+            a: registry.a,
+            f: registry.f.get_flags(),
+            b: registry.b,
+            c: registry.c,
+            d: registry.d,
+            e: registry.e,
+            h: registry.h,
+            l: registry.l,
+            sp: registry.sp,
+            pc,
+            pc_memory: [byte(0), byte(1), byte(2), byte(3)],
+        }
+    }
+
+    /// Reads a line in the format `Display` writes, None if it isn't one
+    pub fn parse(line: &str) -> Option<TraceLine> {
+        let mut trace = TraceLine { a: 0, f: 0, b: 0, c: 0, d: 0, e: 0, h: 0, l: 0, sp: 0, pc: 0, pc_memory: [0; 4] };
+        let mut seen = 0;
+        for field in line.split_whitespace() {
+            let (name, value) = field.split_once(':')?;
+            let byte = || u8::from_str_radix(value, 16).ok();
+            let word = || u16::from_str_radix(value, 16).ok();
+            match name {
+                "A" => trace.a = byte()?,
+                "F" => trace.f = byte()?,
+                "B" => trace.b = byte()?,
+                "C" => trace.c = byte()?,
+                "D" => trace.d = byte()?,
+                "E" => trace.e = byte()?,
+                "H" => trace.h = byte()?,
+                "L" => trace.l = byte()?,
+                "SP" => trace.sp = word()?,
+                "PC" => trace.pc = word()?,
+                "PCMEM" => {
+                    let mut bytes = value.split(',');
+                    for byte in trace.pc_memory.iter_mut() {
+                        *byte = u8::from_str_radix(bytes.next()?, 16).ok()?;
+                    }
+                    if bytes.next().is_some() {
+                        return None;
+                    }
+                }
+                _ => return None,
+            }
+            seen += 1;
+        }
+        (seen == 11).then_some(trace)
+    }
+
+    /// Names of the fields that differ from `other`, F is split into its flags
+    pub fn differences(&self, other: &TraceLine) -> impl Iterator<Item = &'static str> {
+        let flag = |bit: u8| (self.f ^ other.f) & (1 << bit) != 0;
+        [
+            ("A", self.a != other.a),
+            ("Z flag", flag(7)),
+            ("N flag", flag(6)),
+            ("H flag", flag(5)),
+            ("C flag", flag(4)),
+            ("F low bits", (self.f ^ other.f) & 0x0F != 0),
+            ("B", self.b != other.b),
+            ("C", self.c != other.c),
+            ("D", self.d != other.d),
+            ("E", self.e != other.e),
+            ("H", self.h != other.h),
+            ("L", self.l != other.l),
+            ("SP", self.sp != other.sp),
+            ("PC", self.pc != other.pc),
+            ("PCMEM", self.pc_memory != other.pc_memory),
+        ]
+        .into_iter()
+        .filter(|(_, differs)| *differs)
+        .map(|(name, _)| name)
+    }
+}
+
+impl fmt::Display for TraceLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [m0, m1, m2, m3] = self.pc_memory;
+        write!(
+            f,
+            "A:{:02X} F:{:02X} B:{:02X} C:{:02X} D:{:02X} E:{:02X} H:{:02X} L:{:02X} SP:{:04X} PC:{:04X} PCMEM:{:02X},{:02X},{:02X},{:02X}",
+            self.a, self.f, self.b, self.c, self.d, self.e, self.h, self.l, self.sp, self.pc, m0, m1, m2, m3,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    const LINE: &str = "A:01 F:B0 B:00 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0100 PCMEM:00,C3,13,02";
+
+    #[test]
+    fn captures_the_registers_and_the_bytes_at_pc() {
+        let mut cpu = CPU::new();
+        cpu.memory.load_rom(&[0; 0x8000]).unwrap();
+        cpu.memory.in_bootrom = false;
+        cpu.memory.memory[0x0100..0x0104].copy_from_slice(&[0x00, 0xC3, 0x13, 0x02]);
+        let registry = &mut cpu.registry;
+        registry.a = 0x01;
+        registry.f.set_flags(0xB0);
+        registry.c = 0x13;
+        registry.e = 0xD8;
+        registry.h = 0x01;
+        registry.l = 0x4D;
+        registry.sp = 0xFFFE;
+        registry.pc = 0x0100;
+        assert_eq!(TraceLine::capture(&cpu).to_string(), LINE);
+    }
+
+    #[test]
+    fn parses_what_it_prints() {
+        let line = TraceLine::parse(LINE).unwrap();
+        assert_eq!((line.a, line.f, line.sp, line.pc), (0x01, 0xB0, 0xFFFE, 0x0100));
+        assert_eq!(line.pc_memory, [0x00, 0xC3, 0x13, 0x02]);
+        assert_eq!(line.to_string(), LINE);
+    }
+
+    #[test]
+    fn rejects_anything_else() {
+        assert_eq!(TraceLine::parse(""), None);
+        assert_eq!(TraceLine::parse(&LINE.replace(" L:4D", "")), None);
+        assert_eq!(TraceLine::parse(&LINE.replace("A:01", "A:0G")), None);
+        assert_eq!(TraceLine::parse(&LINE.replace("A:01", "X:01")), None);
+        assert_eq!(TraceLine::parse(&LINE.replace("13,02", "13")), None);
+        assert_eq!(TraceLine::parse(&LINE.replace("13,02", "13,02,00")), None);
+    }
+
+    #[test]
+    fn names_the_flags_that_differ() {
+        let expected = TraceLine::parse(LINE).unwrap();
+        let actual = TraceLine { f: 0x90, sp: 0xFFFC, ..expected };
+        assert_eq!(expected.differences(&actual).collect::<Vec<_>>(), ["H flag", "SP"]);
+        let actual = TraceLine { f: 0xB1, ..expected };
+        assert_eq!(expected.differences(&actual).collect::<Vec<_>>(), ["F low bits"]);
+        assert_eq!(expected.differences(&expected).count(), 0);
+    }
+}
@@ -0,0 +1,499 @@
+// Runs a ROM without a window, meant for CI and test ROMs:
+//
+//   rutile-cli game.gb --frames 600 --png screen.png --registers registers.json
+//   rutile-cli game.gb --movie bug.gbm --gif bug.gif --scale 3 --palette Pocket
+//   rutile-cli cpu_instrs.gb --until-serial Passed --frames 3600
+//   rutile-cli game.gb --break '$0150' --break '$C000 A == $10' --break 'Main'
+//   rutile-cli cpu_instrs.gb --frames 60 --trace trace.log
+//   rutile-cli game.gb --frames 3600 --profile profile.csv
+//   rutile-cli 01-special.gb --frames 3600 --compare reference/01-special.log --context 20
+//
+// Whatever the game sent over serial is written to stdout. The exit status is 0 when the run
+// stopped the way it was asked to, 1 when emulation failed or a stop condition never happened
+// within the frame limit and 2 for bad arguments or files that couldn't be read or written.
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Lines, Write};
+use std::process::ExitCode;
+
+use rutile_gb_core::capture::GifEncoder;
+use rutile_gb_core::cpu::{disassembler, CPU, IllegalOpcodePolicy};
+use rutile_gb_core::debugger::{parse_number, Debugger, StopReason};
+use rutile_gb_core::error::EmuError;
+use rutile_gb_core::movie::Movie;
+use rutile_gb_core::palette::{self, Palette};
+use rutile_gb_core::ppu;
+use rutile_gb_core::profiler::Profiler;
+use rutile_gb_core::symbols::Symbols;
+use rutile_gb_core::trace::TraceLine;
+
+#[path = "../image.rs"]
+mod image;
+
+const DEFAULT_FRAMES: u64 = 600;
+const DEFAULT_CONTEXT: usize = 10;
+
+const USAGE: &str = "Usage: rutile-cli <rom> [options]
+  --frames <n>           stop after n frames (600, or the movie length)
+  --until-serial <text>  stop once the serial output contains text
+  --break <addr [cond]>  stop at a breakpoint, can be given more than once. Labels from
+                         the .sym file next to the ROM work as addresses
+  --movie <file>         take the input from a recorded movie
+  --png <file>           write the final frame as PNG
+  --scale <n>            blow up --png, --gif and --frames-dir n times (1)
+  --gif <file>           record every frame into an animated GIF
+  --frames-dir <dir>     write every frame as a numbered PNG into dir
+  --palette <name>       colours for the images: Grey, Classic green, Pocket, Light or
+                         one from --palettes (Grey)
+  --palettes <file>      palettes, one `name: #RRGGBB #RRGGBB #RRGGBB #RRGGBB` per line
+  --registers <file>     write the final registers as JSON
+  --trace <file>         log every instruction in the Gameboy Doctor format
+  --compare <file>       check every instruction against a Gameboy Doctor log, from the end of the boot ROM on
+  --context <n>          lines shown before a divergence (10)
+  --profile <file>       write per-address and per-function counters as CSV";
+
+struct Options {
+    rom: String,
+    frames: Option<u64>,
+    until_serial: Option<String>,
+    breakpoints: Vec<String>,
+    movie: Option<String>,
+    png: Option<String>,
+    scale: usize,
+    gif: Option<String>,
+    frames_dir: Option<String>,
+    palette: String,
+    palettes: Option<String>,
+    registers: Option<String>,
+    trace: Option<String>,
+    compare: Option<String>,
+    context: usize,
+    profile: Option<String>,
+}
+
+enum Outcome {
+    FrameLimit,
+    Serial,
+    Breakpoint(u16),
+    Diverged(Box<Divergence>),
+    ReferenceEnded,
+}
+
+/// Reads the reference log as the run goes, so a long one never sits in memory
+struct Comparison {
+    reference: Lines<BufReader<File>>,
+    line_number: u64,
+    // The last `context` lines that matched
+    recent: VecDeque<String>,
+    context: usize,
+    last_pc: Option<u16>,
+}
+
+/// The LCD recorded at the end of every frame
+struct Capture {
+    scale: usize,
+    palette: Palette,
+    gif: Option<GifEncoder>,
+    frames_dir: Option<String>,
+    frames: u64,
+}
+
+impl Capture {
+    fn add_frame(&mut self, cpu: &CPU) -> Result<(), String> {
+        if self.gif.is_none() && self.frames_dir.is_none() {
+            return Ok(());
+        }
+        let screen = ppu::render(&cpu.memory);
+        if let Some(gif) = &mut self.gif {
+            gif.add_frame(&screen);
+        }
+        if let Some(directory) = &self.frames_dir {
+            let path = std::path::Path::new(directory).join(format!("{:05}.png", self.frames));
+            std::fs::write(&path, image::png(&screen, self.scale, self.palette)?).map_err(|err| format!("Couldn't write {}: {}", path.display(), err))?;
+        }
+        self.frames += 1;
+        Ok(())
+    }
+}
+
+/// What `emulate` runs towards and everything it feeds along the way
+struct Run<'a> {
+    movie: Option<&'a Movie>,
+    frames: u64,
+    until_serial: Option<&'a str>,
+    trace: Option<BufWriter<File>>,
+    comparison: Option<Comparison>,
+    capture: Capture,
+}
+
+struct Divergence {
+    line_number: u64,
+    recent: VecDeque<String>,
+    expected: TraceLine,
+    actual: TraceLine,
+    // The last instruction that ran, it's the one that got something wrong
+    instruction: Option<(u16, String)>,
+}
+
+impl Comparison {
+    fn open(path: &str, context: usize) -> Result<Comparison, String> {
+        let file = File::open(path).map_err(|err| format!("Couldn't read {}: {}", path, err))?;
+        Ok(Comparison { reference: BufReader::new(file).lines(), line_number: 0, recent: VecDeque::new(), context, last_pc: None })
+    }
+
+    /// Checks the instruction about to run, None while it matches
+    fn check(&mut self, cpu: &CPU) -> Result<Option<Outcome>, String> {
+        let actual = TraceLine::capture(cpu);
+        let Some(line) = self.reference.next() else {
+            return Ok(Some(Outcome::ReferenceEnded));
+        };
+        let line = line.map_err(|err| err.to_string())?;
+        self.line_number += 1;
+        let expected = TraceLine::parse(&line).ok_or(format!("Line {} of the reference isn't a trace line", self.line_number))?;
+        if expected != actual {
+            return Ok(Some(Outcome::Diverged(Box::new(Divergence {
+                line_number: self.line_number,
+                recent: std::mem::take(&mut self.recent),
+                expected,
+                actual,
+                instruction: self.last_pc.map(|pc| (pc, disassembler::disassemble(&cpu.memory, pc).text)),
+            }))));
+        }
+        self.last_pc = Some(actual.pc);
+        self.recent.push_back(line);
+        if self.recent.len() > self.context {
+            self.recent.pop_front();
+        }
+        Ok(None)
+    }
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match run(&options) {
+        Ok(status) => ExitCode::from(status),
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        rom: String::new(),
+        frames: None,
+        until_serial: None,
+        breakpoints: Vec::new(),
+        movie: None,
+        png: None,
+        scale: 1,
+        gif: None,
+        frames_dir: None,
+        palette: palette::PRESETS[0].0.to_string(),
+        palettes: None,
+        registers: None,
+        trace: None,
+        compare: None,
+        context: DEFAULT_CONTEXT,
+        profile: None,
+    };
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--frames" => options.frames = Some(value()?.parse().map_err(|_| "--frames needs a number")?),
+            "--until-serial" => options.until_serial = Some(Some(value()?).filter(|text| !text.is_empty()).ok_or("--until-serial needs some text")?),
+            "--break" => options.breakpoints.push(value()?),
+            "--movie" => options.movie = Some(value()?),
+            "--png" => options.png = Some(value()?),
+            "--scale" => options.scale = value()?.parse().ok().filter(|scale| (1..=16).contains(scale)).ok_or("--scale needs a number from 1 to 16")?,
+            "--gif" => options.gif = Some(value()?),
+            "--frames-dir" => options.frames_dir = Some(value()?),
+            "--palette" => options.palette = value()?,
+            "--palettes" => options.palettes = Some(value()?),
+            "--registers" => options.registers = Some(value()?),
+            "--trace" => options.trace = Some(value()?),
+            "--compare" => options.compare = Some(value()?),
+            "--profile" => options.profile = Some(value()?),
+            "--context" => options.context = value()?.parse().map_err(|_| "--context needs a number")?,
+            "-h" | "--help" => return Err("rutile-cli runs a ROM without a window".to_string()),
+            _ if arg.starts_with("--") => return Err(format!("Unknown option {}", arg)),
+            _ if options.rom.is_empty() => options.rom = arg,
+            _ => return Err(format!("Unexpected argument {}", arg)),
+        }
+    }
+    if options.rom.is_empty() {
+        return Err("No ROM given".to_string());
+    }
+    Ok(options)
+}
+
+// Ok holds the exit status, Err is for anything outside of the emulation going wrong
+fn run(options: &Options) -> Result<u8, String> {
+    let mut cpu = CPU::new();
+    cpu.illegal_opcode_policy = IllegalOpcodePolicy::Error;
+    let rom = std::fs::read(&options.rom).map_err(|err| format!("Couldn't read {}: {}", options.rom, err))?;
+    cpu.memory.load_rom(&rom).map_err(|err| err.to_string())?;
+
+    // Optional, RGBDS and WLA write game.sym next to game.gb
+    let symbols = match std::fs::read_to_string(std::path::Path::new(&options.rom).with_extension("sym")) {
+        Ok(text) => Symbols::parse(&text)?,
+        Err(_) => Symbols::new(),
+    };
+
+    let mut debugger = Debugger::new();
+    for breakpoint in &options.breakpoints {
+        let (address, condition) = breakpoint.split_once(' ').unwrap_or((breakpoint, ""));
+        let address = parse_number(address)
+            .or_else(|| symbols.address_of(address))
+            .ok_or(format!("Invalid breakpoint address {}", address))?;
+        debugger.add_breakpoint(address, condition.trim())?;
+    }
+
+    if options.profile.is_some() {
+        debugger.profiler = Some(Box::new(Profiler::new()));
+    }
+
+    let movie = match &options.movie {
+        Some(path) => {
+            let data = std::fs::read(path).map_err(|err| format!("Couldn't read {}: {}", path, err))?;
+            let movie = Movie::from_bytes(&data).map_err(|err| err.to_string())?;
+            movie.start(&mut cpu).map_err(|err| err.to_string())?;
+            Some(movie)
+        }
+        None => None,
+    };
+    let frames = options.frames
+        .or(movie.as_ref().map(|movie| movie.inputs.len() as u64))
+        .unwrap_or(DEFAULT_FRAMES);
+
+    let trace = match &options.trace {
+        Some(path) => Some(BufWriter::new(File::create(path).map_err(|err| format!("Couldn't create {}: {}", path, err))?)),
+        None => None,
+    };
+
+    let comparison = match &options.compare {
+        Some(path) => Some(Comparison::open(path, options.context)?),
+        None => None,
+    };
+
+    if let Some(directory) = &options.frames_dir {
+        std::fs::create_dir_all(directory).map_err(|err| format!("Couldn't create {}: {}", directory, err))?;
+    }
+    let custom = match &options.palettes {
+        Some(path) => palette::parse(&std::fs::read_to_string(path).map_err(|err| format!("Couldn't read {}: {}", path, err))?)?,
+        None => Vec::new(),
+    };
+    let mut colours = palette::all(&custom)
+        .into_iter()
+        .find(|palette| palette.name.eq_ignore_ascii_case(&options.palette))
+        .ok_or(format!("No palette called {}", options.palette))?
+        .colours;
+    if palette::is_cgb_title(&rom) {
+        colours = colours.map(palette::cgb_corrected);
+    }
+    let capture = Capture {
+        scale: options.scale,
+        palette: colours,
+        gif: options.gif.as_ref().map(|_| GifEncoder::new(options.scale, colours)),
+        frames_dir: options.frames_dir.clone(),
+        frames: 0,
+    };
+
+    let mut run = Run {
+        movie: movie.as_ref(),
+        frames,
+        until_serial: options.until_serial.as_deref(),
+        trace,
+        comparison,
+        capture,
+    };
+    let outcome = emulate(&mut cpu, &mut debugger, &mut run)?;
+
+    if let (Some(path), Some(trace)) = (&options.trace, &mut run.trace) {
+        trace.flush().map_err(|err| format!("Couldn't write {}: {}", path, err))?;
+    }
+
+    std::io::stdout().write_all(&cpu.memory.serial).map_err(|err| err.to_string())?;
+    if let Some(path) = &options.png {
+        let png = image::png(&ppu::render(&cpu.memory), options.scale, run.capture.palette)?;
+        std::fs::write(path, png).map_err(|err| format!("Couldn't write {}: {}", path, err))?;
+    }
+    if let (Some(path), Some(gif)) = (&options.gif, run.capture.gif) {
+        std::fs::write(path, gif.finish()).map_err(|err| format!("Couldn't write {}: {}", path, err))?;
+    }
+    if let (Some(path), Some(profiler)) = (&options.profile, &debugger.profiler) {
+        std::fs::write(path, profiler.to_csv(&symbols)).map_err(|err| format!("Couldn't write {}: {}", path, err))?;
+    }
+    if let Some(path) = &options.registers {
+        std::fs::write(path, registers_json(&cpu)).map_err(|err| format!("Couldn't write {}: {}", path, err))?;
+    }
+
+    let waited_for_something = options.until_serial.is_some() || !options.breakpoints.is_empty();
+    Ok(match outcome {
+        Ok(Outcome::FrameLimit) if waited_for_something => {
+            eprintln!("Nothing stopped the run within {} frames", frames);
+            1
+        }
+        Ok(Outcome::FrameLimit) => 0,
+        Ok(Outcome::Serial) => 0,
+        Ok(Outcome::Breakpoint(address)) => {
+            match symbols.label(address) {
+                Some(label) => eprintln!("Stopped at breakpoint {} ({:04X})", label, address),
+                None => eprintln!("Stopped at breakpoint {:04X}", address),
+            }
+            0
+        }
+        Ok(Outcome::ReferenceEnded) => {
+            let lines = run.comparison.map_or(0, |comparison| comparison.line_number);
+            eprintln!("All {} lines of the reference matched", lines);
+            0
+        }
+        Ok(Outcome::Diverged(divergence)) => {
+            report_divergence(&divergence);
+            1
+        }
+        Err(err) => {
+            eprintln!("Emulation failed at {:04X}: {}", cpu.registry.pc, err);
+            1
+        }
+    })
+}
+
+fn emulate(cpu: &mut CPU, debugger: &mut Debugger, run: &mut Run) -> Result<Result<Outcome, EmuError>, String> {
+    for index in 0..run.frames as usize {
+        if let Some(movie) = run.movie {
+            cpu.memory.joypad = movie.inputs.get(index).copied().unwrap_or(0);
+        }
+        let frame = cpu.frame_number();
+        while cpu.frame_number() == frame {
+            let serial_length = cpu.memory.serial.len();
+            if let Some(writer) = &mut run.trace {
+                if let Err(err) = writeln!(writer, "{}", TraceLine::capture(cpu)) {
+                    eprintln!("Trace stopped: {}", err);
+                    run.trace = None;
+                }
+            }
+            // Reference logs start where the boot ROM hands over to the cartridge
+            if let Some(comparison) = run.comparison.as_mut().filter(|_| !cpu.memory.in_bootrom) {
+                if let Some(outcome) = comparison.check(cpu)? {
+                    return Ok(Ok(outcome));
+                }
+            }
+            let stopped = match debugger.step(cpu) {
+                Ok(stopped) => stopped,
+                Err(err) => return Ok(Err(err)),
+            };
+            if let Some(StopReason::Breakpoint(address)) = stopped {
+                return Ok(Ok(Outcome::Breakpoint(address)));
+            }
+            if let Some(text) = run.until_serial {
+                let found = cpu.memory.serial.len() != serial_length
+                    && cpu.memory.serial.windows(text.len()).any(|window| window == text.as_bytes());
+                if found {
+                    return Ok(Ok(Outcome::Serial));
+                }
+            }
+        }
+        run.capture.add_frame(cpu)?;
+    }
+    Ok(Ok(Outcome::FrameLimit))
+}
+
+fn report_divergence(divergence: &Divergence) {
+    eprintln!("Diverged from the reference at line {}", divergence.line_number);
+    for line in &divergence.recent {
+        eprintln!("  {}", line);
+    }
+    eprintln!("- {}", divergence.expected);
+    eprintln!("+ {}", divergence.actual);
+    let differences: Vec<&str> = divergence.expected.differences(&divergence.actual).collect();
+    eprintln!("Differs in {}", differences.join(", "));
+    if let Some((pc, instruction)) = &divergence.instruction {
+        eprintln!("after {:04X}: {}", pc, instruction);
+    }
+}
+
+fn registers_json(cpu: &CPU) -> String {
+    let registry = &cpu.registry;
+    format!(
+        "{{\n  \"a\": {},\n  \"f\": {},\n  \"b\": {},\n  \"c\": {},\n  \"d\": {},\n  \"e\": {},\n  \"h\": {},\n  \"l\": {},\n  \"sp\": {},\n  \"pc\": {},\n  \
+        \"flags\": {{ \"z\": {}, \"n\": {}, \"h\": {}, \"c\": {} }},\n  \"ime\": {},\n  \"halted\": {},\n  \"locked\": {},\n  \"cycles\": {},\n  \"frame\": {}\n}}\n",
+        registry.a, registry.f.get_flags(), registry.b, registry.c, registry.d, registry.e, registry.h, registry.l, registry.sp, registry.pc,
+        registry.f.z_zero, registry.f.n_subtraction_bcd, registry.f.h_half_carry_bcd, registry.f.c_carry,
+        registry.interrupts_enabled, registry.halted, registry.locked, cpu.cycles, cpu.frame_number(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Past the boot ROM in a ROM full of NOPs
+    fn cpu() -> CPU {
+        let mut cpu = CPU::new();
+        cpu.memory.load_rom(&[0; 0x8000]).unwrap();
+        cpu.memory.in_bootrom = false;
+        cpu.registry.pc = 0x0100;
+        cpu
+    }
+
+    fn reference(name: &str, lines: &[TraceLine]) -> String {
+        let path = std::env::temp_dir().join(format!("rutile-cli-{}-{}.log", name, std::process::id()));
+        let text: String = lines.iter().map(|line| format!("{}\n", line)).collect();
+        std::fs::write(&path, text).unwrap();
+        path.display().to_string()
+    }
+
+    // What the NOP ROM traces for its first `count` instructions
+    fn nops(count: u16) -> Vec<TraceLine> {
+        (0..count).map(|index| TraceLine { pc: 0x0100 + index, ..TraceLine::capture(&cpu()) }).collect()
+    }
+
+    #[test]
+    fn stops_at_the_first_divergence() {
+        let mut lines = nops(4);
+        lines[2].a = 0x42;
+        let path = reference("diverge", &lines);
+        let mut comparison = Comparison::open(&path, 1).unwrap();
+        let mut cpu = cpu();
+        for _ in 0..2 {
+            assert!(comparison.check(&cpu).unwrap().is_none());
+            cpu.step().unwrap();
+        }
+        let Some(Outcome::Diverged(divergence)) = comparison.check(&cpu).unwrap() else {
+            panic!("expected a divergence");
+        };
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(divergence.line_number, 3);
+        assert_eq!(divergence.recent, [lines[1].to_string()]);
+        assert_eq!(divergence.expected.differences(&divergence.actual).collect::<Vec<_>>(), ["A"]);
+        assert_eq!(divergence.instruction.map(|(pc, _)| pc), Some(0x0101));
+    }
+
+    #[test]
+    fn ends_with_the_reference() {
+        let path = reference("end", &nops(1));
+        let mut comparison = Comparison::open(&path, 5).unwrap();
+        let mut cpu = cpu();
+        assert!(comparison.check(&cpu).unwrap().is_none());
+        cpu.step().unwrap();
+        assert!(matches!(comparison.check(&cpu).unwrap(), Some(Outcome::ReferenceEnded)));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn rejects_a_reference_that_isnt_a_trace() {
+        let path = std::env::temp_dir().join(format!("rutile-cli-broken-{}.log", std::process::id()));
+        std::fs::write(&path, "Passed\n").unwrap();
+        let mut comparison = Comparison::open(&path.display().to_string(), 5).unwrap();
+        assert!(comparison.check(&cpu()).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,698 @@
+use std::cell::Cell;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, sync_channel, Receiver, Sender, SyncSender, TryRecvError};
+
+use rutile_gb_core::capture::GifEncoder;
+use rutile_gb_core::cheats::Cheats;
+use rutile_gb_core::cpu::{CPURegistry, IllegalOpcodePolicy, CPU};
+use rutile_gb_core::cpu::instructions::Instructions;
+use rutile_gb_core::debugger::{Debugger, StopReason};
+use rutile_gb_core::error::EmuError;
+use rutile_gb_core::memory::Memory;
+use rutile_gb_core::movie::{Movie, MoviePlayer, MovieRecorder};
+use rutile_gb_core::palette::Palette;
+use rutile_gb_core::pacing::FramePacer;
+use rutile_gb_core::ppu;
+use rutile_gb_core::profiler::Profiler;
+use rutile_gb_core::rewind::Rewind;
+use rutile_gb_core::trace::TraceLine;
+
+use crate::files;
+use crate::gdb::GdbStub;
+use crate::image;
+
+// Step over/out, run to cursor and a running GDB client don't wait for real time
+const GOAL_INSTRUCTIONS_PER_FRAME: u32 = 20_000;
+const REWIND_CAPACITY_BYTES: usize = 64 * 1024 * 1024;
+// Snapshots the UI hasn't picked up yet, anything past this is dropped instead of blocking
+const SNAPSHOT_BACKLOG: usize = 2;
+
+/// Changes the UI wants made, run on the emulation side between two instructions
+pub type Command = Box<dyn FnOnce(&mut Machine) + Send>;
+
+/// Things the UI has to hear about even if it skips snapshots
+pub enum Event {
+    Status(String),
+    Error(String),
+    /// A recording was stopped and wants to be saved
+    MovieRecorded(Movie),
+    /// Same for a GIF
+    GifRecorded(Vec<u8>),
+}
+
+pub enum MovieState {
+    Idle,
+    Recording(MovieRecorder),
+    Playing(MoviePlayer),
+}
+
+/// One line per executed instruction, buffered so long traces stream to disk
+pub struct Trace {
+    pub path: String,
+    writer: BufWriter<File>,
+    pub lines: u64,
+}
+
+impl Trace {
+    pub fn create(path: &str) -> Result<Trace, String> {
+        let file = File::create(path).map_err(|err| format!("Couldn't create {}: {}", path, err))?;
+        Ok(Trace { path: path.to_string(), writer: BufWriter::new(file), lines: 0 })
+    }
+
+    fn write(&mut self, cpu: &CPU) -> std::io::Result<()> {
+        self.lines += 1;
+        writeln!(self.writer, "{}", TraceLine::capture(cpu))
+    }
+}
+
+/// The LCD captured frame by frame, it has to happen here since the UI doesn't see every frame
+pub enum Recording {
+    Gif(GifEncoder),
+    /// One PNG per frame, numbered from 00000.png
+    Frames { directory: PathBuf, scale: usize, palette: Palette, written: u64 },
+}
+
+impl Recording {
+    pub fn frames(&self) -> u64 {
+        match self {
+            Recording::Gif(encoder) => encoder.frames(),
+            Recording::Frames { written, .. } => *written,
+        }
+    }
+
+    fn add_frame(&mut self, screen: &[u8]) -> Result<(), String> {
+        match self {
+            Recording::Gif(encoder) => encoder.add_frame(screen),
+            Recording::Frames { directory, scale, palette, written } => {
+                let png = image::png(screen, *scale, *palette)?;
+                files::write(&directory.join(format!("{:05}.png", written)), &png)?;
+                *written += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Everything the emulation side owns
+pub struct Machine {
+    pub cpu: CPU,
+    pub debugger: Debugger,
+    pub gdb: Option<GdbStub>,
+    pub pacer: FramePacer,
+    pub rewind: Rewind,
+    pub rewind_enabled: bool,
+    // Held down in the UI, steps back one snapshot per frame
+    pub rewinding: bool,
+    pub movie: MovieState,
+    // Buttons held in the UI right now, handed to the game on the next frame boundary
+    pub keys: u8,
+    pub trace: Option<Trace>,
+    pub recording: Option<Recording>,
+    // What the UI's open windows need on top of the screen and registers
+    pub shared: Shared,
+    cheats: Cheats,
+    input_frame: u64,
+    // The frame cheats and the recording last saw
+    frame: u64,
+    events: Sender<Event>,
+}
+
+/// What the UI draws from, the machine as it was after the last batch of frames
+pub struct Snapshot {
+    /// One shade per pixel, see `ppu::render`
+    pub screen: Vec<u8>,
+    pub registry: CPURegistry,
+    pub cycles: u64,
+    pub frame: u64,
+    pub in_bootrom: bool,
+    pub last_instruction: Instructions,
+    pub illegal_opcode_policy: IllegalOpcodePolicy,
+    pub paused: bool,
+    pub last_stop: Option<StopReason>,
+    /// Only there while a window asked for it, see `Emulator::memory`
+    pub memory: Option<Box<Memory>>,
+    /// Breakpoints, watchpoints and the call stack, see `Emulator::debugger`. The profiler
+    /// is left out, it comes separately
+    pub debugger: Option<Box<Debugger>>,
+    /// Only there while recording and a window asked for it, see `Emulator::profiler`
+    pub profiler: Option<Box<Profiler>>,
+    pub speed: f64,
+    pub gdb: Option<GdbStatus>,
+    /// Where the trace goes and how many lines it has so far
+    pub trace: Option<(String, u64)>,
+    /// Frames captured so far while recording the LCD
+    pub recording: Option<u64>,
+    pub rewind_enabled: bool,
+    pub rewind_interval: u32,
+    pub rewind_capacity_bytes: usize,
+    pub rewind_len: usize,
+    pub rewind_used_bytes: usize,
+    pub movie: MovieStatus,
+}
+
+/// What goes into snapshots besides the screen and registers, the bus and the profiler
+/// are too big to copy every frame when nothing shows them
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct Shared {
+    pub memory: bool,
+    pub debugger: bool,
+    pub profiler: bool,
+}
+
+pub struct GdbStatus {
+    pub port: u16,
+    pub connected: bool,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum MovieStatus {
+    Idle,
+    Recording { frames: usize },
+    Playing { frame: usize, frames: usize },
+}
+
+impl Machine {
+    fn new(cpu: CPU, events: Sender<Event>) -> Machine {
+        Machine {
+            cpu,
+            debugger: Debugger::new(),
+            gdb: None,
+            pacer: FramePacer::new(),
+            rewind: Rewind::new(1, REWIND_CAPACITY_BYTES),
+            rewind_enabled: true,
+            rewinding: false,
+            movie: MovieState::Idle,
+            keys: 0,
+            trace: None,
+            recording: None,
+            shared: Shared::default(),
+            cheats: Cheats::new(),
+            input_frame: 0,
+            frame: 0,
+            events,
+        }
+    }
+
+    pub fn status(&self, status: String) {
+        // The receiver only goes away when the UI shuts down
+        let _ = self.events.send(Event::Status(status));
+    }
+
+    pub fn error(&self, error: String) {
+        let _ = self.events.send(Event::Error(error));
+    }
+
+    /// Emulates `elapsed` seconds of wall-clock time, or one batch of a debugger/GDB goal
+    fn run_for(&mut self, elapsed: f64) {
+        if let Some(gdb) = &mut self.gdb {
+            if let Err(err) = gdb.poll(&mut self.cpu, &mut self.debugger) {
+                self.error(format!("GDB server stopped: {}", err));
+                self.gdb = None;
+            }
+        }
+        let gdb_running = self.gdb.as_ref().is_some_and(|gdb| gdb.is_running());
+
+        if self.rewinding && self.rewind_enabled {
+            self.rewind_frame();
+            self.pacer.reset();
+        } else if !self.debugger.paused {
+            if self.debugger.has_goal() || gdb_running {
+                self.run(u64::MAX, GOAL_INSTRUCTIONS_PER_FRAME);
+                self.pacer.reset();
+            } else {
+                let budget = self.pacer.budget(elapsed);
+                let target = self.cpu.cycles + budget;
+                if self.run(budget, u32::MAX) {
+                    self.pacer.reset();
+                } else {
+                    self.pacer.overshot(self.cpu.cycles.saturating_sub(target));
+                }
+            }
+        } else {
+            self.pacer.reset();
+        }
+    }
+
+    /// Steps until `cycles` T-cycles ran or after `max_instructions`, true if something stopped it early
+    pub fn run(&mut self, cycles: u64, max_instructions: u32) -> bool {
+        let target = self.cpu.cycles.saturating_add(cycles);
+        let mut instructions = 0;
+        while self.cpu.cycles < target && instructions < max_instructions {
+            instructions += 1;
+            match self.step() {
+                Ok(None) => (),
+                Ok(Some(_)) => return true,
+                Err(err) => {
+                    self.error(err.to_string());
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Runs one instruction through the debugger, writing it to the trace first
+    pub fn step(&mut self) -> Result<Option<StopReason>, EmuError> {
+        self.trace_instruction();
+        let stopped = self.debugger.step(&mut self.cpu);
+        self.latch_input();
+        self.frame_started();
+        stopped
+    }
+
+    /// `step` for the debugger's Step button, which steps even while paused
+    pub fn single_step(&mut self) {
+        self.trace_instruction();
+        let result = self.debugger.single_step(&mut self.cpu);
+        self.latch_input();
+        self.frame_started();
+        if let Err(err) = result {
+            self.error(err.to_string());
+        }
+    }
+
+    fn trace_instruction(&mut self) {
+        if let Some(trace) = &mut self.trace {
+            if let Err(err) = trace.write(&self.cpu) {
+                self.error(format!("Trace stopped: {}", err));
+                self.trace = None;
+            }
+        }
+    }
+
+    pub fn start_trace(&mut self, path: &str) {
+        match Trace::create(path) {
+            Ok(trace) => {
+                self.stop_trace();
+                self.status(format!("Tracing to {}", path));
+                self.trace = Some(trace);
+            }
+            Err(err) => self.error(err),
+        }
+    }
+
+    pub fn stop_trace(&mut self) {
+        if let Some(mut trace) = self.trace.take() {
+            match trace.writer.flush() {
+                Ok(()) => self.status(format!("Wrote {} lines to {}", trace.lines, trace.path)),
+                Err(err) => self.error(format!("Couldn't write {}: {}", trace.path, err)),
+            }
+        }
+    }
+
+    /// Hands the game new input when a frame started, call it after every step.
+    /// Input only changes on frame boundaries so a recording replays the same way.
+    pub fn latch_input(&mut self) {
+        match &mut self.movie {
+            MovieState::Idle => {
+                if self.input_frame != self.cpu.frame_number() {
+                    self.input_frame = self.cpu.frame_number();
+                    self.cpu.memory.joypad = self.keys;
+                }
+            }
+            MovieState::Recording(recorder) => recorder.record(&mut self.cpu, self.keys),
+            MovieState::Playing(player) => {
+                player.play(&mut self.cpu);
+                if player.is_finished() {
+                    self.movie = MovieState::Idle;
+                    self.status("Movie finished".to_string());
+                }
+            }
+        }
+    }
+
+    /// Swaps in a new list of cheats, Game Genie codes take effect right away
+    pub fn set_cheats(&mut self, cheats: Cheats) {
+        if cheats.any_enabled() {
+            self.stop_movie(true);
+        }
+        self.cpu.memory.rom_patches = cheats.rom_patches();
+        self.cheats = cheats;
+    }
+
+    // GameShark codes write their values once per frame, like the real one does in VBlank,
+    // the recording gets the frame that just finished and rewind gets a chance at a snapshot
+    fn frame_started(&mut self) {
+        if self.frame == self.cpu.frame_number() {
+            return;
+        }
+        self.frame = self.cpu.frame_number();
+        if self.rewind_enabled {
+            self.rewind.record_frame(&self.cpu);
+        }
+        self.cheats.apply_frame(&mut self.cpu.memory);
+        if let Some(recording) = &mut self.recording {
+            if let Err(err) = recording.add_frame(&ppu::render(&self.cpu.memory)) {
+                self.error(format!("Recording stopped: {}", err));
+                self.recording = None;
+            }
+        }
+    }
+
+    pub fn start_recording(&mut self, recording: Recording) {
+        self.stop_recording();
+        self.status("Recording the screen".to_string());
+        self.recording = Some(recording);
+    }
+
+    /// A GIF goes to the UI to be saved, frames are already on disk
+    pub fn stop_recording(&mut self) {
+        match self.recording.take() {
+            Some(Recording::Gif(encoder)) => {
+                let _ = self.events.send(Event::GifRecorded(encoder.finish()));
+            }
+            Some(Recording::Frames { directory, written, .. }) => {
+                self.status(format!("Wrote {} frames to {}", written, directory.display()));
+            }
+            None => (),
+        }
+    }
+
+    fn rewind_frame(&mut self) {
+        self.stop_movie(true);
+        // The shadow stack can't be rewound with the machine, it starts over from wherever this lands
+        self.debugger.call_stack.clear();
+        let stepped = self.rewind.step_back(&mut self.cpu);
+        // Landing on an earlier frame isn't a frame boundary that was run into
+        self.frame = self.cpu.frame_number();
+        match stepped {
+            Ok(true) => self.status(format!("Rewinding, {} snapshots left", self.rewind.len())),
+            Ok(false) => self.status("Reached the oldest snapshot".to_string()),
+            Err(err) => self.error(err.to_string()),
+        }
+    }
+
+    /// Ends recording or playback, a finished recording goes to the UI to be saved.
+    /// `interrupted` is for changes to the machine state that break the movie, like loading a state.
+    pub fn stop_movie(&mut self, interrupted: bool) {
+        let movie = std::mem::replace(&mut self.movie, MovieState::Idle);
+        if interrupted && !matches!(movie, MovieState::Idle) {
+            self.status("Movie stopped because the machine state was changed".to_string());
+        }
+        if let MovieState::Recording(recorder) = movie {
+            let _ = self.events.send(Event::MovieRecorded(recorder.finish()));
+        }
+    }
+
+    // Movies don't store cheats, a movie made or played with them wouldn't replay the same way
+    fn cheats_block_movies(&self) -> bool {
+        if self.cheats.any_enabled() {
+            self.error("Turn off the cheats first, movies are recorded and played without them".to_string());
+        }
+        self.cheats.any_enabled()
+    }
+
+    pub fn play_movie(&mut self, movie: Movie) {
+        if self.cheats_block_movies() {
+            return;
+        }
+        self.stop_movie(true);
+        self.debugger.call_stack.clear();
+        match MoviePlayer::start(movie, &mut self.cpu) {
+            Ok(player) => {
+                self.movie = MovieState::Playing(player);
+                self.rewind.clear();
+            }
+            Err(err) => self.error(err.to_string()),
+        }
+    }
+
+    pub fn record_movie(&mut self, from_power_on: bool) {
+        if self.cheats_block_movies() {
+            return;
+        }
+        self.stop_movie(true);
+        self.movie = if from_power_on {
+            self.rewind.clear();
+            self.debugger.call_stack.clear();
+            MovieState::Recording(MovieRecorder::power_on(&mut self.cpu))
+        } else {
+            MovieState::Recording(MovieRecorder::from_save_state(&self.cpu))
+        };
+    }
+
+    fn snapshot(&mut self) -> Box<Snapshot> {
+        let memory = self.shared.memory.then(|| {
+            let mut memory = Box::new(self.cpu.memory.clone());
+            // The UI reads through the snapshot, none of that should land in an access log
+            memory.record_accesses = false;
+            memory.take_accesses();
+            memory
+        });
+        // The profiler's counters are most of the debugger, they're only copied for the profiler window
+        let profiler = self.debugger.profiler.take();
+        let debugger = self.shared.debugger.then(|| Box::new(self.debugger.clone()));
+        self.debugger.profiler = profiler;
+        Box::new(Snapshot {
+            screen: ppu::render(&self.cpu.memory),
+            registry: self.cpu.registry.clone(),
+            cycles: self.cpu.cycles,
+            frame: self.cpu.frame_number(),
+            in_bootrom: self.cpu.memory.in_bootrom,
+            last_instruction: self.cpu.last_instruction,
+            illegal_opcode_policy: self.cpu.illegal_opcode_policy,
+            paused: self.debugger.paused,
+            last_stop: self.debugger.last_stop,
+            memory,
+            debugger,
+            profiler: self.debugger.profiler.as_ref().filter(|_| self.shared.profiler).cloned(),
+            speed: self.pacer.speed,
+            gdb: self.gdb.as_ref().map(|gdb| GdbStatus { port: gdb.port().unwrap_or(0), connected: gdb.is_connected() }),
+            trace: self.trace.as_ref().map(|trace| (trace.path.clone(), trace.lines)),
+            recording: self.recording.as_ref().map(|recording| recording.frames()),
+            rewind_enabled: self.rewind_enabled,
+            rewind_interval: self.rewind.interval,
+            rewind_capacity_bytes: self.rewind.capacity_bytes,
+            rewind_len: self.rewind.len(),
+            rewind_used_bytes: self.rewind.used_bytes(),
+            movie: match &self.movie {
+                MovieState::Idle => MovieStatus::Idle,
+                MovieState::Recording(recorder) => MovieStatus::Recording { frames: recorder.frames() },
+                MovieState::Playing(player) => {
+                    let (frame, frames) = player.progress();
+                    MovieStatus::Playing { frame, frames }
+                }
+            },
+        })
+    }
+
+    /// Runs commands, emulates `elapsed` seconds and publishes a snapshot, false once the UI is gone
+    fn pump(&mut self, commands: &Receiver<Command>, snapshots: &SyncSender<Box<Snapshot>>, elapsed: f64) -> bool {
+        loop {
+            match commands.try_recv() {
+                Ok(command) => command(self),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return false,
+            }
+        }
+        self.run_for(elapsed);
+        // A full backlog means the UI is behind, it gets the next one instead
+        let _ = snapshots.try_send(self.snapshot());
+        true
+    }
+
+    // Nothing to wait for while a goal or GDB client wants the machine to run flat out
+    fn is_busy(&self) -> bool {
+        !self.debugger.paused && (self.debugger.has_goal() || self.gdb.as_ref().is_some_and(|gdb| gdb.is_running()))
+    }
+}
+
+/// The UI's end of the emulation. On desktop the machine runs on its own thread, the web has no
+/// threads so there it runs inline whenever the UI calls `update`. Either way the UI only talks
+/// to it through commands and only sees snapshots, no audio yet since there's no APU.
+pub struct Emulator {
+    commands: Sender<Command>,
+    snapshots: Receiver<Box<Snapshot>>,
+    events: Receiver<Event>,
+    latest: Box<Snapshot>,
+    // Asked for by windows since the last update, and what the emulation side was last told
+    wanted: Cell<Shared>,
+    shared: Shared,
+    #[cfg(target_arch = "wasm32")]
+    inline: (Machine, Receiver<Command>, SyncSender<Box<Snapshot>>),
+}
+
+impl Emulator {
+    pub fn start(cpu: CPU) -> Emulator {
+        let (command_sender, command_receiver) = channel::<Command>();
+        let (snapshot_sender, snapshot_receiver) = sync_channel(SNAPSHOT_BACKLOG);
+        let (event_sender, event_receiver) = channel();
+        let mut machine = Machine::new(cpu, event_sender);
+        let latest = machine.snapshot();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        spawn(machine, command_receiver, snapshot_sender);
+
+        Emulator {
+            commands: command_sender,
+            snapshots: snapshot_receiver,
+            events: event_receiver,
+            latest,
+            wanted: Cell::new(Shared::default()),
+            shared: Shared::default(),
+            #[cfg(target_arch = "wasm32")]
+            inline: (machine, command_receiver, snapshot_sender),
+        }
+    }
+
+    /// Queues a change, it happens between two instructions on the emulation side
+    pub fn send(&self, command: impl FnOnce(&mut Machine) + Send + 'static) {
+        // Only fails if the emulation thread died, the UI shows the last snapshot then
+        let _ = self.commands.send(Box::new(command));
+    }
+
+    /// Picks up the newest snapshot, `elapsed` is only used where the machine runs inline
+    #[allow(unused_variables)]
+    pub fn update(&mut self, elapsed: f64) {
+        // Whatever no window asked for during the last frame stops being copied
+        let wanted = self.wanted.take();
+        if wanted != self.shared {
+            self.shared = wanted;
+            self.send(move |machine| machine.shared = wanted);
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            let (machine, commands, snapshots) = &mut self.inline;
+            machine.pump(commands, snapshots, elapsed);
+        }
+        while let Ok(snapshot) = self.snapshots.try_recv() {
+            self.latest = snapshot;
+        }
+    }
+
+    pub fn snapshot(&self) -> &Snapshot {
+        &self.latest
+    }
+
+    /// The bus as of the latest snapshot. Asking for it keeps it coming with every snapshot,
+    /// so it's None for the first frame a window is open
+    pub fn memory(&self) -> Option<&Memory> {
+        self.wanted.set(Shared { memory: true, ..self.wanted.get() });
+        self.latest.memory.as_deref()
+    }
+
+    /// Same as `memory` for the debugger, without its profiler
+    pub fn debugger(&self) -> Option<&Debugger> {
+        self.wanted.set(Shared { debugger: true, ..self.wanted.get() });
+        self.latest.debugger.as_deref()
+    }
+
+    /// Same as `memory` for the profiler, None while it isn't recording
+    pub fn profiler(&self) -> Option<&Profiler> {
+        self.wanted.set(Shared { profiler: true, ..self.wanted.get() });
+        self.latest.profiler.as_deref()
+    }
+
+    pub fn events(&self) -> impl Iterator<Item = Event> + '_ {
+        self.events.try_iter()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn spawn(mut machine: Machine, commands: Receiver<Command>, snapshots: SyncSender<Box<Snapshot>>) {
+    use std::time::{Duration, Instant};
+
+    use rutile_gb_core::cpu::{CLOCK_HZ, FRAME_T_CYCLES};
+
+    let frame = Duration::from_secs_f64(FRAME_T_CYCLES as f64 / CLOCK_HZ as f64);
+    std::thread::Builder::new()
+        .name("emulation".to_string())
+        .spawn(move || {
+            let mut last = Instant::now();
+            loop {
+                let now = Instant::now();
+                if !machine.pump(&commands, &snapshots, (now - last).as_secs_f64()) {
+                    return;
+                }
+                last = now;
+                if !machine.is_busy() {
+                    // Wakes up early for a command so stepping and input feel immediate
+                    match commands.recv_timeout(frame.saturating_sub(now.elapsed())) {
+                        Ok(command) => command(&mut machine),
+                        Err(std::sync::mpsc::RecvTimeoutError::Timeout) => (),
+                        Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => return,
+                    }
+                }
+            }
+        })
+        .expect("Couldn't start the emulation thread");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rutile_gb_core::cpu::FRAME_T_CYCLES;
+
+    // Past the boot ROM in a ROM full of NOPs
+    fn machine() -> Machine {
+        let mut cpu = CPU::new();
+        cpu.memory.load_rom(&[0; 0x8000]).unwrap();
+        cpu.memory.in_bootrom = false;
+        cpu.registry.pc = 0x0100;
+        let (events, _) = channel();
+        Machine::new(cpu, events)
+    }
+
+    #[test]
+    fn rewind_snapshots_every_frame_of_a_long_run() {
+        let mut machine = machine();
+        machine.rewind.interval = 1;
+        machine.run(FRAME_T_CYCLES * 10, u32::MAX);
+        assert_eq!(machine.cpu.frame_number(), 10);
+        assert_eq!(machine.rewind.len(), 10);
+    }
+
+    #[test]
+    fn rewind_steps_back_exactly_n_frames() {
+        let mut machine = machine();
+        machine.rewind.interval = 1;
+        machine.run(FRAME_T_CYCLES * 10, u32::MAX);
+        for _ in 0..3 {
+            machine.rewind_frame();
+        }
+        assert_eq!(machine.cpu.frame_number(), 7);
+    }
+
+    #[test]
+    fn snapshots_leave_out_what_nothing_asked_for() {
+        let mut machine = machine();
+        machine.debugger.profiler = Some(Box::new(Profiler::new()));
+        let snapshot = machine.snapshot();
+        assert!(snapshot.memory.is_none() && snapshot.debugger.is_none() && snapshot.profiler.is_none());
+        assert_eq!(snapshot.registry.pc, 0x0100);
+
+        machine.shared = Shared { memory: true, ..Shared::default() };
+        let snapshot = machine.snapshot();
+        assert!(snapshot.memory.is_some() && snapshot.debugger.is_none());
+
+        // The debugger comes without the profiler, that's only there when asked for
+        machine.shared = Shared { debugger: true, ..Shared::default() };
+        let snapshot = machine.snapshot();
+        assert!(snapshot.debugger.is_some_and(|debugger| debugger.profiler.is_none()) && snapshot.profiler.is_none());
+        assert!(machine.debugger.profiler.is_some());
+        machine.shared = Shared { profiler: true, ..Shared::default() };
+        assert!(machine.snapshot().profiler.is_some());
+    }
+
+    #[test]
+    fn movies_wait_until_cheats_are_off() {
+        let mut machine = machine();
+        let mut cheats = Cheats::new();
+        cheats.add("01FF34C1", "").unwrap();
+        machine.set_cheats(cheats.clone());
+        machine.record_movie(false);
+        assert!(matches!(machine.movie, MovieState::Idle));
+
+        cheats.cheats[0].enabled = false;
+        machine.set_cheats(cheats.clone());
+        machine.record_movie(false);
+        assert!(matches!(machine.movie, MovieState::Recording(_)));
+
+        // Turning one on mid-recording ends the movie
+        cheats.cheats[0].enabled = true;
+        machine.set_cheats(cheats);
+        assert!(matches!(machine.movie, MovieState::Idle));
+    }
+}
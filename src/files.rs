@@ -0,0 +1,68 @@
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+// Desktop builds use native dialogs and the file system, the web build the browser's
+// file input, downloads and localStorage. Both offer pick/save_as/read/write.
+#[cfg(not(target_arch = "wasm32"))]
+mod native;
+#[cfg(not(target_arch = "wasm32"))]
+use native as platform;
+#[cfg(target_arch = "wasm32")]
+mod web;
+#[cfg(target_arch = "wasm32")]
+use web as platform;
+
+pub use platform::{read, save_as, write};
+
+/// What a picked file is going to be used for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Purpose {
+    Rom,
+    Movie,
+    Report,
+    Screenshot,
+    Gif,
+    Palettes,
+}
+
+impl Purpose {
+    fn filter(self) -> Option<(&'static str, &'static [&'static str])> {
+        match self {
+            Purpose::Rom => None,
+            Purpose::Movie => Some(("Movie", &["gbm"])),
+            Purpose::Report => Some(("CSV", &["csv"])),
+            Purpose::Screenshot => Some(("PNG", &["png"])),
+            Purpose::Gif => Some(("GIF", &["gif"])),
+            Purpose::Palettes => Some(("Palettes", &["txt"])),
+        }
+    }
+}
+
+pub struct PickedFile {
+    pub purpose: Purpose,
+    // Only the file name on the web
+    pub path: PathBuf,
+    pub data: Vec<u8>,
+}
+
+/// Picking a file is async on the web, so the result always comes back through `poll`
+pub struct FilePicker {
+    sender: Sender<Result<PickedFile, String>>,
+    receiver: Receiver<Result<PickedFile, String>>,
+}
+
+impl FilePicker {
+    pub fn new() -> FilePicker {
+        let (sender, receiver) = channel();
+        FilePicker { sender, receiver }
+    }
+
+    pub fn pick(&self, purpose: Purpose) {
+        platform::pick(purpose, self.sender.clone());
+    }
+
+    /// A file that was picked since the last call, if any
+    pub fn poll(&self) -> Option<Result<PickedFile, String>> {
+        self.receiver.try_recv().ok()
+    }
+}
@@ -0,0 +1,41 @@
+use std::path::Path;
+use std::sync::mpsc::Sender;
+
+use super::{PickedFile, Purpose};
+
+pub fn pick(purpose: Purpose, sender: Sender<Result<PickedFile, String>>) {
+    let mut dialog = rfd::FileDialog::new();
+    if let Some((name, extensions)) = purpose.filter() {
+        dialog = dialog.add_filter(name, extensions);
+    }
+    let Some(path) = dialog.pick_file() else {
+        return;
+    };
+    let picked = match std::fs::read(&path) {
+        Ok(data) => Ok(PickedFile { purpose, path, data }),
+        Err(err) => Err(format!("Couldn't read {}: {}", path.display(), err)),
+    };
+    // The receiver lives as long as the app
+    let _ = sender.send(picked);
+}
+
+/// Asks where to save `data`, returns where it went or None if the dialog was cancelled
+pub fn save_as(purpose: Purpose, file_name: &str, data: &[u8]) -> Result<Option<String>, String> {
+    let mut dialog = rfd::FileDialog::new().set_file_name(file_name);
+    if let Some((name, extensions)) = purpose.filter() {
+        dialog = dialog.add_filter(name, extensions);
+    }
+    let Some(path) = dialog.save_file() else {
+        return Ok(None);
+    };
+    write(&path, data)?;
+    Ok(Some(path.display().to_string()))
+}
+
+pub fn read(path: &Path) -> Result<Vec<u8>, String> {
+    std::fs::read(path).map_err(|err| format!("Couldn't read {}: {}", path.display(), err))
+}
+
+pub fn write(path: &Path, data: &[u8]) -> Result<(), String> {
+    std::fs::write(path, data).map_err(|err| format!("Couldn't write {}: {}", path.display(), err))
+}
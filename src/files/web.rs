@@ -0,0 +1,86 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Blob, HtmlAnchorElement, Storage, Url};
+
+use super::{PickedFile, Purpose};
+
+pub fn pick(purpose: Purpose, sender: Sender<Result<PickedFile, String>>) {
+    let mut dialog = rfd::AsyncFileDialog::new();
+    if let Some((name, extensions)) = purpose.filter() {
+        dialog = dialog.add_filter(name, extensions);
+    }
+    wasm_bindgen_futures::spawn_local(async move {
+        if let Some(handle) = dialog.pick_file().await {
+            let data = handle.read().await;
+            let _ = sender.send(Ok(PickedFile { purpose, path: PathBuf::from(handle.file_name()), data }));
+        }
+    });
+}
+
+/// Browsers don't hand out save dialogs, so the file is offered as a download
+pub fn save_as(_purpose: Purpose, file_name: &str, data: &[u8]) -> Result<Option<String>, String> {
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(data));
+    let blob = Blob::new_with_u8_array_sequence(&parts).map_err(js_error)?;
+    let url = Url::create_object_url_with_blob(&blob).map_err(js_error)?;
+    let document = window()?.document().ok_or("No document")?;
+    let link: HtmlAnchorElement = document.create_element("a").map_err(js_error)?.dyn_into().map_err(|_| "Not a link")?;
+    link.set_href(&url);
+    link.set_download(file_name);
+    link.click();
+    Url::revoke_object_url(&url).map_err(js_error)?;
+    Ok(Some(file_name.to_string()))
+}
+
+// Save states live in localStorage. It only holds strings, so the data is deflated (save states are
+// mostly zeros) and base64 encoded, the path is only used as the key.
+pub fn read(path: &Path) -> Result<Vec<u8>, String> {
+    let key = path.to_string_lossy();
+    let encoded = storage()?.get_item(&key).map_err(js_error)?.ok_or(format!("Nothing saved as {}", key))?;
+    let binary = window()?.atob(&encoded).map_err(js_error)?;
+    let compressed: Vec<u8> = binary.chars().map(|c| c as u8).collect();
+    miniz_oxide::inflate::decompress_to_vec(&compressed).map_err(|err| format!("{} is damaged: {:?}", key, err))
+}
+
+pub fn write(path: &Path, data: &[u8]) -> Result<(), String> {
+    let key = path.to_string_lossy();
+    // btoa wants every byte as one character
+    let binary: String = miniz_oxide::deflate::compress_to_vec(data, 6).into_iter().map(char::from).collect();
+    let encoded = window()?.btoa(&binary).map_err(js_error)?;
+    storage()?.set_item(&key, &encoded).map_err(|err| format!("Couldn't store {}: {}", key, js_error(err)))
+}
+
+fn window() -> Result<web_sys::Window, String> {
+    web_sys::window().ok_or_else(|| "No window".to_string())
+}
+
+fn storage() -> Result<Storage, String> {
+    window()?.local_storage().map_err(js_error)?.ok_or_else(|| "localStorage isn't available".to_string())
+}
+
+fn js_error(err: JsValue) -> String {
+    err.as_string().unwrap_or_else(|| format!("{:?}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use rutile_gb_core::cpu::CPU;
+    use wasm_bindgen_test::{wasm_bindgen_test, wasm_bindgen_test_configure};
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn save_state_survives_local_storage() {
+        let mut cpu = CPU::new();
+        cpu.memory.load_rom(&[0; 0x8000]).unwrap();
+        cpu.memory.memory[0xC000] = 0x42;
+        let state = cpu.save_state();
+
+        super::write(Path::new("test.gb.ss1"), &state).unwrap();
+        assert_eq!(super::read(Path::new("test.gb.ss1")).unwrap(), state);
+        assert!(super::read(Path::new("missing.gb.ss1")).is_err());
+    }
+}
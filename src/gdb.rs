@@ -0,0 +1,537 @@
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+use rutile_gb_core::cpu::CPU;
+use rutile_gb_core::debugger::{Debugger, StopReason, WatchKind};
+
+// GDB has no SM83 target, the Z80 one is the closest thing clients know about.
+// Only the registers the SM83 really has are described, all of them 16 bit and little endian
+const TARGET_XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+  <architecture>z80</architecture>
+  <feature name="org.gnu.gdb.z80.cpu">
+    <reg name="af" bitsize="16" type="int"/>
+    <reg name="bc" bitsize="16" type="int"/>
+    <reg name="de" bitsize="16" type="int"/>
+    <reg name="hl" bitsize="16" type="data_ptr"/>
+    <reg name="sp" bitsize="16" type="data_ptr"/>
+    <reg name="pc" bitsize="16" type="code_ptr"/>
+  </feature>
+</target>
+"#;
+const REGISTER_COUNT: usize = 6;
+
+/// GDB remote serial protocol server, polled between frames on the emulation side so nothing blocks
+pub struct GdbStub {
+    listener: TcpListener,
+    client: Option<TcpStream>,
+    buffer: Vec<u8>,
+    // The client sent continue and waits for a stop reply
+    running: bool,
+}
+
+impl GdbStub {
+    pub fn listen(port: u16) -> io::Result<GdbStub> {
+        let listener = TcpListener::bind(("127.0.0.1", port))?;
+        listener.set_nonblocking(true)?;
+
+        Ok(GdbStub {
+            listener,
+            client: None,
+            buffer: Vec::new(),
+            running: false,
+        })
+    }
+
+    pub fn port(&self) -> Option<u16> {
+        self.listener.local_addr().ok().map(|address| address.port())
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.client.is_some()
+    }
+
+    /// True while the client let the emulator run and waits for it to stop
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    pub fn poll(&mut self, cpu: &mut CPU, debugger: &mut Debugger) -> io::Result<()> {
+        if self.client.is_none() {
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    stream.set_nodelay(true)?;
+                    // GDB expects the target to be stopped when it attaches
+                    debugger.pause();
+                    self.client = Some(stream);
+                    self.buffer.clear();
+                    self.running = false;
+                }
+                Err(err) if err.kind() == ErrorKind::WouldBlock => return Ok(()),
+                Err(err) => return Err(err),
+            }
+        }
+
+        if !self.receive()? {
+            self.disconnect(debugger);
+            return Ok(());
+        }
+
+        while let Some(packet) = self.next_packet()? {
+            match packet {
+                Packet::Interrupt => {
+                    debugger.pause();
+                    if self.running {
+                        self.running = false;
+                        self.send("S02")?;
+                    }
+                }
+                Packet::Command(command) => {
+                    if let Some(reply) = self.handle(&command, cpu, debugger) {
+                        self.send(&reply)?;
+                    }
+                    if self.client.is_none() {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+
+        if self.running && debugger.paused {
+            self.running = false;
+            let reply = stop_reply(debugger.last_stop);
+            self.send(&reply)?;
+        }
+        Ok(())
+    }
+
+    // Reads whatever is waiting on the socket, false once the client is gone
+    fn receive(&mut self) -> io::Result<bool> {
+        let Some(client) = &mut self.client else { return Ok(false) };
+        client.set_nonblocking(true)?;
+
+        let mut chunk = [0; 4096];
+        let result = loop {
+            match client.read(&mut chunk) {
+                Ok(0) => break Ok(false),
+                Ok(read) => self.buffer.extend_from_slice(&chunk[..read]),
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break Ok(true),
+                Err(err) if err.kind() == ErrorKind::ConnectionReset => break Ok(false),
+                Err(err) => break Err(err),
+            }
+        };
+
+        client.set_nonblocking(false)?;
+        result
+    }
+
+    fn next_packet(&mut self) -> io::Result<Option<Packet>> {
+        loop {
+            match self.buffer.first() {
+                None => return Ok(None),
+                // Acks, we don't resend anything so they don't matter
+                Some(b'+') | Some(b'-') => {
+                    self.buffer.remove(0);
+                }
+                Some(0x03) => {
+                    self.buffer.remove(0);
+                    return Ok(Some(Packet::Interrupt));
+                }
+                Some(b'$') => break,
+                Some(_) => {
+                    self.buffer.remove(0);
+                }
+            }
+        }
+
+        // $<data>#<two hex digits checksum>
+        let Some(end) = self.buffer.iter().position(|&byte| byte == b'#') else { return Ok(None) };
+        if self.buffer.len() < end + 3 {
+            return Ok(None);
+        }
+
+        let data: Vec<u8> = self.buffer[1..end].to_vec();
+        let checksum = std::str::from_utf8(&self.buffer[end + 1..end + 3]).ok().and_then(|text| u8::from_str_radix(text, 16).ok());
+        self.buffer.drain(..end + 3);
+
+        if checksum != Some(checksum_of(&data)) {
+            self.write_raw(b"-")?;
+            return self.next_packet();
+        }
+        self.write_raw(b"+")?;
+        Ok(Some(Packet::Command(String::from_utf8_lossy(&data).into_owned())))
+    }
+
+    fn send(&mut self, data: &str) -> io::Result<()> {
+        let packet = format!("${}#{:02x}", data, checksum_of(data.as_bytes()));
+        self.write_raw(packet.as_bytes())
+    }
+
+    fn write_raw(&mut self, data: &[u8]) -> io::Result<()> {
+        match &mut self.client {
+            Some(client) => client.write_all(data),
+            None => Ok(()),
+        }
+    }
+
+    fn disconnect(&mut self, debugger: &mut Debugger) {
+        self.client = None;
+        self.running = false;
+        self.buffer.clear();
+        debugger.resume();
+    }
+
+    // Returns the reply, None when the reply has to wait until the emulator stops
+    fn handle(&mut self, command: &str, cpu: &mut CPU, debugger: &mut Debugger) -> Option<String> {
+        let reply = match command.as_bytes().first().copied().unwrap_or(b' ') {
+            b'?' => stop_reply(debugger.last_stop),
+            b'g' => (0..REGISTER_COUNT).map(|index| encode_u16(read_register(cpu, index))).collect(),
+            b'G' => {
+                let values = decode_hex(&command[1..]);
+                for (index, value) in values.chunks(2).take(REGISTER_COUNT).enumerate() {
+                    if value.len() == 2 {
+                        write_register(cpu, index, u16::from_le_bytes([value[0], value[1]]));
+                    }
+                }
+                "OK".to_string()
+            }
+            b'p' => match usize::from_str_radix(&command[1..], 16) {
+                Ok(index) if index < REGISTER_COUNT => encode_u16(read_register(cpu, index)),
+                _ => "E01".to_string(),
+            },
+            b'P' => {
+                let parsed = command[1..].split_once('=').and_then(|(index, value)| {
+                    let value = decode_hex(value);
+                    Some((usize::from_str_radix(index, 16).ok()?, u16::from_le_bytes([*value.first()?, *value.get(1)?])))
+                });
+                match parsed {
+                    Some((index, value)) if index < REGISTER_COUNT => {
+                        write_register(cpu, index, value);
+                        "OK".to_string()
+                    }
+                    _ => "E01".to_string(),
+                }
+            }
+            b'm' => match parse_address_length(&command[1..]) {
+                Some((address, length)) => (0..length)
+                    .map(|offset| format!("{:02x}", cpu.memory.peek(address.wrapping_add(offset))))
+                    .collect(),
+                None => "E01".to_string(),
+            },
+            b'M' => {
+                let parsed = command[1..].split_once(':').and_then(|(range, data)| Some((parse_address_length(range)?, decode_hex(data))));
+                match parsed {
+                    Some(((address, _), data)) => {
+                        for (offset, byte) in data.iter().enumerate() {
+                            cpu.memory.write_byte(address.wrapping_add(offset as u16), *byte);
+                        }
+                        "OK".to_string()
+                    }
+                    None => "E01".to_string(),
+                }
+            }
+            b'Z' | b'z' => self.breakpoint(command, debugger),
+            b'c' => return self.continue_execution(debugger),
+            b's' => return Some(self.step(cpu, debugger)),
+            b'v' if command == "vCont?" => "vCont;c;C;s;S".to_string(),
+            b'v' if command.starts_with("vCont;c") || command.starts_with("vCont;C") => return self.continue_execution(debugger),
+            b'v' if command.starts_with("vCont;s") || command.starts_with("vCont;S") => return Some(self.step(cpu, debugger)),
+            b'q' => query(command),
+            b'H' => "OK".to_string(),
+            b'D' => {
+                self.send("OK").ok();
+                self.disconnect(debugger);
+                return None;
+            }
+            b'k' => {
+                self.disconnect(debugger);
+                return None;
+            }
+            _ => String::new(),
+        };
+        Some(reply)
+    }
+
+    fn continue_execution(&mut self, debugger: &mut Debugger) -> Option<String> {
+        debugger.resume();
+        self.running = true;
+        None
+    }
+
+    fn step(&mut self, cpu: &mut CPU, debugger: &mut Debugger) -> String {
+        match debugger.single_step(cpu) {
+            Ok(()) => "S05".to_string(),
+            // SIGILL, the core couldn't execute the instruction
+            Err(_) => "S04".to_string(),
+        }
+    }
+
+    // Z<type>,<address>,<kind>, type 0/1 are breakpoints, 2/3/4 write/read/access watchpoints
+    fn breakpoint(&mut self, command: &str, debugger: &mut Debugger) -> String {
+        let insert = command.starts_with('Z');
+        let mut fields = command[1..].split(',');
+        let kind = fields.next().unwrap_or("");
+        let address = fields.next().and_then(|address| u16::from_str_radix(address, 16).ok());
+        let length = fields.next().and_then(|length| u16::from_str_radix(length, 16).ok()).unwrap_or(1).max(1);
+        let Some(address) = address else { return "E01".to_string() };
+        let end = address.wrapping_add(length - 1);
+
+        let watch_kind = match kind {
+            "0" | "1" => None,
+            "2" => Some(WatchKind::Write),
+            "3" => Some(WatchKind::Read),
+            "4" => Some(WatchKind::ReadWrite),
+            _ => return String::new(),
+        };
+
+        match (watch_kind, insert) {
+            (None, true) => {
+                if !debugger.has_breakpoint(address) {
+                    debugger.toggle_breakpoint(address);
+                }
+            }
+            (None, false) => debugger.breakpoints.retain(|breakpoint| breakpoint.address != address),
+            (Some(kind), true) => debugger.add_watchpoint(address, end, kind),
+            (Some(kind), false) => debugger.watchpoints.retain(|watchpoint| {
+                !(watchpoint.start == address && watchpoint.end == end && watchpoint.kind == kind)
+            }),
+        }
+        "OK".to_string()
+    }
+}
+
+enum Packet {
+    Interrupt,
+    Command(String),
+}
+
+fn query(command: &str) -> String {
+    if command.starts_with("qSupported") {
+        "PacketSize=4000;qXfer:features:read+;swbreak+;hwbreak+".to_string()
+    } else if let Some(range) = command.strip_prefix("qXfer:features:read:target.xml:") {
+        match range.split_once(',') {
+            Some((offset, length)) => {
+                let offset = usize::from_str_radix(offset, 16).unwrap_or(0).min(TARGET_XML.len());
+                let length = usize::from_str_radix(length, 16).unwrap_or(0);
+                let end = (offset + length).min(TARGET_XML.len());
+                let prefix = if end == TARGET_XML.len() { "l" } else { "m" };
+                format!("{}{}", prefix, &TARGET_XML[offset..end])
+            }
+            None => "E01".to_string(),
+        }
+    } else if command == "qAttached" {
+        "1".to_string()
+    } else if command == "qC" {
+        "QC1".to_string()
+    } else if command == "qfThreadInfo" {
+        "m1".to_string()
+    } else if command == "qsThreadInfo" {
+        "l".to_string()
+    } else {
+        String::new()
+    }
+}
+
+fn stop_reply(reason: Option<StopReason>) -> String {
+    match reason {
+        Some(StopReason::Watchpoint { address, write: true }) => format!("T05watch:{:04x};", address),
+        Some(StopReason::Watchpoint { address, write: false }) => format!("T05rwatch:{:04x};", address),
+        Some(StopReason::Breakpoint(_)) => "T05swbreak:;".to_string(),
+        Some(StopReason::IllegalOpcode(_)) => "S04".to_string(),
+        _ => "S05".to_string(),
+    }
+}
+
+fn read_register(cpu: &mut CPU, index: usize) -> u16 {
+    match index {
+        0 => cpu.registry.get_af(),
+        1 => cpu.registry.get_bc(),
+        2 => cpu.registry.get_de(),
+        3 => cpu.registry.get_hl(),
+        4 => cpu.registry.sp,
+        _ => cpu.registry.pc,
+    }
+}
+
+fn write_register(cpu: &mut CPU, index: usize, value: u16) {
+    match index {
+        0 => cpu.registry.set_af(value),
+        1 => cpu.registry.set_bc(value),
+        2 => cpu.registry.set_de(value),
+        3 => cpu.registry.set_hl(value),
+        4 => cpu.registry.sp = value,
+        _ => cpu.registry.pc = value,
+    }
+}
+
+fn checksum_of(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte))
+}
+
+fn encode_u16(value: u16) -> String {
+    let [low, high] = value.to_le_bytes();
+    format!("{:02x}{:02x}", low, high)
+}
+
+fn decode_hex(text: &str) -> Vec<u8> {
+    text.as_bytes()
+        .chunks_exact(2)
+        .filter_map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
+
+fn parse_address_length(text: &str) -> Option<(u16, u16)> {
+    let (address, length) = text.split_once(',')?;
+    Some((u16::from_str_radix(address, 16).ok()?, u16::from_str_radix(length, 16).ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread::sleep;
+    use std::time::Duration;
+
+    use super::*;
+
+    // A stub with a client connected over loopback, both ends on this thread
+    struct Session {
+        stub: GdbStub,
+        client: TcpStream,
+        cpu: CPU,
+        debugger: Debugger,
+    }
+
+    impl Session {
+        fn new() -> Session {
+            let stub = GdbStub::listen(0).unwrap();
+            let client = TcpStream::connect(("127.0.0.1", stub.port().unwrap())).unwrap();
+            client.set_nonblocking(true).unwrap();
+            let mut cpu = CPU::new();
+            cpu.memory.load_rom(&[0; 0x8000]).unwrap();
+            cpu.memory.in_bootrom = false;
+            let mut session = Session { stub, client, cpu, debugger: Debugger::new() };
+            session.poll_until(|session| session.stub.is_connected());
+            session
+        }
+
+        fn poll_until(&mut self, mut done: impl FnMut(&mut Session) -> bool) {
+            for _ in 0..1000 {
+                self.stub.poll(&mut self.cpu, &mut self.debugger).unwrap();
+                if done(self) {
+                    return;
+                }
+                sleep(Duration::from_millis(1));
+            }
+            panic!("the stub never got there");
+        }
+
+        // Polls the stub until what came back satisfies `complete`
+        fn receive(&mut self, complete: impl Fn(&[u8]) -> bool) -> String {
+            let mut received = Vec::new();
+            self.poll_until(|session| {
+                let mut chunk = [0; 4096];
+                match session.client.read(&mut chunk) {
+                    Ok(read) => received.extend_from_slice(&chunk[..read]),
+                    Err(err) if err.kind() == ErrorKind::WouldBlock => (),
+                    Err(err) => panic!("{}", err),
+                }
+                complete(&received)
+            });
+            String::from_utf8(received).unwrap()
+        }
+
+        fn send_raw(&mut self, data: &[u8]) {
+            self.client.write_all(data).unwrap();
+        }
+
+        // Sends a packet and returns the data of the reply after checking its ack and checksum
+        fn command(&mut self, data: &str) -> String {
+            self.send_raw(format!("${}#{:02x}", data, checksum_of(data.as_bytes())).as_bytes());
+            let reply = self.receive(|received| received.len() >= 5 && received[received.len() - 3] == b'#');
+            let packet = reply.strip_prefix("+$").unwrap();
+            let (data, checksum) = packet.split_at(packet.len() - 3);
+            assert_eq!(checksum, format!("#{:02x}", checksum_of(data.as_bytes())));
+            data.to_string()
+        }
+    }
+
+    #[test]
+    fn naks_bad_checksums_and_skips_stray_bytes() {
+        let mut session = Session::new();
+        assert!(session.debugger.paused);
+        session.send_raw(b"$?#00");
+        assert_eq!(session.receive(|received| !received.is_empty()), "-");
+        // Acks from the client and noise before a packet are skipped
+        session.send_raw(b"+x");
+        assert_eq!(session.command("?"), "S05");
+    }
+
+    #[test]
+    fn reads_and_writes_registers() {
+        let mut session = Session::new();
+        // AF, BC, DE, HL, SP and PC, each little endian
+        assert_eq!(session.command("Gf012341278563412cdab0150"), "OK");
+        assert_eq!(session.cpu.registry.get_af(), 0x12F0);
+        assert_eq!(session.cpu.registry.get_hl(), 0x1234);
+        assert_eq!(session.cpu.registry.pc, 0x5001);
+        assert_eq!(session.command("g"), "f012341278563412cdab0150");
+        assert_eq!(session.command("p4"), "cdab");
+
+        assert_eq!(session.command("P4=feff"), "OK");
+        assert_eq!(session.cpu.registry.sp, 0xFFFE);
+        assert_eq!(session.command("p6"), "E01");
+        assert_eq!(session.command("P6=0000"), "E01");
+    }
+
+    #[test]
+    fn reads_and_writes_memory_without_logging_accesses() {
+        let mut session = Session::new();
+        assert_eq!(session.command("Mc000,3:0a0b0c"), "OK");
+        assert_eq!(session.cpu.memory.memory[0xC000..0xC003], [0x0A, 0x0B, 0x0C]);
+
+        session.cpu.memory.record_accesses = true;
+        assert_eq!(session.command("mc000,4"), "0a0b0c00");
+        assert!(session.cpu.memory.take_accesses().is_empty());
+        assert_eq!(session.command("mc000"), "E01");
+    }
+
+    #[test]
+    fn sets_and_clears_breakpoints_and_watchpoints() {
+        let mut session = Session::new();
+        assert_eq!(session.command("Z0,150,1"), "OK");
+        assert!(session.debugger.has_breakpoint(0x0150));
+        assert_eq!(session.command("z0,150,1"), "OK");
+        assert!(!session.debugger.has_breakpoint(0x0150));
+
+        assert_eq!(session.command("Z2,c000,2"), "OK");
+        let watchpoint = &session.debugger.watchpoints[0];
+        assert_eq!((watchpoint.start, watchpoint.end, watchpoint.kind), (0xC000, 0xC001, WatchKind::Write));
+        assert_eq!(session.command("z2,c000,2"), "OK");
+        assert!(session.debugger.watchpoints.is_empty());
+
+        // Unsupported kinds get the empty reply
+        assert_eq!(session.command("Z9,c000,1"), "");
+    }
+
+    #[test]
+    fn continue_replies_once_interrupted() {
+        let mut session = Session::new();
+        session.send_raw(b"$c#63");
+        assert_eq!(session.receive(|received| !received.is_empty()), "+");
+        assert!(session.stub.is_running() && !session.debugger.paused);
+
+        session.send_raw(&[0x03]);
+        assert_eq!(session.receive(|received| received.ends_with(b"#b5")), "$S02#b5");
+        assert!(!session.stub.is_running() && session.debugger.paused);
+    }
+
+    #[test]
+    fn serves_the_target_description_in_pieces() {
+        let mut session = Session::new();
+        assert!(session.command("qSupported:swbreak+").contains("qXfer:features:read+"));
+        let first = session.command("qXfer:features:read:target.xml:0,10");
+        assert_eq!(first, format!("m{}", &TARGET_XML[..0x10]));
+        let last = session.command(&format!("qXfer:features:read:target.xml:10,{:x}", TARGET_XML.len()));
+        assert_eq!(last, format!("l{}", &TARGET_XML[0x10..]));
+    }
+}
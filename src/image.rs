@@ -0,0 +1,17 @@
+// Shared with rutile-cli through #[path], so this goes through the core crate by name
+use rutile_gb_core::capture::scale_screen;
+use rutile_gb_core::palette::Palette;
+use rutile_gb_core::ppu;
+
+/// A frame from `ppu::render` as an RGB PNG, `scale` times the native 160x144
+pub fn png(screen: &[u8], scale: usize, palette: Palette) -> Result<Vec<u8>, String> {
+    let mut data = Vec::new();
+    let mut encoder = png::Encoder::new(&mut data, (ppu::SCREEN_WIDTH * scale) as u32, (ppu::SCREEN_HEIGHT * scale) as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let pixels: Vec<u8> = scale_screen(screen, scale).iter().flat_map(|shade| palette[*shade as usize]).collect();
+    let mut writer = encoder.write_header().map_err(|err| err.to_string())?;
+    writer.write_image_data(&pixels).map_err(|err| err.to_string())?;
+    writer.finish().map_err(|err| err.to_string())?;
+    Ok(data)
+}
@@ -1,20 +1,20 @@
-#![feature(exclusive_range_pattern)]
+use rutile_gb_core::cpu::CPU;
 
-use cpu::CPU;
-
-mod cpu;
-mod memory;
+mod emulator;
+mod files;
+mod gdb;
+mod image;
 mod ui;
 
-use eframe::egui;
 use ui::MyApp;
 
+#[cfg(not(target_arch = "wasm32"))]
 fn main() {
     let cpu: CPU = CPU::new();
     let ui = MyApp::init(cpu);
 
     let options = eframe::NativeOptions {
-        initial_window_size: Some(egui::vec2(800.0, 1250.0)),
+        initial_window_size: Some(eframe::egui::vec2(800.0, 1250.0)),
         ..Default::default()
     };
     eframe::run_native(
@@ -23,3 +23,16 @@ fn main() {
         Box::new(|_cc| Box::new(ui)),
     ).unwrap();
 }
+
+// Built with trunk, see index.html
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    let runner = eframe::WebRunner::new();
+    wasm_bindgen_futures::spawn_local(async move {
+        runner.start(
+            "rutile_canvas",
+            eframe::WebOptions::default(),
+            Box::new(|_cc| Box::new(MyApp::init(CPU::new()))),
+        ).await.expect("Couldn't start eframe");
+    });
+}
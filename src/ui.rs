@@ -1,36 +1,219 @@
-use eframe::{egui::{self, RichText, Widget}, epaint::Color32};
+use std::path::{Path, PathBuf};
 
-use crate::cpu::CPU;
-use crate::cpu::instructions::Instructions;
+use eframe::{egui::{self, RichText}, epaint::Color32};
+
+use rutile_gb_core::cheats::Cheats;
+use rutile_gb_core::cpu::{CPU, IllegalOpcodePolicy};
+use rutile_gb_core::cpu::disassembler;
+use rutile_gb_core::cpu::instructions::Instructions;
+use rutile_gb_core::debugger::parse_number;
+use rutile_gb_core::movie::Movie;
+use rutile_gb_core::ppu;
+use rutile_gb_core::symbols::Symbols;
+
+use crate::emulator::{Emulator, Event};
+use crate::files::{self, FilePicker, PickedFile, Purpose};
+
+mod capture;
+mod cheats;
+mod debugger;
+mod io;
+mod memory;
+mod movie;
+mod pacing;
+mod palette;
+mod profiler;
+mod rewind;
+mod savestates;
+mod search;
+mod vram;
 
 pub struct MyApp {
-    speed: u64,
-    cpu: CPU,
-    halt: bool,
-    single_step: bool,
+    emulator: Emulator,
+    speed: f64,
+    fast_forward_speed: f64,
+    last_time: Option<f64>,
+    debugger_inputs: debugger::DebuggerInputs,
+    cheat_inputs: cheats::CheatInputs,
+    memory_viewer: memory::MemoryViewer,
+    io_viewer: io::IoViewer,
+    profiler_view: profiler::ProfilerView,
+    search_view: search::RamSearchView,
+    vram_viewer: vram::VramViewer,
+    files: FilePicker,
     img: egui::ColorImage,
     picked_path: String,
+    rom_path: Option<PathBuf>,
+    symbols: Symbols,
+    cheats: Cheats,
+    save_slot: u8,
+    // Screenshots and recordings are this many times 160x144
+    capture_scale: usize,
+    palettes: palette::PaletteChoice,
+    // The ROM header asks for a CGB, its colours go through the CGB's LCD curve
+    cgb_title: bool,
+    // What the emulation side was last told, so only changes are sent
+    keys: u8,
+    rewinding: bool,
+    status: Option<String>,
+    last_error: Option<String>,
 }
 
 impl MyApp {
     pub fn init(cpu: CPU) -> Self {
         Self {
-            speed: 0,
-            cpu: cpu,
-            halt: false,
-            img: egui::ColorImage::new([160, 144], Color32::WHITE),
-            single_step: false,
+            emulator: Emulator::start(cpu),
+            speed: 1.0,
+            fast_forward_speed: 4.0,
+            last_time: None,
+            debugger_inputs: debugger::DebuggerInputs::new(),
+            cheat_inputs: cheats::CheatInputs::new(),
+            memory_viewer: memory::MemoryViewer::new(),
+            io_viewer: io::IoViewer::new(),
+            profiler_view: profiler::ProfilerView::new(),
+            search_view: search::RamSearchView::new(),
+            vram_viewer: vram::VramViewer::new(),
+            files: FilePicker::new(),
+            img: egui::ColorImage::new([ppu::SCREEN_WIDTH, ppu::SCREEN_HEIGHT], Color32::WHITE),
             picked_path: "No Game Selected".to_string(),
+            rom_path: None,
+            symbols: Symbols::new(),
+            cheats: Cheats::new(),
+            save_slot: 1,
+            capture_scale: 1,
+            palettes: palette::PaletteChoice::new(),
+            cgb_title: false,
+            keys: 0,
+            rewinding: false,
+            status: None,
+            last_error: None,
+        }
+    }
+
+    fn open_picked(&mut self, picked: PickedFile) {
+        match picked.purpose {
+            Purpose::Rom => {
+                self.picked_path = picked.path.display().to_string();
+                self.last_error = None;
+                self.cgb_title = rutile_gb_core::palette::is_cgb_title(&picked.data);
+                self.load_symbols(&picked.path);
+                self.load_cheats(&picked.path);
+                self.rom_path = Some(picked.path);
+                self.emulator.send(move |machine| {
+                    if let Err(err) = machine.cpu.memory.load_rom(&picked.data) {
+                        machine.error(err.to_string());
+                    }
+                    machine.rewind.clear();
+                    machine.debugger.call_stack.clear();
+                    machine.stop_movie(true);
+                });
+            }
+            Purpose::Movie => match Movie::from_bytes(&picked.data) {
+                Ok(movie) => self.emulator.send(move |machine| machine.play_movie(movie)),
+                Err(err) => self.last_error = Some(err.to_string()),
+            },
+            Purpose::Palettes => self.import_palettes(&picked.data),
+            // These are only ever saved
+            Purpose::Report | Purpose::Screenshot | Purpose::Gif => (),
+        }
+    }
+
+    // RGBDS and WLA put game.sym next to game.gb, a ROM without one just has no labels
+    fn load_symbols(&mut self, rom: &Path) {
+        self.symbols = Symbols::new();
+        let Ok(text) = files::read(&rom.with_extension("sym")) else {
+            return;
+        };
+        match Symbols::parse(&String::from_utf8_lossy(&text)) {
+            Ok(symbols) => {
+                self.status = Some(format!("Loaded {} symbols", symbols.len()));
+                self.symbols = symbols;
+            }
+            Err(err) => self.last_error = Some(err),
+        }
+    }
+
+    /// A number like `$0150` or a label from the symbol file
+    fn parse_address(&self, text: &str) -> Option<u16> {
+        let text = text.trim();
+        parse_number(text).or_else(|| self.symbols.address_of(text))
+    }
+
+    /// `$0150` or `Main ($0150)`
+    fn address_text(&self, address: u16) -> String {
+        match self.symbols.label(address) {
+            Some(label) => format!("{} (${:04X})", label, address),
+            None => format!("${:04X}", address),
+        }
+    }
+
+    /// Like `address_text` but inside functions too, `Main+3 ($0153)`
+    fn location_text(&self, address: u16) -> String {
+        match self.symbols.nearest(address) {
+            Some((label, 0)) => format!("{} (${:04X})", label, address),
+            Some((label, offset)) => format!("{}+{} (${:04X})", label, offset, address),
+            None => format!("${:04X}", address),
+        }
+    }
+
+    fn handle_event(&mut self, event: Event) {
+        match event {
+            Event::Status(status) => self.status = Some(status),
+            Event::Error(error) => self.last_error = Some(error),
+            Event::MovieRecorded(movie) => {
+                let file_name = match self.rom_path.as_ref().and_then(|path| path.file_stem()) {
+                    Some(stem) => format!("{}.gbm", stem.to_string_lossy()),
+                    None => "movie.gbm".to_string(),
+                };
+                match files::save_as(Purpose::Movie, &file_name, &movie.to_bytes()) {
+                    Ok(Some(location)) => self.status = Some(format!("Saved {} frames to {}", movie.inputs.len(), location)),
+                    Ok(None) => (),
+                    Err(err) => self.last_error = Some(err),
+                }
+            }
+            Event::GifRecorded(gif) => match files::save_as(Purpose::Gif, &self.capture_file_name("gif"), &gif) {
+                Ok(Some(location)) => self.status = Some(format!("Saved the recording to {}", location)),
+                Ok(None) => (),
+                Err(err) => self.last_error = Some(err),
+            },
         }
     }
 }
 
 impl eframe::App for MyApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        if self.single_step || ((self.speed == 100) ||(!self.halt && self.speed > 0 && (ctx.frame_nr() % (100 - self.speed) == 0))) {
-            self.cpu.step();
-            self.single_step = false;
+        while let Some(picked) = self.files.poll() {
+            match picked {
+                Ok(picked) => self.open_picked(picked),
+                Err(err) => self.last_error = Some(err),
+            }
         }
+        self.poll_keys(ctx);
+        self.poll_rewind_key(ctx);
+        self.poll_speed(ctx);
+
+        let elapsed = self.elapsed(ctx);
+        self.emulator.update(elapsed);
+        let events: Vec<Event> = self.emulator.events().collect();
+        for event in events {
+            self.handle_event(event);
+        }
+
+        let palette = self.screen_palette();
+        for (pixel, shade) in self.img.pixels.iter_mut().zip(&self.emulator.snapshot().screen) {
+            let [red, green, blue] = palette[*shade as usize];
+            *pixel = Color32::from_rgb(red, green, blue);
+        }
+
+        self.savestate_hotkeys(ctx);
+        self.screenshot_hotkey(ctx);
+        self.debugger_window(ctx);
+        self.memory_window(ctx);
+        self.vram_window(ctx);
+        self.io_window(ctx);
+        self.profiler_window(ctx);
+        self.cheats_window(ctx);
+        self.search_window(ctx);
 
         egui::CentralPanel::default().show(ctx, |ui| {
             let texture: egui::TextureHandle = ui.ctx().load_texture(
@@ -41,43 +224,71 @@ impl eframe::App for MyApp {
 
             ui.heading(format!("Rutil Gameboy Emulator - {}", self.picked_path));
             if ui.button("Open file…").clicked() {
-                if let Some(path) = rfd::FileDialog::new().pick_file() {
-                    self.picked_path = path.display().to_string();
-                    let data: Vec<u8> = std::fs::read(path.display().to_string()).unwrap();
-                    self.cpu.memory.load_rom(data)
-                }
+                self.files.pick(Purpose::Rom);
             }
-            ui.add(egui::Slider::new(&mut self.speed, 0..=100).text("Emulator Speed"));
+            self.pacing_controls(ui);
             if ui.button("Stop/Resume").clicked() {
-                self.halt = !self.halt;
+                self.emulator.send(|machine| {
+                    if machine.debugger.paused {
+                        machine.debugger.resume();
+                    } else {
+                        machine.debugger.pause();
+                    }
+                });
             }
             if ui.button("Single Step").clicked() {
-                self.single_step = true;
+                self.emulator.send(|machine| machine.single_step());
+            }
+            let mut policy = self.emulator.snapshot().illegal_opcode_policy;
+            egui::ComboBox::from_label("On illegal opcode")
+                .selected_text(format!("{:?}", policy))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut policy, IllegalOpcodePolicy::Lock, "Lock");
+                    ui.selectable_value(&mut policy, IllegalOpcodePolicy::Break, "Break");
+                    ui.selectable_value(&mut policy, IllegalOpcodePolicy::Error, "Error");
+                });
+            if policy != self.emulator.snapshot().illegal_opcode_policy {
+                self.emulator.send(move |machine| machine.cpu.illegal_opcode_policy = policy);
             }
+            if self.emulator.snapshot().registry.locked {
+                ui.label(RichText::new("CPU locked up on an illegal opcode").color(Color32::RED));
+            }
+            self.savestate_controls(ui);
+            self.rewind_controls(ui);
+            self.movie_controls(ui);
+            self.capture_controls(ui);
+            self.palette_controls(ui);
+            if let Some(status) = &self.status {
+                ui.label(status);
+            }
+            if let Some(error) = &self.last_error {
+                ui.label(RichText::new(error).color(Color32::RED));
+            }
+            let snap = self.emulator.snapshot();
             ui.horizontal(|ui| {
                 ui.image(&texture, texture.size_vec2());
                 ui.vertical(|ui| {
                     ui.label(RichText::new("Registers:").strong().underline());
                     ui.label(format!("
-                        B: {:04X}\n
-                        C: {:04X}\n
-                        D: {:04X}\n
-                        E: {:04X}\n
-                        H: {:04X}\n
-                        L: {:04X}\n
-                        A: {:04X}\n
-                        SP: {:08X}\n
-                        PC: {:08X}\n
+                        B: {:02X}\n
+                        C: {:02X}\n
+                        D: {:02X}\n
+                        E: {:02X}\n
+                        H: {:02X}\n
+                        L: {:02X}\n
+                        A: {:02X}\n
+                        SP: {:04X}\n
+                        PC: {:04X}\n
                         ",
-                        self.cpu.registry.b,
-                        self.cpu.registry.c,
-                        self.cpu.registry.d,
-                        self.cpu.registry.e,
-                        self.cpu.registry.h,
-                        self.cpu.registry.l,
-                        self.cpu.registry.a,
-                        self.cpu.registry.sp,
-                        self.cpu.registry.pc,
+                        snap.registry.b,
+                        snap.registry.c,
+                        snap.registry.d,
+                        snap.registry.e,
+                        snap.registry.h,
+                        snap.registry.l,
+                        snap.registry.a,
+                        snap.registry.sp,
+                        snap.registry.pc,
                         ));
                 });
                 ui.vertical(|ui| {
@@ -89,37 +300,64 @@ impl eframe::App for MyApp {
                         C: {}\n
                         Bootrom: {}\n
                         ",
-                        self.cpu.registry.f.z_zero,
-                        self.cpu.registry.f.n_subtraction_bcd,
-                        self.cpu.registry.f.h_half_carry_bcd,
-                        self.cpu.registry.f.c_carry,
-                        self.cpu.memory.in_bootrom
+                        snap.registry.f.z_zero,
+                        snap.registry.f.n_subtraction_bcd,
+                        snap.registry.f.h_half_carry_bcd,
+                        snap.registry.f.c_carry,
+                        snap.in_bootrom
                         ));
                 });
                 ui.vertical(|ui| {
                     ui.label(RichText::new("Instruction Info:").strong().underline());
-                    ui.label(format!("Current Instruction: {:?}", self.cpu.last_instruction));
-                    let mut opcode = self.cpu.memory.read_byte(self.cpu.registry.pc);
-                    let prefixed = opcode == 0xCB;
-                    if prefixed {
-                        opcode = self.cpu.memory.read_byte(self.cpu.registry.pc + 1);
+                    ui.label(format!("Current Instruction: {:?}", snap.last_instruction));
+                    // Only while the disassembly or another window has the bus coming anyway
+                    if let Some(memory) = &snap.memory {
+                        let mut opcode = memory.peek(snap.registry.pc);
+                        let prefixed = opcode == 0xCB;
+                        if prefixed {
+                            opcode = memory.peek(snap.registry.pc.wrapping_add(1));
+                        }
+                        let next_instruction = Instructions::read_byte(opcode, prefixed).unwrap_or(Instructions::NOP());
+                        ui.label(format!("Next Instruction if not SP change: {:?} - Opcode: {:02X}", next_instruction, opcode));
+                        ui.label(format!("Is prefixed: {}", prefixed));
                     }
-                    let next_instruction = Instructions::read_byte(opcode, prefixed).unwrap_or(Instructions::NOP());
-                    ui.label(format!("Next Instruction if not SP change: {:?} - Opcode: {:02X}", next_instruction, opcode));
-                    ui.label(format!("Is prefixed: {}", prefixed));
-                    ui.label(format!("N8: {}", self.cpu.memory.read_byte(self.cpu.registry.sp)));
-                    ui.label(format!("N16: {}", self.cpu.memory.read_word(self.cpu.registry.sp)));
-                    ui.label(format!("E8: {}", self.cpu.memory.read_byte(self.cpu.registry.pc) as i8));
                 })
             });
-            ui.vertical(|ui| {
-                ui.label(RichText::new("Memory:").strong().underline());
-                egui::ScrollArea::vertical().show(ui, |ui| {
-                    ui.label(egui::RichText::new(format!("{:02X?}", self.cpu.memory.memory)).monospace());
+            egui::CollapsingHeader::new(RichText::new("Disassembly (right click for options)").strong()).default_open(false).show(ui, |ui| {
+                // Collapsed, neither the bus nor the breakpoints have to be copied each frame
+                let (Some(memory), Some(debugger)) = (self.emulator.memory(), self.emulator.debugger()) else {
+                    return;
+                };
+                let pc = snap.registry.pc;
+                let start = disassembler::context_start(memory, pc, 16);
+                egui::ScrollArea::vertical().id_source("disassembly").max_height(250.0).show(ui, |ui| {
+                    for line in disassembler::disassemble_range(memory, start, 32) {
+                        if let Some(label) = self.symbols.label(line.address) {
+                            ui.label(RichText::new(format!("{}:", label)).monospace().color(Color32::LIGHT_BLUE));
+                        }
+                        let marker = if debugger.has_breakpoint(line.address) { "●" } else { " " };
+                        let bytes: Vec<String> = line.opcode_bytes().iter().map(|byte| format!("{:02X}", byte)).collect();
+                        let mut text = RichText::new(format!("{} {:04X}  {:<8}  {}", marker, line.address, bytes.join(" "), line.text_with_labels(&self.symbols))).monospace();
+                        if line.address == pc {
+                            text = text.strong().color(Color32::YELLOW);
+                        }
+                        ui.add(egui::Label::new(text).sense(egui::Sense::click())).context_menu(|ui| {
+                            if ui.button("Run to cursor").clicked() {
+                                let address = line.address;
+                                self.emulator.send(move |machine| machine.debugger.run_to(address));
+                                ui.close_menu();
+                            }
+                            if ui.button("Toggle breakpoint").clicked() {
+                                let address = line.address;
+                                self.emulator.send(move |machine| machine.debugger.toggle_breakpoint(address));
+                                ui.close_menu();
+                            }
+                        });
+                    }
                 });
-            })
+            });
         });
 
-        ctx.request_repaint(); // The loop runs at VSYNC, Emulator runs on it's own speed
+        ctx.request_repaint(); // Picks up new snapshots at VSYNC, the emulation runs on its own clock
     }
 }
\ No newline at end of file
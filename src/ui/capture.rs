@@ -0,0 +1,89 @@
+use eframe::egui::{self, Key};
+
+use rutile_gb_core::capture::GifEncoder;
+
+use crate::emulator::Recording;
+use crate::files::{self, Purpose};
+use crate::image;
+
+use super::MyApp;
+
+const SCREENSHOT_KEY: Key = Key::F12;
+const MAX_SCALE: usize = 8;
+
+impl MyApp {
+    /// game.gb -> game.png, or screenshot.png without a ROM
+    pub(super) fn capture_file_name(&self, extension: &str) -> String {
+        match self.rom_path.as_ref().and_then(|path| path.file_stem()) {
+            Some(stem) => format!("{}.{}", stem.to_string_lossy(), extension),
+            None => format!("screenshot.{}", extension),
+        }
+    }
+
+    /// Saves the frame on screen right now
+    pub(super) fn screenshot(&mut self) {
+        let png = match image::png(&self.emulator.snapshot().screen, self.capture_scale, self.screen_palette()) {
+            Ok(png) => png,
+            Err(err) => {
+                self.last_error = Some(err);
+                return;
+            }
+        };
+        match files::save_as(Purpose::Screenshot, &self.capture_file_name("png"), &png) {
+            Ok(Some(location)) => self.status = Some(format!("Saved a screenshot to {}", location)),
+            Ok(None) => (),
+            Err(err) => self.last_error = Some(err),
+        }
+    }
+
+    pub(super) fn screenshot_hotkey(&mut self, ctx: &egui::Context) {
+        if ctx.input(|input| input.key_pressed(SCREENSHOT_KEY)) {
+            self.screenshot();
+        }
+    }
+
+    // Frames go into a directory next to the ROM, game.gb -> game.gb.frames/00000.png
+    fn record_frames(&mut self) {
+        let Some(rom) = &self.rom_path else {
+            self.last_error = Some("Load a ROM before recording frames".to_string());
+            return;
+        };
+        let mut name = rom.file_name().unwrap_or_default().to_os_string();
+        name.push(".frames");
+        let directory = rom.with_file_name(name);
+        if let Err(err) = std::fs::create_dir_all(&directory) {
+            self.last_error = Some(format!("Couldn't create {}: {}", directory.display(), err));
+            return;
+        }
+        let (scale, palette) = (self.capture_scale, self.screen_palette());
+        self.emulator.send(move |machine| machine.start_recording(Recording::Frames { directory, scale, palette, written: 0 }));
+    }
+
+    pub(super) fn capture_controls(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.button("Screenshot (F12)").clicked() {
+                self.screenshot();
+            }
+            ui.add(egui::Slider::new(&mut self.capture_scale, 1..=MAX_SCALE).text("x scale"));
+            match self.emulator.snapshot().recording {
+                None => {
+                    if ui.button("Record GIF").clicked() {
+                        let encoder = GifEncoder::new(self.capture_scale, self.screen_palette());
+                        self.emulator.send(move |machine| machine.start_recording(Recording::Gif(encoder)));
+                    }
+                    // The browser has no file system to put thousands of frames in
+                    let frames = ui.add_enabled(!cfg!(target_arch = "wasm32"), egui::Button::new("Record frames"));
+                    if frames.on_hover_text("One PNG per frame in a directory next to the ROM").clicked() {
+                        self.record_frames();
+                    }
+                }
+                Some(frames) => {
+                    ui.label(format!("Recording the screen, {} frames", frames));
+                    if ui.button("Stop").clicked() {
+                        self.emulator.send(|machine| machine.stop_recording());
+                    }
+                }
+            }
+        });
+    }
+}
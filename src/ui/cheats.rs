@@ -0,0 +1,115 @@
+use std::path::{Path, PathBuf};
+
+use eframe::{egui::{self, RichText}, epaint::Color32};
+
+use rutile_gb_core::cheats::{CheatCode, Cheats};
+
+use crate::files;
+
+use super::MyApp;
+
+pub struct CheatInputs {
+    code: String,
+    name: String,
+    error: Option<String>,
+}
+
+impl CheatInputs {
+    pub fn new() -> Self {
+        Self { code: String::new(), name: String::new(), error: None }
+    }
+}
+
+// Stored next to the ROM like the save state slots, game.gb -> game.gb.cht
+fn cheats_path(rom: &Path) -> Option<PathBuf> {
+    let mut name = rom.file_name()?.to_os_string();
+    name.push(".cht");
+    Some(rom.with_file_name(name))
+}
+
+impl MyApp {
+    /// Picks up the cheats saved for `rom`, a ROM without any starts with none
+    pub(super) fn load_cheats(&mut self, rom: &Path) {
+        self.cheats = Cheats::new();
+        if let Some(Ok(data)) = cheats_path(rom).map(|path| files::read(&path)) {
+            match Cheats::parse(&String::from_utf8_lossy(&data)) {
+                Ok(cheats) => self.cheats = cheats,
+                Err(err) => self.last_error = Some(err),
+            }
+        }
+        self.send_cheats();
+    }
+
+    fn send_cheats(&self) {
+        let cheats = self.cheats.clone();
+        self.emulator.send(move |machine| machine.set_cheats(cheats));
+    }
+
+    // Every change is saved right away, without a ROM there's nowhere to put them
+    pub(super) fn cheats_changed(&mut self) {
+        self.send_cheats();
+        let Some(path) = self.rom_path.as_deref().and_then(cheats_path) else {
+            return;
+        };
+        if let Err(err) = files::write(&path, self.cheats.to_text().as_bytes()) {
+            self.last_error = Some(err);
+        }
+    }
+
+    pub(super) fn cheats_window(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Cheats").default_open(false).show(ctx, |ui| {
+            let mut changed = false;
+            let mut remove = None;
+            egui::Grid::new("cheats").striped(true).show(ui, |ui| {
+                for (index, cheat) in self.cheats.cheats.iter_mut().enumerate() {
+                    changed |= ui.checkbox(&mut cheat.enabled, "").changed();
+                    ui.monospace(&cheat.text);
+                    ui.label(match cheat.code {
+                        CheatCode::GameShark { value, address, .. } => format!("{:04X} = {:02X} every frame", address, value),
+                        CheatCode::GameGenie { address, value, compare: Some(compare) } => {
+                            format!("{:04X} reads {:02X} if it holds {:02X}", address, value, compare)
+                        }
+                        CheatCode::GameGenie { address, value, compare: None } => format!("{:04X} reads {:02X}", address, value),
+                    });
+                    ui.label(&cheat.name);
+                    if ui.small_button("🗑").on_hover_text("Remove").clicked() {
+                        remove = Some(index);
+                    }
+                    ui.end_row();
+                }
+            });
+            if let Some(index) = remove {
+                self.cheats.cheats.remove(index);
+                changed = true;
+            }
+
+            ui.separator();
+            let inputs = &mut self.cheat_inputs;
+            ui.horizontal(|ui| {
+                ui.label("Code:");
+                ui.add(egui::TextEdit::singleline(&mut inputs.code).hint_text("01FF34C1 or 3E0-3AF-E6E").desired_width(120.0));
+                ui.label("Name:");
+                ui.add(egui::TextEdit::singleline(&mut inputs.name).desired_width(140.0));
+                if ui.button("Add").clicked() {
+                    match self.cheats.add(&inputs.code, &inputs.name) {
+                        Ok(()) => {
+                            *inputs = CheatInputs::new();
+                            changed = true;
+                        }
+                        Err(err) => inputs.error = Some(err),
+                    }
+                }
+            });
+            if let Some(error) = &inputs.error {
+                ui.label(RichText::new(error).color(Color32::RED));
+            }
+            if self.rom_path.is_none() {
+                ui.label("Cheats are only saved once a ROM is loaded");
+            }
+
+            if changed {
+                self.cheats_changed();
+            }
+        });
+    }
+}
@@ -0,0 +1,283 @@
+use eframe::{egui::{self, RichText}, epaint::Color32};
+
+use rutile_gb_core::callstack::{FrameKind, Imbalance};
+use rutile_gb_core::debugger::{Condition, StopReason, WatchKind};
+
+use crate::gdb::GdbStub;
+
+use super::MyApp;
+
+/// Text fields of the debugger window that haven't been turned into breakpoints/watchpoints yet
+pub struct DebuggerInputs {
+    breakpoint_address: String,
+    breakpoint_condition: String,
+    watch_start: String,
+    watch_end: String,
+    watch_kind: WatchKind,
+    gdb_port: String,
+    trace_path: String,
+    error: Option<String>,
+}
+
+impl DebuggerInputs {
+    pub fn new() -> Self {
+        Self {
+            breakpoint_address: String::new(),
+            breakpoint_condition: String::new(),
+            watch_start: String::new(),
+            watch_end: String::new(),
+            watch_kind: WatchKind::Write,
+            gdb_port: "2345".to_string(),
+            trace_path: "trace.log".to_string(),
+            error: None,
+        }
+    }
+}
+
+impl MyApp {
+    pub(super) fn debugger_window(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Debugger").default_open(false).default_width(380.0).show(ctx, |ui| {
+            let paused = self.emulator.snapshot().paused;
+            ui.horizontal(|ui| {
+                let label = if paused { "Continue" } else { "Pause" };
+                if ui.button(label).clicked() {
+                    self.emulator.send(move |machine| {
+                        if paused {
+                            machine.debugger.resume();
+                        } else {
+                            machine.debugger.pause();
+                        }
+                    });
+                }
+                ui.add_enabled_ui(paused, |ui| {
+                    if ui.button("Step").clicked() {
+                        self.emulator.send(|machine| machine.single_step());
+                    }
+                    if ui.button("Step Over").clicked() {
+                        self.emulator.send(|machine| {
+                            let result = machine.debugger.step_over(&mut machine.cpu);
+                            machine.latch_input();
+                            if let Err(err) = result {
+                                machine.error(err.to_string());
+                            }
+                        });
+                    }
+                    if ui.button("Step Out").clicked() {
+                        self.emulator.send(|machine| machine.debugger.step_out(&machine.cpu));
+                    }
+                });
+            });
+            if let Some(reason) = self.emulator.snapshot().last_stop {
+                ui.label(match reason {
+                    StopReason::Breakpoint(address) => format!("Stopped at breakpoint {}", self.address_text(address)),
+                    StopReason::Watchpoint { address, write: true } => format!("Stopped on write to {:04X}", address),
+                    StopReason::Watchpoint { address, write: false } => format!("Stopped on read from {:04X}", address),
+                    StopReason::GoalReached => "Stopped at target".to_string(),
+                    StopReason::IllegalOpcode(address) => format!("Stopped at illegal opcode {:04X}", address),
+                    StopReason::GaveUp => "Gave up, the target was never reached".to_string(),
+                });
+            }
+
+            ui.separator();
+            self.call_stack(ui);
+
+            ui.separator();
+            ui.label(RichText::new("Breakpoints:").strong().underline());
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut self.debugger_inputs.breakpoint_address).hint_text("$0150 or label").desired_width(100.0));
+                ui.add(egui::TextEdit::singleline(&mut self.debugger_inputs.breakpoint_condition).hint_text("Condition, e.g. A == $10 && ZF").desired_width(200.0));
+                if ui.button("Add").clicked() {
+                    let address = self.parse_address(&self.debugger_inputs.breakpoint_address);
+                    let inputs = &mut self.debugger_inputs;
+                    let condition = inputs.breakpoint_condition.trim().to_string();
+                    // Checked here so a typo shows up next to the field, the machine parses it again
+                    let valid = if condition.is_empty() { Ok(()) } else { Condition::parse(&condition).map(|_| ()) };
+                    inputs.error = match (address, valid) {
+                        (Some(address), Ok(())) => {
+                            self.emulator.send(move |machine| {
+                                // Already validated, this can't fail
+                                let _ = machine.debugger.add_breakpoint(address, &condition);
+                            });
+                            None
+                        }
+                        (Some(_), Err(err)) => Some(err),
+                        (None, _) => Some(format!("'{}' isn't an address or label", inputs.breakpoint_address)),
+                    };
+                }
+            });
+            let mut toggle = None;
+            let mut remove = None;
+            egui::Grid::new("breakpoints").striped(true).show(ui, |ui| {
+                for (index, breakpoint) in self.emulator.debugger().into_iter().flat_map(|debugger| &debugger.breakpoints).enumerate() {
+                    let mut enabled = breakpoint.enabled;
+                    if ui.checkbox(&mut enabled, "").changed() {
+                        toggle = Some((index, enabled));
+                    }
+                    ui.monospace(self.address_text(breakpoint.address));
+                    ui.monospace(&breakpoint.condition_text);
+                    ui.label(format!("{} hits", breakpoint.hits));
+                    if ui.small_button("x").clicked() {
+                        remove = Some(index);
+                    }
+                    ui.end_row();
+                }
+            });
+            if let Some((index, enabled)) = toggle {
+                self.emulator.send(move |machine| {
+                    if let Some(breakpoint) = machine.debugger.breakpoints.get_mut(index) {
+                        breakpoint.enabled = enabled;
+                    }
+                });
+            }
+            if let Some(index) = remove {
+                self.emulator.send(move |machine| {
+                    if index < machine.debugger.breakpoints.len() {
+                        machine.debugger.breakpoints.remove(index);
+                    }
+                });
+            }
+
+            ui.separator();
+            ui.label(RichText::new("Watchpoints:").strong().underline());
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut self.debugger_inputs.watch_start).hint_text("$C000").desired_width(60.0));
+                ui.add(egui::TextEdit::singleline(&mut self.debugger_inputs.watch_end).hint_text("End").desired_width(60.0));
+                egui::ComboBox::from_id_source("watch_kind")
+                    .selected_text(format!("{:?}", self.debugger_inputs.watch_kind))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.debugger_inputs.watch_kind, WatchKind::Read, "Read");
+                        ui.selectable_value(&mut self.debugger_inputs.watch_kind, WatchKind::Write, "Write");
+                        ui.selectable_value(&mut self.debugger_inputs.watch_kind, WatchKind::ReadWrite, "ReadWrite");
+                    });
+                if ui.button("Add").clicked() {
+                    let start = self.parse_address(&self.debugger_inputs.watch_start);
+                    // Leaving the end empty watches a single byte
+                    let end = if self.debugger_inputs.watch_end.trim().is_empty() { start } else { self.parse_address(&self.debugger_inputs.watch_end) };
+                    let inputs = &mut self.debugger_inputs;
+                    inputs.error = match (start, end) {
+                        (Some(start), Some(end)) => {
+                            let kind = inputs.watch_kind;
+                            self.emulator.send(move |machine| machine.debugger.add_watchpoint(start, end, kind));
+                            None
+                        }
+                        _ => Some("The watchpoint range isn't valid".to_string()),
+                    };
+                }
+            });
+            let mut toggle = None;
+            let mut remove = None;
+            egui::Grid::new("watchpoints").striped(true).show(ui, |ui| {
+                for (index, watchpoint) in self.emulator.debugger().into_iter().flat_map(|debugger| &debugger.watchpoints).enumerate() {
+                    let mut enabled = watchpoint.enabled;
+                    if ui.checkbox(&mut enabled, "").changed() {
+                        toggle = Some((index, enabled));
+                    }
+                    ui.monospace(format!("{:04X}-{:04X}", watchpoint.start, watchpoint.end));
+                    ui.label(format!("{:?}", watchpoint.kind));
+                    ui.label(format!("{} hits", watchpoint.hits));
+                    if ui.small_button("x").clicked() {
+                        remove = Some(index);
+                    }
+                    ui.end_row();
+                }
+            });
+            if let Some((index, enabled)) = toggle {
+                self.emulator.send(move |machine| {
+                    if let Some(watchpoint) = machine.debugger.watchpoints.get_mut(index) {
+                        watchpoint.enabled = enabled;
+                    }
+                });
+            }
+            if let Some(index) = remove {
+                self.emulator.send(move |machine| {
+                    if index < machine.debugger.watchpoints.len() {
+                        machine.debugger.watchpoints.remove(index);
+                    }
+                });
+            }
+
+            ui.separator();
+            ui.label(RichText::new("GDB Server:").strong().underline());
+            ui.horizontal(|ui| match &self.emulator.snapshot().gdb {
+                Some(gdb) => {
+                    let state = if gdb.connected { "client attached" } else { "waiting for a client" };
+                    ui.label(format!("Listening on 127.0.0.1:{}, {}", gdb.port, state));
+                    if ui.button("Stop").clicked() {
+                        self.emulator.send(|machine| machine.gdb = None);
+                    }
+                }
+                None => {
+                    ui.add(egui::TextEdit::singleline(&mut self.debugger_inputs.gdb_port).desired_width(60.0));
+                    if ui.button("Listen").clicked() {
+                        let inputs = &mut self.debugger_inputs;
+                        inputs.error = match inputs.gdb_port.trim().parse::<u16>() {
+                            Ok(port) => {
+                                // The socket belongs to the emulation side, it polls it between frames
+                                self.emulator.send(move |machine| match GdbStub::listen(port) {
+                                    Ok(gdb) => machine.gdb = Some(gdb),
+                                    Err(err) => machine.error(format!("Couldn't listen on port {}: {}", port, err)),
+                                });
+                                None
+                            }
+                            Err(_) => Some(format!("'{}' isn't a port", inputs.gdb_port)),
+                        };
+                    }
+                }
+            });
+
+            ui.separator();
+            ui.label(RichText::new("Trace (Gameboy Doctor format):").strong().underline());
+            ui.horizontal(|ui| match &self.emulator.snapshot().trace {
+                Some((path, lines)) => {
+                    ui.label(format!("{} lines to {}", lines, path));
+                    if ui.button("Stop").clicked() {
+                        self.emulator.send(|machine| machine.stop_trace());
+                    }
+                }
+                None => {
+                    ui.add(egui::TextEdit::singleline(&mut self.debugger_inputs.trace_path).desired_width(200.0));
+                    if ui.button("Start").clicked() {
+                        let path = self.debugger_inputs.trace_path.trim().to_string();
+                        self.emulator.send(move |machine| machine.start_trace(&path));
+                    }
+                }
+            });
+
+            if let Some(error) = &self.debugger_inputs.error {
+                ui.label(RichText::new(error).color(Color32::RED));
+            }
+        });
+    }
+
+    fn call_stack(&mut self, ui: &mut egui::Ui) {
+        let Some(debugger) = self.emulator.debugger() else {
+            return;
+        };
+        let call_stack = &debugger.call_stack;
+        ui.label(RichText::new("Call Stack:").strong().underline());
+        egui::ScrollArea::vertical().id_source("call_stack").max_height(150.0).show(ui, |ui| {
+            ui.monospace(format!("#0 {}", self.location_text(self.emulator.snapshot().registry.pc)));
+            for (depth, frame) in call_stack.frames.iter().rev().enumerate() {
+                let kind = match frame.kind {
+                    FrameKind::Call => "CALL",
+                    FrameKind::Rst => "RST",
+                    FrameKind::Interrupt => "interrupt",
+                };
+                ui.monospace(format!(
+                    "#{} {} from {}, returns to {}",
+                    depth + 1,
+                    kind,
+                    self.location_text(frame.call_site),
+                    self.address_text(frame.return_address),
+                ));
+            }
+        });
+        if let Some(imbalance) = call_stack.last_imbalance {
+            let text = match imbalance {
+                Imbalance::ReturnMismatch { at, to } => format!("RET at {} went to {}, not where it was called from", self.address_text(at), self.address_text(to)),
+                Imbalance::Discarded { at, frames } => format!("The code at {} moved SP past {} frames without returning", self.address_text(at), frames),
+            };
+            ui.label(RichText::new(format!("{} ({} so far)", text, call_stack.imbalances)).color(Color32::YELLOW));
+        }
+    }
+}
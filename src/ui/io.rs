@@ -0,0 +1,76 @@
+use eframe::egui::{self, RichText};
+
+use rutile_gb_core::io::{self, Field};
+
+use super::MyApp;
+
+pub struct IoViewer {
+    // Hides the addresses nothing is mapped to
+    show_unused: bool,
+}
+
+impl IoViewer {
+    pub fn new() -> Self {
+        Self { show_unused: false }
+    }
+}
+
+impl MyApp {
+    pub(super) fn io_window(&mut self, ctx: &egui::Context) {
+        egui::Window::new("IO Registers").default_open(false).default_width(420.0).show(ctx, |ui| {
+            ui.checkbox(&mut self.io_viewer.show_unused, "Show unused addresses");
+            egui::ScrollArea::vertical().id_source("io").max_height(500.0).show(ui, |ui| {
+                let mut write = None;
+                let Some(memory) = self.emulator.memory() else {
+                    return;
+                };
+                for address in (0xFF00..=0xFF7F).chain([0xFFFF]) {
+                    let value = memory.read_byte(address);
+                    let Some(register) = io::describe(address) else {
+                        if self.io_viewer.show_unused {
+                            ui.monospace(format!("{:04X}  --    {:02X}", address, value));
+                        }
+                        continue;
+                    };
+                    let header = RichText::new(format!("{:04X}  {:<5} {:02X}  {:08b}", address, register.name, value, value)).monospace();
+                    egui::CollapsingHeader::new(header).id_source(address).show(ui, |ui| {
+                        egui::Grid::new(("io_fields", address)).show(ui, |ui| {
+                            for field in register.fields {
+                                ui.label(field.name);
+                                if let Some(new) = field_editor(ui, address, field, field.get(value)) {
+                                    write = Some((address, field.set(value, new)));
+                                }
+                                ui.end_row();
+                            }
+                        });
+                    });
+                }
+                if let Some((address, value)) = write {
+                    // Through the bus like a game would, so writes have their side effects
+                    self.emulator.send(move |machine| machine.cpu.memory.write_byte(address, value));
+                }
+            });
+        });
+    }
+}
+
+// Checkbox for flags, a combo box for named values and a number otherwise, returns the new value if it was changed
+fn field_editor(ui: &mut egui::Ui, address: u16, field: &Field, value: u8) -> Option<u8> {
+    let mut edited = value;
+    if !field.values.is_empty() {
+        egui::ComboBox::from_id_source((address, field.name))
+            .selected_text(field.value_name(value).unwrap_or("?"))
+            .show_ui(ui, |ui| {
+                for (option, name) in field.values.iter().enumerate() {
+                    ui.selectable_value(&mut edited, option as u8, *name);
+                }
+            });
+    } else if field.width == 1 {
+        let mut set = value != 0;
+        ui.checkbox(&mut set, "");
+        edited = set as u8;
+    } else {
+        ui.add(egui::DragValue::new(&mut edited).clamp_range(0..=field.max()).hexadecimal(2, false, true));
+    }
+    (edited != value).then_some(edited)
+}
@@ -0,0 +1,158 @@
+use eframe::{egui::{self, RichText, Sense, TextStyle}, epaint::Color32};
+
+use rutile_gb_core::debugger::parse_number;
+use rutile_gb_core::memory::Region;
+
+use super::MyApp;
+
+const BYTES_PER_ROW: usize = 16;
+const ROWS: usize = 0x10000 / BYTES_PER_ROW;
+const JUMP_REGIONS: [Region; 8] = [
+    Region::Rom(0),
+    Region::Rom(1),
+    Region::Vram,
+    Region::ExternalRam,
+    Region::Wram,
+    Region::Oam,
+    Region::Io,
+    Region::Hram,
+];
+
+/// State of the hex editor between repaints
+pub struct MemoryViewer {
+    jump: String,
+    scroll_to: Option<u16>,
+    // Byte being edited and the text typed so far
+    editing: Option<(u16, String)>,
+    // The address space as the CPU sees it, now and before the last step, to highlight changes
+    current: Vec<u8>,
+    previous: Vec<u8>,
+    cycles: u64,
+    error: Option<String>,
+}
+
+impl MemoryViewer {
+    pub fn new() -> Self {
+        Self {
+            jump: String::new(),
+            scroll_to: None,
+            editing: None,
+            current: Vec::new(),
+            previous: Vec::new(),
+            cycles: 0,
+            error: None,
+        }
+    }
+
+    fn changed(&self, address: u16) -> bool {
+        !self.previous.is_empty() && self.previous[address as usize] != self.current[address as usize]
+    }
+}
+
+impl MyApp {
+    pub(super) fn memory_window(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Memory").default_open(false).default_width(620.0).show(ctx, |ui| {
+            let Some(memory) = self.emulator.memory() else {
+                return;
+            };
+            let cycles = self.emulator.snapshot().cycles;
+            let viewer = &mut self.memory_viewer;
+            if viewer.current.is_empty() || cycles != viewer.cycles {
+                // read_byte so the boot ROM overlay and the joypad register show what the game reads
+                let bytes = (0..=0xFFFF).map(|address| memory.read_byte(address)).collect();
+                viewer.previous = std::mem::replace(&mut viewer.current, bytes);
+                viewer.cycles = cycles;
+            }
+
+            ui.horizontal(|ui| {
+                let response = ui.add(egui::TextEdit::singleline(&mut self.memory_viewer.jump).hint_text("$C000").desired_width(60.0));
+                let entered = response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter));
+                if ui.button("Go").clicked() || entered {
+                    let viewer = &mut self.memory_viewer;
+                    match parse_number(viewer.jump.trim()) {
+                        Some(address) => {
+                            viewer.scroll_to = Some(address);
+                            viewer.error = None;
+                        }
+                        None => viewer.error = Some(format!("'{}' isn't an address", viewer.jump)),
+                    }
+                }
+                for region in JUMP_REGIONS {
+                    if ui.small_button(region.to_string()).clicked() {
+                        self.memory_viewer.scroll_to = Some(region.start());
+                    }
+                }
+            });
+            if let Some(error) = &self.memory_viewer.error {
+                ui.label(RichText::new(error).color(Color32::RED));
+            }
+
+            let row_height = ui.text_style_height(&TextStyle::Monospace);
+            let mut scroll = egui::ScrollArea::vertical().id_source("memory").max_height(400.0).auto_shrink([false, false]);
+            if let Some(address) = self.memory_viewer.scroll_to.take() {
+                let row = address as usize / BYTES_PER_ROW;
+                scroll = scroll.vertical_scroll_offset(row as f32 * (row_height + ui.spacing().item_spacing.y));
+            }
+            scroll.show_rows(ui, row_height, ROWS, |ui, rows| {
+                for row in rows {
+                    self.memory_row(ui, (row * BYTES_PER_ROW) as u16);
+                }
+            });
+        });
+    }
+
+    fn memory_row(&mut self, ui: &mut egui::Ui, start: u16) {
+        ui.horizontal(|ui| {
+            ui.spacing_mut().item_spacing.x = 4.0;
+            ui.monospace(format!("{:04X}", start));
+            ui.add_sized([90.0, 0.0], egui::Label::new(RichText::new(Region::of(start).to_string()).small().weak()));
+
+            let mut commit = None;
+            for offset in 0..BYTES_PER_ROW as u16 {
+                let address = start + offset;
+                if offset == 8 {
+                    ui.add_space(6.0);
+                }
+                let viewer = &mut self.memory_viewer;
+                match &mut viewer.editing {
+                    Some((editing, text)) if *editing == address => {
+                        let response = ui.add(egui::TextEdit::singleline(text).font(TextStyle::Monospace).desired_width(16.0).char_limit(2));
+                        response.request_focus();
+                        if ui.input(|input| input.key_pressed(egui::Key::Escape)) {
+                            viewer.editing = None;
+                        } else if response.lost_focus() || ui.input(|input| input.key_pressed(egui::Key::Enter)) {
+                            commit = Some((address, u8::from_str_radix(text.trim(), 16).ok()));
+                        }
+                    }
+                    _ => {
+                        let value = viewer.current[address as usize];
+                        let mut text = RichText::new(format!("{:02X}", value)).monospace();
+                        if viewer.changed(address) {
+                            text = text.color(Color32::YELLOW);
+                        }
+                        let response = ui.add(egui::Label::new(text).sense(Sense::click()))
+                            .on_hover_text(format!("{:04X} {}", address, Region::of(address)));
+                        if response.clicked() {
+                            viewer.editing = Some((address, format!("{:02X}", value)));
+                        }
+                    }
+                }
+            }
+            if let Some((address, value)) = commit {
+                self.memory_viewer.editing = None;
+                match value {
+                    // Pokes the backing array directly, going through write_byte would start serial transfers and the like
+                    Some(value) => self.emulator.send(move |machine| machine.cpu.memory.memory[address as usize] = value),
+                    None => self.memory_viewer.error = Some("Bytes are edited as two hex digits".to_string()),
+                }
+            }
+
+            ui.add_space(6.0);
+            let ascii: String = self.memory_viewer.current[start as usize..start as usize + BYTES_PER_ROW]
+                .iter()
+                .map(|byte| if byte.is_ascii_graphic() || *byte == b' ' { *byte as char } else { '.' })
+                .collect();
+            ui.monospace(ascii);
+        });
+    }
+}
@@ -0,0 +1,69 @@
+use eframe::egui::{self, Key};
+
+use rutile_gb_core::joypad;
+
+use crate::emulator::MovieStatus;
+use crate::files::Purpose;
+
+use super::MyApp;
+
+const BUTTON_KEYS: [(Key, u8); 8] = [
+    (Key::ArrowRight, joypad::RIGHT),
+    (Key::ArrowLeft, joypad::LEFT),
+    (Key::ArrowUp, joypad::UP),
+    (Key::ArrowDown, joypad::DOWN),
+    (Key::X, joypad::A),
+    (Key::Z, joypad::B),
+    (Key::Space, joypad::SELECT),
+    (Key::Enter, joypad::START),
+];
+
+impl MyApp {
+    /// Hands the buttons held on the keyboard to the emulation, it latches them on the next frame
+    pub(super) fn poll_keys(&mut self, ctx: &egui::Context) {
+        let keys = if ctx.wants_keyboard_input() {
+            0
+        } else {
+            ctx.input(|input| {
+                BUTTON_KEYS.iter().filter(|(key, _)| input.key_down(*key)).fold(0, |pressed, (_, button)| pressed | button)
+            })
+        };
+        if keys != self.keys {
+            self.keys = keys;
+            self.emulator.send(move |machine| machine.keys = keys);
+        }
+    }
+
+    pub(super) fn movie_controls(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            match self.emulator.snapshot().movie {
+                MovieStatus::Idle => {
+                    // Movies don't store cheats, see `Machine::cheats_block_movies`
+                    let allowed = !self.cheats.any_enabled();
+                    let hint = "Turn off the cheats to record or play movies";
+                    if ui.add_enabled(allowed, egui::Button::new("Record from power on")).on_disabled_hover_text(hint).clicked() {
+                        self.emulator.send(|machine| machine.record_movie(true));
+                    }
+                    if ui.add_enabled(allowed, egui::Button::new("Record from here")).on_disabled_hover_text(hint).clicked() {
+                        self.emulator.send(|machine| machine.record_movie(false));
+                    }
+                    if ui.add_enabled(allowed, egui::Button::new("Play movie…")).on_disabled_hover_text(hint).clicked() {
+                        self.files.pick(Purpose::Movie);
+                    }
+                }
+                MovieStatus::Recording { frames } => {
+                    ui.label(format!("Recording, {} frames", frames));
+                    if ui.button("Stop and save…").clicked() {
+                        self.emulator.send(|machine| machine.stop_movie(false));
+                    }
+                }
+                MovieStatus::Playing { frame, frames } => {
+                    ui.label(format!("Playing frame {} of {}", frame, frames));
+                    if ui.button("Stop").clicked() {
+                        self.emulator.send(|machine| machine.stop_movie(false));
+                    }
+                }
+            }
+        });
+    }
+}
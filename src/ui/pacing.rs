@@ -0,0 +1,38 @@
+use eframe::egui::{self, Key};
+
+use super::MyApp;
+
+const FAST_FORWARD_KEY: Key = Key::Tab;
+const SPEED_PRESETS: [f64; 6] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0];
+
+impl MyApp {
+    /// Wall-clock seconds since the last repaint
+    pub(super) fn elapsed(&mut self, ctx: &egui::Context) -> f64 {
+        let now = ctx.input(|input| input.time);
+        let elapsed = self.last_time.map_or(0.0, |last| now - last);
+        self.last_time = Some(now);
+        elapsed
+    }
+
+    /// Tells the emulation how fast to run, the fast forward speed while Tab is held
+    pub(super) fn poll_speed(&mut self, ctx: &egui::Context) {
+        let fast_forward = !ctx.wants_keyboard_input() && ctx.input(|input| input.key_down(FAST_FORWARD_KEY));
+        let speed = if fast_forward { self.fast_forward_speed } else { self.speed };
+        if speed != self.emulator.snapshot().speed {
+            self.emulator.send(move |machine| machine.pacer.speed = speed);
+        }
+    }
+
+    pub(super) fn pacing_controls(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.add(egui::Slider::new(&mut self.speed, 0.1..=8.0).logarithmic(true).text("×"));
+            for preset in SPEED_PRESETS {
+                ui.selectable_value(&mut self.speed, preset, format!("{}×", preset));
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.add(egui::Slider::new(&mut self.fast_forward_speed, 2.0..=16.0).text("× while holding Tab"));
+            ui.label(format!("Frame {}", self.emulator.snapshot().frame));
+        });
+    }
+}
@@ -0,0 +1,117 @@
+use std::path::Path;
+
+use eframe::egui;
+
+use rutile_gb_core::palette::{self, NamedPalette, Palette};
+
+use crate::files::{self, Purpose};
+
+use super::MyApp;
+
+// Looked for in the working directory, on the web it's the localStorage key
+const PALETTES_FILE: &str = "palettes.txt";
+
+/// Which palette the screen, screenshots and recordings use
+pub struct PaletteChoice {
+    pub selected: NamedPalette,
+    /// User-defined ones from `PALETTES_FILE`, the presets aren't in here
+    pub custom: Vec<NamedPalette>,
+    // Being edited in the custom palette editor
+    editing: NamedPalette,
+}
+
+impl PaletteChoice {
+    pub fn new() -> Self {
+        let (name, colours) = palette::PRESETS[0];
+        let selected = NamedPalette { name: name.to_string(), colours };
+        let mut choice = Self { selected: selected.clone(), custom: Vec::new(), editing: selected };
+        // No file just means no custom palettes yet
+        if let Ok(data) = files::read(Path::new(PALETTES_FILE)) {
+            choice.custom = palette::parse(&String::from_utf8_lossy(&data)).unwrap_or_default();
+        }
+        choice
+    }
+}
+
+impl MyApp {
+    /// The selected palette as it should be drawn, CGB games get the CGB's LCD colours
+    pub(super) fn screen_palette(&self) -> Palette {
+        let colours = self.palettes.selected.colours;
+        if self.cgb_title {
+            colours.map(palette::cgb_corrected)
+        } else {
+            colours
+        }
+    }
+
+    /// Adds palettes from a picked config file, ones with the same name are replaced
+    pub(super) fn import_palettes(&mut self, data: &[u8]) {
+        match palette::parse(&String::from_utf8_lossy(data)) {
+            Ok(imported) => {
+                self.status = Some(format!("Loaded {} palettes", imported.len()));
+                for palette in imported {
+                    self.add_custom_palette(palette);
+                }
+                self.save_palettes();
+            }
+            Err(err) => self.last_error = Some(err),
+        }
+    }
+
+    fn add_custom_palette(&mut self, palette: NamedPalette) {
+        let custom = &mut self.palettes.custom;
+        match custom.iter_mut().find(|existing| existing.name == palette.name) {
+            Some(existing) => *existing = palette,
+            None => custom.push(palette),
+        }
+    }
+
+    fn save_palettes(&mut self) {
+        if let Err(err) = files::write(Path::new(PALETTES_FILE), palette::to_text(&self.palettes.custom).as_bytes()) {
+            self.last_error = Some(err);
+        }
+    }
+
+    pub(super) fn palette_controls(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let choice = &mut self.palettes;
+            egui::ComboBox::from_label("Palette").selected_text(&choice.selected.name).show_ui(ui, |ui| {
+                for palette in palette::all(&choice.custom) {
+                    let name = palette.name.clone();
+                    ui.selectable_value(&mut choice.selected, palette, name);
+                }
+            });
+            if ui.button("Load palettes…").clicked() {
+                self.files.pick(Purpose::Palettes);
+            }
+            if self.cgb_title {
+                ui.label("CGB colour correction on");
+            }
+        });
+        egui::CollapsingHeader::new("Custom palette").show(ui, |ui| {
+            let editing = &mut self.palettes.editing;
+            ui.horizontal(|ui| {
+                ui.label("Name:");
+                ui.text_edit_singleline(&mut editing.name);
+                for colour in editing.colours.iter_mut() {
+                    ui.color_edit_button_srgb(colour);
+                }
+            });
+            let name = editing.name.trim().to_string();
+            // Colons separate the name from the colours in the file
+            let taken = palette::PRESETS.iter().any(|(preset, _)| *preset == name);
+            let valid = !name.is_empty() && !name.contains(':') && !taken;
+            let (restart, save) = ui.horizontal(|ui| {
+                (ui.button("Start from the selected one").clicked(), ui.add_enabled(valid, egui::Button::new("Save and use")).clicked())
+            }).inner;
+            if restart {
+                self.palettes.editing = self.palettes.selected.clone();
+            } else if save {
+                let palette = NamedPalette { name, colours: self.palettes.editing.colours };
+                self.palettes.selected = palette.clone();
+                self.add_custom_palette(palette);
+                self.save_palettes();
+            }
+        });
+    }
+}
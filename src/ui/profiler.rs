@@ -0,0 +1,130 @@
+use eframe::{egui::{self, RichText, TextureOptions}, epaint::Color32};
+
+use rutile_gb_core::profiler::Profiler;
+
+use crate::files::{self, Purpose};
+
+use super::MyApp;
+
+const HEATMAP_SIZE: usize = 256;
+const HEATMAP_SCALE: f32 = 2.0;
+const TOP_FUNCTIONS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Counter {
+    Executions,
+    Cycles,
+    Reads,
+    Writes,
+}
+
+impl Counter {
+    fn value(self, profiler: &Profiler, address: usize) -> u64 {
+        match self {
+            Counter::Executions => profiler.executions[address] as u64,
+            Counter::Cycles => profiler.cycles[address],
+            Counter::Reads => profiler.reads[address] as u64,
+            Counter::Writes => profiler.writes[address] as u64,
+        }
+    }
+}
+
+pub struct ProfilerView {
+    counter: Counter,
+}
+
+impl ProfilerView {
+    pub fn new() -> Self {
+        Self { counter: Counter::Executions }
+    }
+}
+
+// Black through red to yellow, on a log scale so a few hot loops don't wash out everything else
+fn heat(value: u64, max: u64) -> Color32 {
+    if value == 0 {
+        return Color32::BLACK;
+    }
+    let t = ((value as f32).ln_1p() / (max as f32).ln_1p()).clamp(0.0, 1.0);
+    let red = (t * 2.0).min(1.0);
+    let green = (t * 2.0 - 1.0).max(0.0);
+    Color32::from_rgb((red * 255.0) as u8, (green * 255.0) as u8, 0)
+}
+
+impl MyApp {
+    pub(super) fn profiler_window(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Profiler").default_open(false).show(ctx, |ui| {
+            let profiling = self.emulator.profiler().is_some();
+            ui.horizontal(|ui| {
+                let mut record = profiling;
+                if ui.checkbox(&mut record, "Record").changed() {
+                    self.emulator.send(move |machine| {
+                        machine.debugger.profiler = record.then(|| Box::new(Profiler::new()));
+                    });
+                }
+                if ui.add_enabled(profiling, egui::Button::new("Clear")).clicked() {
+                    self.emulator.send(|machine| {
+                        if let Some(profiler) = &mut machine.debugger.profiler {
+                            **profiler = Profiler::new();
+                        }
+                    });
+                }
+                if ui.add_enabled(profiling, egui::Button::new("Export CSV…")).clicked() {
+                    self.export_profile();
+                }
+            });
+            let Some(profiler) = self.emulator.profiler() else {
+                ui.label("Recording counts every instruction and memory access, it's off until ticked.");
+                return;
+            };
+
+            ui.horizontal(|ui| {
+                let counter = &mut self.profiler_view.counter;
+                ui.selectable_value(counter, Counter::Executions, "Executions");
+                ui.selectable_value(counter, Counter::Cycles, "Cycles");
+                ui.selectable_value(counter, Counter::Reads, "Reads");
+                ui.selectable_value(counter, Counter::Writes, "Writes");
+            });
+            let counter = self.profiler_view.counter;
+            let max = (0..0x10000).map(|address| counter.value(profiler, address)).max().unwrap_or(0);
+            let pixels = (0..0x10000).map(|address| heat(counter.value(profiler, address), max)).collect();
+            let image = egui::ColorImage { size: [HEATMAP_SIZE, HEATMAP_SIZE], pixels };
+            let texture = ui.ctx().load_texture("profiler-heatmap", image, TextureOptions::NEAREST);
+            ui.label("One pixel per address, $0000 top left, one row per 256 bytes");
+            let rect = ui.image(&texture, texture.size_vec2() * HEATMAP_SCALE).rect;
+            if let Some(pointer) = ui.ctx().pointer_hover_pos().filter(|pointer| rect.contains(*pointer)) {
+                let position = (pointer - rect.min) / HEATMAP_SCALE;
+                let address = (position.y as usize).min(255) * HEATMAP_SIZE + (position.x as usize).min(255);
+                ui.monospace(format!(
+                    "{}: {} executions, {} cycles, {} reads, {} writes",
+                    self.location_text(address as u16),
+                    profiler.executions[address],
+                    profiler.cycles[address],
+                    profiler.reads[address],
+                    profiler.writes[address],
+                ));
+            }
+
+            ui.label(RichText::new("Hottest functions (self time):").strong().underline());
+            egui::Grid::new("profiler_functions").striped(true).show(ui, |ui| {
+                for total in profiler.function_totals(&self.symbols).iter().take(TOP_FUNCTIONS) {
+                    ui.monospace(&total.name);
+                    ui.label(format!("{} cycles", total.cycles));
+                    ui.label(format!("{} instructions", total.instructions));
+                    ui.end_row();
+                }
+            });
+        });
+    }
+
+    fn export_profile(&mut self) {
+        let Some(profiler) = self.emulator.profiler() else {
+            return;
+        };
+        let csv = profiler.to_csv(&self.symbols);
+        match files::save_as(Purpose::Report, "profile.csv", csv.as_bytes()) {
+            Ok(Some(location)) => self.status = Some(format!("Saved the profile to {}", location)),
+            Ok(None) => (),
+            Err(err) => self.last_error = Some(err),
+        }
+    }
+}
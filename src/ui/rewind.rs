@@ -0,0 +1,41 @@
+use eframe::egui::{self, Key};
+
+use super::MyApp;
+
+const REWIND_KEY: Key = Key::Backspace;
+
+impl MyApp {
+    /// The emulation steps back one snapshot per frame while the rewind key is held
+    pub(super) fn poll_rewind_key(&mut self, ctx: &egui::Context) {
+        let rewinding = !ctx.wants_keyboard_input() && ctx.input(|input| input.key_down(REWIND_KEY));
+        if rewinding != self.rewinding {
+            self.rewinding = rewinding;
+            self.emulator.send(move |machine| machine.rewinding = rewinding);
+        }
+    }
+
+    pub(super) fn rewind_controls(&mut self, ui: &mut egui::Ui) {
+        let snap = self.emulator.snapshot();
+        let mut enabled = snap.rewind_enabled;
+        let mut interval = snap.rewind_interval;
+        let mut capacity_mib = snap.rewind_capacity_bytes / (1024 * 1024);
+        ui.horizontal(|ui| {
+            if ui.checkbox(&mut enabled, "Rewind (hold Backspace)").changed() {
+                self.emulator.send(move |machine| {
+                    machine.rewind_enabled = enabled;
+                    if !enabled {
+                        machine.rewind.clear();
+                    }
+                });
+            }
+            if ui.add(egui::Slider::new(&mut interval, 1..=60).text("Frames per snapshot")).changed() {
+                self.emulator.send(move |machine| machine.rewind.interval = interval);
+            }
+            if ui.add(egui::Slider::new(&mut capacity_mib, 8..=1024).text("MiB")).changed() {
+                self.emulator.send(move |machine| machine.rewind.capacity_bytes = capacity_mib * 1024 * 1024);
+            }
+            let snap = self.emulator.snapshot();
+            ui.label(format!("{} snapshots, {:.1} MiB", snap.rewind_len, snap.rewind_used_bytes as f32 / (1024.0 * 1024.0)));
+        });
+    }
+}
@@ -0,0 +1,72 @@
+use std::path::PathBuf;
+
+use eframe::egui::{self, Key};
+
+use crate::files;
+
+use super::MyApp;
+
+const SLOT_KEYS: [Key; 9] = [Key::F1, Key::F2, Key::F3, Key::F4, Key::F5, Key::F6, Key::F7, Key::F8, Key::F9];
+
+impl MyApp {
+    // Slots are stored next to the ROM, game.gb -> game.gb.ss1, on the web that's the localStorage key
+    fn slot_path(&self, slot: u8) -> Option<PathBuf> {
+        let rom = self.rom_path.as_ref()?;
+        let mut name = rom.file_name()?.to_os_string();
+        name.push(format!(".ss{}", slot));
+        Some(rom.with_file_name(name))
+    }
+
+    pub(super) fn save_slot(&mut self, slot: u8) {
+        let Some(path) = self.slot_path(slot) else {
+            self.last_error = Some("Load a ROM before saving a state".to_string());
+            return;
+        };
+        self.emulator.send(move |machine| match files::write(&path, &machine.cpu.save_state()) {
+            Ok(()) => machine.status(format!("Saved state to slot {}", slot)),
+            Err(err) => machine.error(err),
+        });
+    }
+
+    pub(super) fn load_slot(&mut self, slot: u8) {
+        let Some(path) = self.slot_path(slot) else {
+            self.last_error = Some("Load a ROM before loading a state".to_string());
+            return;
+        };
+        match files::read(&path) {
+            Ok(data) => self.emulator.send(move |machine| match machine.cpu.load_state(&data) {
+                Ok(()) => {
+                    machine.debugger.call_stack.clear();
+                    machine.stop_movie(true);
+                    machine.status(format!("Loaded state from slot {}", slot));
+                }
+                Err(err) => machine.error(err.to_string()),
+            }),
+            Err(err) => self.last_error = Some(err),
+        }
+    }
+
+    /// F1-F9 load a slot, Shift+F1-F9 save to it
+    pub(super) fn savestate_hotkeys(&mut self, ctx: &egui::Context) {
+        let pressed = ctx.input(|input| {
+            SLOT_KEYS.iter().position(|key| input.key_pressed(*key)).map(|index| (index as u8 + 1, input.modifiers.shift))
+        });
+        match pressed {
+            Some((slot, true)) => self.save_slot(slot),
+            Some((slot, false)) => self.load_slot(slot),
+            None => (),
+        }
+    }
+
+    pub(super) fn savestate_controls(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.add(egui::Slider::new(&mut self.save_slot, 1..=9).text("Slot"));
+            if ui.button("Save State").on_hover_text("Shift+F1-F9").clicked() {
+                self.save_slot(self.save_slot);
+            }
+            if ui.button("Load State").on_hover_text("F1-F9").clicked() {
+                self.load_slot(self.save_slot);
+            }
+        });
+    }
+}
@@ -0,0 +1,110 @@
+use eframe::{egui::{self, RichText}, epaint::Color32};
+
+use rutile_gb_core::cheats::game_shark_code;
+use rutile_gb_core::debugger::parse_number;
+use rutile_gb_core::search::{Filter, RamSearch, Width};
+
+use super::MyApp;
+
+// Listing thousands of rows helps nobody, filter some more first
+const SHOWN_CANDIDATES: usize = 100;
+
+pub struct RamSearchView {
+    width: Width,
+    value: String,
+    search: Option<RamSearch>,
+    error: Option<String>,
+}
+
+impl RamSearchView {
+    pub fn new() -> Self {
+        Self { width: Width::Byte, value: String::new(), search: None, error: None }
+    }
+}
+
+impl MyApp {
+    pub(super) fn search_window(&mut self, ctx: &egui::Context) {
+        egui::Window::new("RAM Search").default_open(false).show(ctx, |ui| {
+            let Some(memory) = self.emulator.memory() else {
+                return;
+            };
+            let view = &mut self.search_view;
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut view.width, Width::Byte, "8-bit");
+                ui.selectable_value(&mut view.width, Width::Word, "16-bit");
+                if ui.button("New search").on_hover_text("Remembers every byte of cartridge RAM, WRAM and HRAM").clicked() {
+                    view.search = Some(RamSearch::start(memory, view.width));
+                    view.error = None;
+                }
+            });
+            let Some(search) = &mut view.search else {
+                ui.label("Start a search, then filter after the value you're after changed in the game.");
+                return;
+            };
+
+            ui.horizontal(|ui| {
+                let mut filter = None;
+                for (name, option) in [
+                    ("Equal", Filter::Equal),
+                    ("Changed", Filter::Changed),
+                    ("Increased", Filter::Increased),
+                    ("Decreased", Filter::Decreased),
+                ] {
+                    if ui.button(name).clicked() {
+                        filter = Some(option);
+                    }
+                }
+                ui.separator();
+                ui.add(egui::TextEdit::singleline(&mut view.value).hint_text("$1F or 31").desired_width(60.0));
+                if ui.button("Value").clicked() {
+                    match parse_number(view.value.trim()) {
+                        Some(value) if search.width == Width::Word || value <= 0xFF => filter = Some(Filter::Value(value)),
+                        _ => view.error = Some(format!("{} isn't an {} value", view.value, if search.width == Width::Byte { "8-bit" } else { "16-bit" })),
+                    }
+                }
+                if let Some(filter) = filter {
+                    search.filter(memory, filter);
+                    view.error = None;
+                }
+            });
+            if let Some(error) = &view.error {
+                ui.label(RichText::new(error).color(Color32::RED));
+            }
+
+            ui.label(format!("{} candidates", search.candidates.len()));
+            let mut freeze = None;
+            egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                egui::Grid::new("search_candidates").striped(true).show(ui, |ui| {
+                    for candidate in search.candidates.iter().take(SHOWN_CANDIDATES) {
+                        let current = search.width.read(memory, candidate.address);
+                        ui.monospace(format!("${:04X}", candidate.address));
+                        ui.monospace(format!("was {}", candidate.previous));
+                        ui.monospace(format!("now {} (${:X})", current, current));
+                        if ui.small_button("Freeze").on_hover_text("Adds a GameShark code that keeps it at the current value").clicked() {
+                            freeze = Some((candidate.address, current));
+                        }
+                        ui.end_row();
+                    }
+                });
+            });
+            if search.candidates.len() > SHOWN_CANDIDATES {
+                ui.label(format!("Showing the first {}", SHOWN_CANDIDATES));
+            }
+
+            if let Some((address, value)) = freeze {
+                let [low, high] = value.to_le_bytes();
+                let mut codes = vec![game_shark_code(address, low)];
+                if search.width == Width::Word {
+                    codes.push(game_shark_code(address.wrapping_add(1), high));
+                }
+                for code in codes {
+                    if let Err(err) = self.cheats.add(&code, &format!("Freeze ${:04X}", address)) {
+                        self.last_error = Some(err);
+                    }
+                }
+                self.cheats_changed();
+                self.status = Some(format!("Added a cheat for ${:04X}, see the Cheats window", address));
+            }
+        });
+    }
+}
@@ -0,0 +1,206 @@
+use eframe::{egui::{self, Rect, RichText, Stroke, TextureOptions}, epaint::Color32};
+
+use rutile_gb_core::ppu::{self, Sprite};
+
+use super::MyApp;
+
+const TILE_SCALE: f32 = 2.0;
+const MAP_SCALE: f32 = 1.5;
+const SPRITE_SCALE: f32 = 3.0;
+const VIEWPORT_COLOR: Color32 = Color32::RED;
+const WINDOW_COLOR: Color32 = Color32::LIGHT_BLUE;
+
+#[derive(PartialEq)]
+pub enum VramTab {
+    Tiles,
+    Maps,
+    Oam,
+}
+
+/// Which palette register the tile viewer colours tiles with
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TilePalette {
+    Bgp,
+    Obp0,
+    Obp1,
+    // 0, 1, 2, 3 map to themselves, shows the raw colour numbers
+    Identity,
+}
+
+pub struct VramViewer {
+    tab: VramTab,
+    palette: TilePalette,
+}
+
+impl VramViewer {
+    pub fn new() -> Self {
+        Self { tab: VramTab::Tiles, palette: TilePalette::Bgp }
+    }
+}
+
+// Shades as they come out of the ppu module, one byte per pixel
+fn shades_image(width: usize, height: usize, shades: &[u8]) -> egui::ColorImage {
+    let pixels = shades.iter().map(|shade| Color32::from_gray(ppu::GREY_SHADES[*shade as usize])).collect();
+    egui::ColorImage { size: [width, height], pixels }
+}
+
+fn show_image(ui: &mut egui::Ui, name: &str, image: egui::ColorImage, scale: f32) -> Rect {
+    let texture = ui.ctx().load_texture(name, image, TextureOptions::NEAREST);
+    ui.image(&texture, texture.size_vec2() * scale).rect
+}
+
+// Outlines a 256x256-wrapping rectangle on a map drawn at `rect`, split into up to four pieces at the edges
+fn outline_wrapped(ui: &egui::Ui, rect: Rect, x: usize, y: usize, width: usize, height: usize, color: Color32) {
+    let scale = rect.width() / ppu::MAP_SIZE as f32;
+    let stroke = Stroke::new(1.5, color);
+    for (left, columns) in split_wrapped(x, width) {
+        for (top, rows) in split_wrapped(y, height) {
+            let min = rect.min + egui::vec2(left as f32, top as f32) * scale;
+            let piece = Rect::from_min_size(min, egui::vec2(columns as f32, rows as f32) * scale);
+            ui.painter().rect_stroke(piece, 0.0, stroke);
+        }
+    }
+}
+
+fn split_wrapped(start: usize, length: usize) -> Vec<(usize, usize)> {
+    let first = length.min(ppu::MAP_SIZE - start);
+    let mut pieces = vec![(start, first)];
+    if first < length {
+        pieces.push((0, length - first));
+    }
+    pieces
+}
+
+impl MyApp {
+    pub(super) fn vram_window(&mut self, ctx: &egui::Context) {
+        // Collapsed windows skip the body, so nothing is rendered unless it's open
+        egui::Window::new("VRAM").default_open(false).show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                let viewer = &mut self.vram_viewer;
+                ui.selectable_value(&mut viewer.tab, VramTab::Tiles, "Tiles");
+                ui.selectable_value(&mut viewer.tab, VramTab::Maps, "Tile maps");
+                ui.selectable_value(&mut viewer.tab, VramTab::Oam, "OAM");
+            });
+            ui.separator();
+            match self.vram_viewer.tab {
+                VramTab::Tiles => self.tiles_tab(ui),
+                VramTab::Maps => self.maps_tab(ui),
+                VramTab::Oam => self.oam_tab(ui),
+            }
+        });
+    }
+
+    fn tiles_tab(&mut self, ui: &mut egui::Ui) {
+        let palette = &mut self.vram_viewer.palette;
+        egui::ComboBox::from_label("Palette")
+            .selected_text(format!("{:?}", palette))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(palette, TilePalette::Bgp, "Bgp");
+                ui.selectable_value(palette, TilePalette::Obp0, "Obp0");
+                ui.selectable_value(palette, TilePalette::Obp1, "Obp1");
+                ui.selectable_value(palette, TilePalette::Identity, "Identity");
+            });
+
+        let Some(memory) = self.emulator.memory() else {
+            return;
+        };
+        let register = match self.vram_viewer.palette {
+            TilePalette::Bgp => memory.memory[ppu::BGP],
+            TilePalette::Obp0 => memory.memory[ppu::OBP0],
+            TilePalette::Obp1 => memory.memory[ppu::OBP1],
+            TilePalette::Identity => 0b11_10_01_00,
+        };
+        // There's no CGB support, so this is the one DMG bank
+        ui.label("0x8000-0x97FF, bank 0");
+        let image = shades_image(ppu::TILES_WIDTH, ppu::TILES_HEIGHT, &ppu::render_tiles(memory, register));
+        let rect = show_image(ui, "vram-tiles", image, TILE_SCALE);
+        if let Some(pointer) = ui.ctx().pointer_hover_pos().filter(|pointer| rect.contains(*pointer)) {
+            let position = (pointer - rect.min) / (8.0 * TILE_SCALE);
+            let tile = position.y as usize * 16 + position.x as usize;
+            ui.label(format!("Tile {} at {:04X}", tile, 0x8000 + tile * 16));
+        }
+    }
+
+    fn maps_tab(&mut self, ui: &mut egui::Ui) {
+        let Some(memory) = self.emulator.memory() else {
+            return;
+        };
+        let mem = &memory.memory;
+        let lcdc = mem[ppu::LCDC];
+        let background_map = if lcdc & ppu::BG_MAP != 0 { 0x9C00 } else { 0x9800 };
+        let window_map = if lcdc & ppu::WINDOW_MAP != 0 { 0x9C00 } else { 0x9800 };
+        let window_enabled = lcdc & ppu::WINDOW_ENABLE != 0;
+
+        ui.horizontal(|ui| {
+            ui.label(RichText::new("Viewport (SCX/SCY)").color(VIEWPORT_COLOR));
+            ui.label(format!("{}, {}", mem[ppu::SCX], mem[ppu::SCY]));
+            ui.label(RichText::new("Window (WX/WY)").color(WINDOW_COLOR));
+            ui.label(format!("{}, {}{}", mem[ppu::WX], mem[ppu::WY], if window_enabled { "" } else { ", off" }));
+        });
+        ui.horizontal(|ui| {
+            for map in [0x9800, 0x9C00] {
+                ui.vertical(|ui| {
+                    let mut uses = Vec::new();
+                    if map == background_map {
+                        uses.push("background");
+                    }
+                    if map == window_map && window_enabled {
+                        uses.push("window");
+                    }
+                    ui.label(format!("{:04X} {}", map, uses.join(", ")));
+                    let image = shades_image(ppu::MAP_SIZE, ppu::MAP_SIZE, &ppu::render_tile_map(memory, map));
+                    let rect = show_image(ui, &format!("vram-map-{:04X}", map), image, MAP_SCALE);
+                    if map == background_map {
+                        let (x, y) = (mem[ppu::SCX] as usize, mem[ppu::SCY] as usize);
+                        outline_wrapped(ui, rect, x, y, ppu::SCREEN_WIDTH, ppu::SCREEN_HEIGHT, VIEWPORT_COLOR);
+                    }
+                    // The window always starts at the top left of its map, whatever part of the screen it covers
+                    let (wx, wy) = (mem[ppu::WX] as usize, mem[ppu::WY] as usize);
+                    if map == window_map && window_enabled && wx < ppu::SCREEN_WIDTH + 7 && wy < ppu::SCREEN_HEIGHT {
+                        let width = ppu::SCREEN_WIDTH + 7 - wx.max(7);
+                        outline_wrapped(ui, rect, 0, 0, width, ppu::SCREEN_HEIGHT - wy, WINDOW_COLOR);
+                    }
+                });
+            }
+        });
+    }
+
+    fn oam_tab(&mut self, ui: &mut egui::Ui) {
+        let Some(memory) = self.emulator.memory() else {
+            return;
+        };
+        let height = ppu::sprite_height(memory);
+        ui.label(format!("8x{} sprites, positions as stored (x - 8, y - 16 on screen)", height));
+        egui::ScrollArea::vertical().id_source("oam").max_height(400.0).show(ui, |ui| {
+            egui::Grid::new("oam_table").striped(true).show(ui, |ui| {
+                for heading in ["#", "X", "Y", "Tile", "Attributes", "Flags", ""] {
+                    ui.label(RichText::new(heading).strong());
+                }
+                ui.end_row();
+                for index in 0..ppu::SPRITE_COUNT {
+                    let sprite = Sprite::read(memory, index);
+                    ui.monospace(format!("{}", index));
+                    ui.monospace(format!("{}", sprite.x));
+                    ui.monospace(format!("{}", sprite.y));
+                    ui.monospace(format!("{:02X}", sprite.tile));
+                    ui.monospace(format!("{:02X}", sprite.attributes));
+                    let mut flags = vec![format!("OBP{}", sprite.palette())];
+                    if sprite.x_flip() {
+                        flags.push("X flip".to_string());
+                    }
+                    if sprite.y_flip() {
+                        flags.push("Y flip".to_string());
+                    }
+                    if sprite.behind_background() {
+                        flags.push("behind BG".to_string());
+                    }
+                    ui.label(flags.join(", "));
+                    let image = shades_image(8, height, &ppu::render_sprite(memory, &sprite));
+                    show_image(ui, &format!("oam-{}", index), image, SPRITE_SCALE);
+                    ui.end_row();
+                }
+            });
+        });
+    }
+}
+